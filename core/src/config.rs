@@ -24,15 +24,109 @@ pub struct DatabaseConfig {
 pub struct IngestConfig {
     pub source: IngestSourceConfig,
     pub start_from: Option<chrono::DateTime<Utc>>,
+    /// Fills per batch sent to the pipeline. Hour files are streamed and cut into
+    /// batches of this size, so it bounds memory together with `channel_buffer_size`.
     pub batch_size: usize,
+    /// Threads decoding JSON lines into fills, shared by every hour file in flight.
+    /// Defaults to the number of CPU cores.
+    pub parse_workers: Option<usize>,
     pub max_retries: u32,
     pub retry_base_delay_ms: u64,
+    /// Requester-pays transfer price in USD per GB, used for cost estimates
+    pub transfer_cost_per_gb: f64,
+    /// Stop a backfill before it downloads more than this many GB
+    pub max_download_gb: Option<f64>,
+    /// Stop a backfill before its estimated transfer cost exceeds this many USD
+    pub max_cost_usd: Option<f64>,
+    /// The `hyperliquid-archive` bucket with market data snapshots
+    pub archive: ArchiveConfig,
+    /// Live tail of fills from the Hyperliquid API
+    pub live: LiveConfig,
+}
+
+/// Market data published in the `hyperliquid-archive` bucket. Read with the endpoint,
+/// credentials, requester-pays and cache settings of `ingest.source`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ArchiveConfig {
+    pub s3_bucket: String,
+    /// Coins whose L2 book snapshots are loaded; every coin published for an hour if empty
+    #[serde(default)]
+    pub l2_book_coins: Vec<String>,
+    /// Coin files of one hour downloaded and loaded concurrently
+    pub l2_book_concurrency: usize,
+}
+
+/// Source `run` tails for fills newer than the published hour files
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LiveConfig {
+    /// `api` to poll the info API, `websocket` to stream trades; `run` only polls S3 if unset
+    pub source: Option<String>,
+    /// Info API endpoint
+    pub api_url: String,
+    /// WebSocket endpoint streaming the `trades` channel
+    pub ws_url: String,
+    /// Milliseconds of streamed trades collected into one batch
+    pub ws_flush_ms: u64,
+    /// Info requests sent per minute. `recentTrades` and `userFillsByTime` weigh 20
+    /// against Hyperliquid's limit of 1200 per minute and IP.
+    pub requests_per_minute: u32,
+    /// Seconds to wait after a poll of every coin and address before the next one
+    pub poll_interval_secs: u64,
+    /// Coins polled with `recentTrades` or subscribed to over the WebSocket; the perp
+    /// universe of the market registry if empty
+    #[serde(default)]
+    pub coins: Vec<String>,
+    /// Addresses whose fills are polled with `userFillsByTime`
+    #[serde(default)]
+    pub watched_addresses: Vec<String>,
+    /// Seconds before the last fill seen that each poll starts from, so fills
+    /// published late are not missed
+    pub overlap_secs: u64,
+    /// Seconds between checks for newly published hour files while tailing the live source
+    pub history_poll_secs: u64,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct IngestSourceConfig {
     pub s3_bucket: String,
+    pub s3_region: String,
+    /// Custom endpoint for S3-compatible stores such as MinIO or localstack
+    pub s3_endpoint_url: Option<String>,
+    /// Address buckets as `<endpoint>/<bucket>` instead of `<bucket>.<endpoint>`
+    pub s3_force_path_style: bool,
+    /// Send `x-amz-request-payer: requester`, required by the Hyperliquid buckets
+    pub s3_requester_pays: bool,
     pub aws_profile: Option<String>,
+    /// Static credentials, used instead of the default provider chain when set
+    pub aws_access_key_id: Option<String>,
+    pub aws_secret_access_key: Option<String>,
+    pub aws_session_token: Option<String>,
+    /// Read hour files from this local mirror of the bucket instead of S3
+    pub local_dir: Option<String>,
+    /// Keep downloaded S3 objects here and reuse them instead of downloading again
+    pub cache_dir: Option<String>,
+    /// Size limit of the object cache; least recently used objects are evicted beyond it
+    pub cache_max_gb: f64,
+    /// Only read objects and listings from the object cache, failing instead of downloading
+    pub offline: bool,
+    /// Override the built-in fill format epochs. Must cover every hour that is ingested.
+    #[serde(default)]
+    pub schema_epochs: Vec<SchemaEpochConfig>,
+    /// Rejected lines tolerated per hour file before the whole hour fails
+    pub max_rejects_per_hour: usize,
+}
+
+/// Key prefix and record format used by the bucket for a range of hours
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SchemaEpochConfig {
+    /// Parser name: `node_trades`, `node_fills` or `node_fills_by_block`
+    pub format: String,
+    /// Key prefix inside the bucket, e.g. `node_fills_by_block/hourly`
+    pub prefix: String,
+    /// First hour in this format, unbounded if unset
+    pub start: Option<chrono::DateTime<Utc>>,
+    /// First hour no longer in this format, unbounded if unset
+    pub end: Option<chrono::DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -100,6 +194,53 @@ impl Config {
             ));
         }
 
+        let source = &self.ingest.source;
+        if source.aws_access_key_id.is_some() != source.aws_secret_access_key.is_some() {
+            return Err(ConfigError::Message(
+                "ingest.source.aws_access_key_id and aws_secret_access_key must be set together".into(),
+            ));
+        }
+
+        if source.cache_max_gb <= 0.0 {
+            return Err(ConfigError::Message(
+                "ingest.source.cache_max_gb must be greater than 0".into(),
+            ));
+        }
+
+        if self.ingest.parse_workers == Some(0) {
+            return Err(ConfigError::Message(
+                "ingest.parse_workers must be greater than 0".into(),
+            ));
+        }
+
+        if self.ingest.transfer_cost_per_gb < 0.0
+            || self.ingest.max_download_gb.is_some_and(|gb| gb < 0.0)
+            || self.ingest.max_cost_usd.is_some_and(|usd| usd < 0.0)
+        {
+            return Err(ConfigError::Message(
+                "ingest.transfer_cost_per_gb, max_download_gb and max_cost_usd must not be negative".into(),
+            ));
+        }
+
+        if self.ingest.archive.l2_book_concurrency == 0 {
+            return Err(ConfigError::Message(
+                "ingest.archive.l2_book_concurrency must be greater than 0".into(),
+            ));
+        }
+
+        let live = &self.ingest.live;
+        if live.source.as_deref().is_some_and(|source| source != "api" && source != "websocket") {
+            return Err(ConfigError::Message(
+                "ingest.live.source must be \"api\", \"websocket\" or unset".into(),
+            ));
+        }
+
+        if live.requests_per_minute == 0 || live.ws_flush_ms == 0 || live.history_poll_secs == 0 {
+            return Err(ConfigError::Message(
+                "ingest.live.requests_per_minute, ws_flush_ms and history_poll_secs must be greater than 0".into(),
+            ));
+        }
+
         if self.pipeline.channel_buffer_size == 0 {
             return Err(ConfigError::Message(
                 "pipeline.channel_buffer_size must be greater than 0".into(),
@@ -123,12 +264,46 @@ impl Default for Config {
             ingest: IngestConfig {
                 source: IngestSourceConfig {
                     s3_bucket: "hl-mainnet-node-data".to_string(),
+                    s3_region: "ap-northeast-1".to_string(),
+                    s3_endpoint_url: None,
+                    s3_force_path_style: false,
+                    s3_requester_pays: true,
                     aws_profile: None,
+                    aws_access_key_id: None,
+                    aws_secret_access_key: None,
+                    aws_session_token: None,
+                    local_dir: None,
+                    cache_dir: None,
+                    cache_max_gb: 50.0,
+                    offline: false,
+                    schema_epochs: Vec::new(),
+                    max_rejects_per_hour: 100,
                 },
                 start_from: None, // Will be set to now() - 7 days in load()
                 batch_size: 1000,
+                parse_workers: None,
                 max_retries: 3,
                 retry_base_delay_ms: 1000,
+                transfer_cost_per_gb: 0.09,
+                max_download_gb: None,
+                max_cost_usd: None,
+                archive: ArchiveConfig {
+                    s3_bucket: "hyperliquid-archive".to_string(),
+                    l2_book_coins: Vec::new(),
+                    l2_book_concurrency: 4,
+                },
+                live: LiveConfig {
+                    source: None,
+                    api_url: "https://api.hyperliquid.xyz/info".to_string(),
+                    ws_url: "wss://api.hyperliquid.xyz/ws".to_string(),
+                    ws_flush_ms: 1000,
+                    requests_per_minute: 50,
+                    poll_interval_secs: 10,
+                    coins: Vec::new(),
+                    watched_addresses: Vec::new(),
+                    overlap_secs: 300,
+                    history_poll_secs: 60,
+                },
             },
             pipeline: PipelineConfig {
                 channel_buffer_size: 1000,
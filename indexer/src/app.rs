@@ -1,4 +1,6 @@
-use crate::ingest::S3Source;
+use crate::ingest::parse_pool::ParsePool;
+use crate::ingest::schema::SchemaRegistry;
+use crate::ingest::{ApiSource, CompositeSource, IngestSource, LocalSource, S3Source, WebSocketSource};
 use crate::pipeline::Pipeline;
 use crate::store::Store;
 use chrono::{DateTime, Utc};
@@ -7,10 +9,14 @@ use sqlx::PgPool;
 use std::sync::Arc;
 use tracing::{info, instrument};
 
+#[allow(dead_code)]
 pub struct App {
     config: Config,
     store: Arc<Store>,
     pipeline: Pipeline,
+    /// Pipeline `run` uses when a live source is configured: hour files for closed
+    /// hours, the live source for the current one
+    live: Option<Pipeline>,
 }
 
 impl App {
@@ -21,28 +27,55 @@ impl App {
         // Create store
         let store = Arc::new(Store::new(pool).await?);
 
-        // Create S3 ingest source
-        let source = S3Source::new(
-            config.ingest.source.s3_bucket.clone(),
-            config.ingest.source.aws_profile.clone(),
-        ).await?;
+        let schemas = Arc::new(SchemaRegistry::from_config(&config.ingest.source)?);
+        let parse_pool = ParsePool::new(config.ingest.parse_workers);
+        info!(workers = parse_pool.workers(), "Parse pool ready");
+
+        // Create ingest source: a local mirror if configured, S3 otherwise
+        let source: Arc<dyn IngestSource> = match &config.ingest.source.local_dir {
+            Some(local_dir) => {
+                info!(dir = %local_dir, "Using local directory as ingest source");
+                Arc::new(LocalSource::new(local_dir, schemas, parse_pool)?)
+            }
+            None => Arc::new(S3Source::new(&config.ingest.source, schemas, parse_pool).await?),
+        };
 
         // Health check
         info!("Performing health checks");
         store.health_check().await?;
         // Note: We skip source.health_check() for S3 to avoid unnecessary requests
+        if config.ingest.source.local_dir.is_some() {
+            source.health_check().await?;
+        }
 
         // Create pipeline
         let pipeline = Pipeline::new(
-            Arc::new(source),
+            Arc::clone(&source),
             Arc::clone(&store),
             config.clone(),
         );
 
+        let live_source: Option<Arc<dyn IngestSource>> = match config.ingest.live.source.as_deref() {
+            Some("api") => {
+                info!(url = %config.ingest.live.api_url, "Using info API as live source");
+                Some(Arc::new(ApiSource::new(&config.ingest.live, store.market_registry())?))
+            }
+            Some("websocket") => {
+                info!(url = %config.ingest.live.ws_url, "Using WebSocket trades as live source");
+                Some(Arc::new(WebSocketSource::new(&config.ingest.live, store.market_registry())))
+            }
+            _ => None,
+        };
+        let live = live_source.map(|live_source| {
+            let composite = CompositeSource::new(source, live_source, &config.ingest.live);
+            Pipeline::new(Arc::new(composite), Arc::clone(&store), config.clone())
+        });
+
         Ok(Self {
             config,
             store,
             pipeline,
+            live,
         })
     }
 
@@ -50,7 +83,11 @@ impl App {
         self.pipeline.run_backfill(end_at).await
     }
 
+    pub async fn plan_backfill(&self, end_at: Option<DateTime<Utc>>) -> Result<()> {
+        self.pipeline.plan_backfill(end_at).await
+    }
+
     pub async fn run_continuous(&self) -> Result<()> {
-        self.pipeline.run_continuous().await
+        self.live.as_ref().unwrap_or(&self.pipeline).run_continuous().await
     }
 }
\ No newline at end of file
@@ -0,0 +1,457 @@
+use super::cursor::{Cursor, SourceKind};
+use super::schema::{parse_api_fill, parse_api_trade, ApiTrade};
+use super::IngestSource;
+use crate::market::MarketRegistry;
+use crate::model::{Fill, IngestBatch};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
+use indexer_core::config::LiveConfig;
+use indexer_core::{Error, Result};
+use metrics::{counter, histogram};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, instrument, warn};
+
+/// `source_id` of fills built from `recentTrades`
+pub const RECENT_TRADES_SOURCE: &str = "api:recentTrades";
+
+/// `source_id` of fills from `userFillsByTime`
+pub const USER_FILLS_SOURCE: &str = "api:userFillsByTime";
+
+/// Timeout of one info request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Wait before retrying when the API answers 429 without a `Retry-After`
+const DEFAULT_RETRY_AFTER_SECS: u64 = 60;
+
+/// Polls the public info API for fills newer than the published hour files:
+/// `recentTrades` for every coin, giving both sides of each trade, and
+/// `userFillsByTime` for watched addresses, giving their fills with fees and PnL.
+/// Requests are paced by a rate limiter shared by the whole poll. The fills are
+/// provisional and replaced when the hour file with the same trades is loaded.
+/// `recentTrades` only returns the latest trades of a coin, so a coin polled too
+/// rarely loses trades; those gaps are counted, and a warning names the rate needed.
+pub struct ApiSource {
+    client: reqwest::Client,
+    url: String,
+    limiter: DefaultDirectRateLimiter,
+    requests_per_minute: u32,
+    markets: Arc<MarketRegistry>,
+    coins: Vec<String>,
+    watched_addresses: Vec<String>,
+    poll_interval: Duration,
+    overlap: chrono::Duration,
+    /// Newest trade time seen per coin, to tell when a response missed trades
+    newest_trades: Mutex<HashMap<String, i64>>,
+    /// Requests per poll last warned about as more than the rate limit covers
+    warned_requests: AtomicUsize,
+}
+
+impl ApiSource {
+    /// Source polling `config.api_url`, taking coins from `markets` unless `config.coins` lists them
+    pub fn new(config: &LiveConfig, markets: Arc<MarketRegistry>) -> Result<Self> {
+        let requests_per_minute = NonZeroU32::new(config.requests_per_minute)
+            .ok_or_else(|| Error::Config("ingest.live.requests_per_minute must be greater than 0".to_string()))?;
+
+        let client = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+
+        Ok(Self {
+            client,
+            url: config.api_url.clone(),
+            limiter: RateLimiter::direct(Quota::per_minute(requests_per_minute)),
+            requests_per_minute: requests_per_minute.get(),
+            markets,
+            coins: config.coins.clone(),
+            watched_addresses: config.watched_addresses.iter().map(|address| address.to_lowercase()).collect(),
+            poll_interval: Duration::from_secs(config.poll_interval_secs),
+            overlap: chrono::Duration::seconds(config.overlap_secs as i64),
+            newest_trades: Mutex::new(HashMap::new()),
+            warned_requests: AtomicUsize::new(0),
+        })
+    }
+
+    /// Warn once per poll size when a poll of `requests` takes longer than
+    /// `poll_interval` at the rate limit, so every coin is polled less often than configured
+    fn check_poll_rate(&self, requests: usize) {
+        let covered = self.requests_per_minute as u64 * self.poll_interval.as_secs() / 60;
+        if requests as u64 <= covered || self.warned_requests.swap(requests, Ordering::Relaxed) == requests {
+            return;
+        }
+
+        let poll_secs = self.poll_interval.as_secs() + requests as u64 * 60 / self.requests_per_minute as u64;
+        warn!(
+            requests,
+            covered,
+            poll_secs,
+            "⚠️ A live poll takes longer than poll_interval_secs at requests_per_minute, so active coins \
+             lose trades between polls; list the coins to tail in ingest.live.coins or raise the rate"
+        );
+    }
+
+    /// Count a gap when none of `trades` of `coin` is at or before the newest trade
+    /// of the previous poll, or `since` on the first one: trades between them were
+    /// pushed out of `recentTrades` before this poll
+    fn record_window(&self, coin: &str, trades: &[ApiTrade], since: DateTime<Utc>) {
+        let (Some(oldest), Some(newest)) = (
+            trades.iter().map(|trade| trade.time).min(),
+            trades.iter().map(|trade| trade.time).max(),
+        ) else {
+            return;
+        };
+
+        let mut newest_trades = self.newest_trades.lock().unwrap_or_else(|e| e.into_inner());
+        let covered_to = newest_trades.get(coin).copied().unwrap_or_else(|| since.timestamp_millis());
+        if oldest > covered_to {
+            debug!(coin = %coin, missed_ms = oldest - covered_to, "recentTrades window missed trades");
+            counter!("indexer_api_trade_gaps").increment(1);
+            histogram!("indexer_api_trade_gap_ms").record((oldest - covered_to) as f64);
+        }
+        newest_trades.insert(coin.to_string(), newest.max(covered_to));
+    }
+
+    /// Send one info request once the rate limiter allows it
+    async fn info<T: DeserializeOwned>(&self, request: Value) -> Result<T> {
+        self.limiter.until_ready().await;
+
+        let response = self.client.post(&self.url).json(&request).send().await?;
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after_secs = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(DEFAULT_RETRY_AFTER_SECS);
+            counter!("indexer_api_rate_limited").increment(1);
+            return Err(Error::RateLimit { retry_after_secs });
+        }
+
+        counter!("indexer_api_requests").increment(1);
+        Ok(response.error_for_status()?.json().await?)
+    }
+
+    async fn coins(&self) -> Vec<String> {
+        if self.coins.is_empty() {
+            self.markets.perp_coins().await
+        } else {
+            self.coins.clone()
+        }
+    }
+
+    /// Both sides of the recent trades of `coin` at or after `since`
+    async fn recent_trades(&self, coin: &str, since: DateTime<Utc>) -> Result<Vec<Fill>> {
+        let trades: Vec<ApiTrade> = self.info(json!({ "type": "recentTrades", "coin": coin })).await?;
+        self.record_window(coin, &trades, since);
+
+        let mut fills = Vec::with_capacity(trades.len() * 2);
+        for trade in trades.iter().filter(|trade| trade.time >= since.timestamp_millis()) {
+            match parse_api_trade(trade) {
+                Ok(trade_fills) => fills.extend(trade_fills),
+                Err(e) => {
+                    warn!(coin = %coin, error = %e, "Skipping unparseable API trade");
+                    counter!("indexer_api_rejected_fills").increment(1);
+                }
+            }
+        }
+
+        Ok(fills)
+    }
+
+    /// Fills of `user` at or after `since`, dropping any older ones the API still returns
+    async fn user_fills(&self, user: &str, since: DateTime<Utc>) -> Result<Vec<Fill>> {
+        let raw: Vec<Value> = self
+            .info(json!({
+                "type": "userFillsByTime",
+                "user": user,
+                "startTime": since.timestamp_millis(),
+                "aggregateByTime": false,
+            }))
+            .await?;
+
+        let mut fills = Vec::with_capacity(raw.len());
+        for fill in raw {
+            match parse_api_fill(user, fill) {
+                Ok(fill) if fill.timestamp >= since => fills.push(fill),
+                Ok(_) => {}
+                Err(e) => {
+                    warn!(user = %user, error = %e, "Skipping unparseable API fill");
+                    counter!("indexer_api_rejected_fills").increment(1);
+                }
+            }
+        }
+
+        Ok(fills)
+    }
+}
+
+#[async_trait]
+impl IngestSource for ApiSource {
+    /// Poll every watched address and coin once. User fills come first so they win
+    /// over the `recentTrades` copy of the same trade side in the same batch. A coin
+    /// or address that fails is skipped until the next poll, except on rate limiting,
+    /// which fails the page so the pipeline backs off.
+    #[instrument(skip(self))]
+    async fn fetch_page(
+        &self,
+        start_from: DateTime<Utc>,
+        _cursor: Option<Cursor>,
+    ) -> Result<IngestBatch> {
+        let since = start_from - self.overlap;
+        let mut fills = Vec::new();
+
+        for user in &self.watched_addresses {
+            match self.user_fills(user, since).await {
+                Ok(user_fills) => fills.extend(user_fills.into_iter().map(|fill| Fill {
+                    source_id: Some(USER_FILLS_SOURCE.to_string()),
+                    ..fill
+                })),
+                Err(e @ Error::RateLimit { .. }) => return Err(e),
+                Err(e) => warn!(user = %user, error = %e, "Failed to poll user fills"),
+            }
+        }
+
+        let coins = self.coins().await;
+        self.check_poll_rate(self.watched_addresses.len() + coins.len());
+        for coin in &coins {
+            match self.recent_trades(coin, since).await {
+                Ok(trade_fills) => fills.extend(trade_fills.into_iter().map(|fill| Fill {
+                    source_id: Some(RECENT_TRADES_SOURCE.to_string()),
+                    ..fill
+                })),
+                Err(e @ Error::RateLimit { .. }) => return Err(e),
+                Err(e) => warn!(coin = %coin, error = %e, "Failed to poll recent trades"),
+            }
+        }
+
+        let last_timestamp = fills.iter().map(|fill| fill.timestamp).max();
+        debug!(
+            coins = coins.len(),
+            users = self.watched_addresses.len(),
+            fills = fills.len(),
+            "Polled info API"
+        );
+
+        Ok(IngestBatch {
+            fills,
+            cursor: Some(self.hour_cursor(last_timestamp.unwrap_or(start_from))),
+            has_more: false,
+            bytes_downloaded: None,
+            hours: Vec::new(),
+            rejects: Vec::new(),
+            blocks: Vec::new(),
+        })
+    }
+
+    fn hour_cursor(&self, hour: DateTime<Utc>) -> Cursor {
+        Cursor::hourly(SourceKind::Api, None, hour)
+    }
+
+    fn serves_history(&self) -> bool {
+        false
+    }
+
+    fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    fn source_id(&self) -> &str {
+        "api"
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        let _: Value = self.info(json!({ "type": "meta" })).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TradeSide;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const USER: &str = "0x1111111111111111111111111111111111111111";
+    const OTHER: &str = "0x2222222222222222222222222222222222222222";
+
+    /// Status, extra headers and JSON body of a stub answer
+    type Reply = (u16, Vec<(&'static str, &'static str)>, Value);
+
+    /// Info API stub on a local port, answering each request with `reply` for its body.
+    /// Returns its URL and the requests it received.
+    async fn stub_api(reply: fn(&Value) -> Reply) -> (String, Arc<Mutex<Vec<Value>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/info", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let received = Arc::clone(&requests);
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buffer = Vec::new();
+                let mut chunk = [0u8; 4096];
+                let body = loop {
+                    let read = socket.read(&mut chunk).await.unwrap();
+                    buffer.extend_from_slice(&chunk[..read]);
+                    let text = String::from_utf8_lossy(&buffer);
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length = text[..end]
+                            .lines()
+                            .filter_map(|line| line.split_once(':'))
+                            .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                            .and_then(|(_, length)| length.trim().parse::<usize>().ok())
+                            .unwrap_or(0);
+                        if buffer.len() >= end + 4 + length {
+                            break serde_json::from_slice::<Value>(&buffer[end + 4..end + 4 + length]).unwrap();
+                        }
+                    }
+                };
+
+                let (status, headers, json) = reply(&body);
+                received.lock().unwrap().push(body);
+                let json = json.to_string();
+                let mut response = format!(
+                    "HTTP/1.1 {} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+                    status,
+                    json.len()
+                );
+                for (name, value) in headers {
+                    response.push_str(&format!("{}: {}\r\n", name, value));
+                }
+                response.push_str("\r\n");
+                response.push_str(&json);
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        (url, requests)
+    }
+
+    fn source(url: String, coins: &[&str], watched: &[&str]) -> ApiSource {
+        let config = LiveConfig {
+            api_url: url,
+            requests_per_minute: 6000,
+            coins: coins.iter().map(|coin| coin.to_string()).collect(),
+            watched_addresses: watched.iter().map(|address| address.to_string()).collect(),
+            ..indexer_core::Config::default().ingest.live
+        };
+        ApiSource::new(&config, Arc::new(MarketRegistry::empty())).unwrap()
+    }
+
+    fn at(millis: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(millis).unwrap()
+    }
+
+    fn trade(time: i64, tid: i64) -> Value {
+        json!({
+            "coin": "BTC", "side": "B", "px": "100000", "sz": "0.1", "time": time,
+            "hash": "0xabc", "tid": tid, "users": [USER, OTHER],
+        })
+    }
+
+    #[tokio::test]
+    async fn rate_limited_request_reports_retry_after() {
+        let (url, _) = stub_api(|request| match request["type"].as_str() {
+            Some("meta") => (429, vec![("Retry-After", "7")], json!(null)),
+            _ => (429, vec![], json!(null)),
+        })
+        .await;
+        let source = source(url, &["BTC"], &[]);
+
+        let with_header = source.info::<Value>(json!({ "type": "meta" })).await;
+        assert!(matches!(with_header, Err(Error::RateLimit { retry_after_secs: 7 })));
+
+        let without_header = source.info::<Value>(json!({ "type": "spotMeta" })).await;
+        assert!(matches!(
+            without_header,
+            Err(Error::RateLimit { retry_after_secs }) if retry_after_secs == DEFAULT_RETRY_AFTER_SECS
+        ));
+    }
+
+    #[tokio::test]
+    async fn rate_limited_poll_fails_the_page() {
+        let (url, _) = stub_api(|_| (429, vec![("Retry-After", "3")], json!(null))).await;
+        let source = source(url, &["BTC"], &[]);
+
+        let page = source.fetch_page(at(1_754_000_000_000), None).await;
+        assert!(matches!(page, Err(Error::RateLimit { retry_after_secs: 3 })));
+    }
+
+    #[tokio::test]
+    async fn recent_trades_keeps_trades_since() {
+        let (url, requests) = stub_api(|_| {
+            (200, vec![], json!([trade(1_754_000_002_000, 3), trade(1_754_000_000_000, 2), trade(1_753_999_999_999, 1)]))
+        })
+        .await;
+        let source = source(url, &["BTC"], &[]);
+
+        let fills = source.recent_trades("BTC", at(1_754_000_000_000)).await.unwrap();
+
+        assert_eq!(fills.len(), 4);
+        assert!(fills.iter().all(|fill| fill.timestamp >= at(1_754_000_000_000)));
+        assert_eq!(fills.iter().filter(|fill| fill.side == TradeSide::Buy).count(), 2);
+        assert_eq!(requests.lock().unwrap()[0], json!({ "type": "recentTrades", "coin": "BTC" }));
+    }
+
+    #[tokio::test]
+    async fn user_fills_keeps_fills_since() {
+        let (url, requests) = stub_api(|_| {
+            (200, vec![], json!([
+                {
+                    "coin": "BTC", "px": "100000", "sz": "0.1", "side": "B", "time": 1_753_999_999_000i64,
+                    "fee": "0.5", "closedPnl": "0", "tid": 1, "oid": 8, "crossed": true,
+                },
+                {
+                    "coin": "BTC", "px": "100000", "sz": "0.1", "side": "B", "time": 1_754_000_001_000i64,
+                    "fee": "0.5", "closedPnl": "0", "tid": 2, "oid": 9, "crossed": true,
+                },
+            ]))
+        })
+        .await;
+        let source = source(url, &[], &[USER]);
+
+        let fills = source.user_fills(USER, at(1_754_000_000_000)).await.unwrap();
+
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].user_address, USER);
+        assert_eq!(fills[0].tid, Some(2));
+        let request = &requests.lock().unwrap()[0];
+        assert_eq!(request["type"], "userFillsByTime");
+        assert_eq!(request["startTime"], 1_754_000_000_000i64);
+    }
+
+    /// Both copies of the user's side reach the store, which keeps the user's own fill
+    /// with its fee over the copy built from the trade
+    #[tokio::test]
+    async fn user_fills_come_before_recent_trades_copy() {
+        let (url, _) = stub_api(|request| match request["type"].as_str() {
+            Some("userFillsByTime") => (200, vec![], json!([{
+                "coin": "BTC", "px": "100000", "sz": "0.1", "side": "B", "time": 1_754_000_001_000i64,
+                "fee": "0.5", "closedPnl": "0", "tid": 1, "oid": 9, "crossed": true,
+            }])),
+            _ => (200, vec![], json!([trade(1_754_000_001_000, 1)])),
+        })
+        .await;
+        let source = source(url, &["BTC"], &[USER]);
+
+        let page = source.fetch_page(at(1_754_000_300_000), None).await.unwrap();
+
+        let user_buys: Vec<&Fill> = page
+            .fills
+            .iter()
+            .filter(|fill| fill.user_address == USER && fill.side == TradeSide::Buy && fill.tid == Some(1))
+            .collect();
+        assert_eq!(user_buys.len(), 2);
+        assert_eq!(user_buys[0].source_id.as_deref(), Some(USER_FILLS_SOURCE));
+        assert!(user_buys[0].fee.is_some());
+        assert_eq!(user_buys[1].source_id.as_deref(), Some(RECENT_TRADES_SOURCE));
+        assert!(page.fills.iter().all(Fill::is_provisional));
+        assert!(!page.has_more);
+    }
+}
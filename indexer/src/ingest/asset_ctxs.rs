@@ -0,0 +1,304 @@
+use super::budget::{format_bytes, DownloadBudget};
+use super::cursor::{Cursor, SourceKind};
+use super::hourly::HourlyObjectStore;
+use super::{open_archive, LoaderCheckpoint};
+use super::schema::{parse_decimal, parse_node_time};
+use crate::model::AssetCtx;
+use crate::store::Store;
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use indexer_core::{Config, Error, Result};
+use metrics::counter;
+use rust_decimal::Decimal;
+use std::io::Read;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+/// Checkpoint source of the asset context loader
+pub const SOURCE_ID: &str = "asset_ctxs";
+
+/// Directory of the daily `YYYYMMDD.csv.lz4` files in the archive bucket
+const PREFIX: &str = "asset_ctxs/";
+
+/// Rows written per insert
+const INSERT_CHUNK_ROWS: usize = 5_000;
+
+/// Loads the daily `asset_ctxs/<date>.csv.lz4` files of the archive bucket into
+/// `asset_ctxs`, one day at a time, checkpointing after every day under its own source id
+pub struct AssetCtxLoader {
+    objects: Arc<dyn HourlyObjectStore>,
+    store: Arc<Store>,
+    budget: DownloadBudget,
+}
+
+/// Days and rows one run loaded, and what it downloaded
+#[derive(Debug, Default)]
+pub struct AssetCtxSummary {
+    pub days: usize,
+    pub rows: usize,
+    pub rows_inserted: usize,
+    pub rejected_rows: usize,
+    pub missing_days: usize,
+    pub bytes: u64,
+}
+
+/// Rows parsed from one daily file
+struct ParsedCtxs {
+    rows: Vec<AssetCtx>,
+    rejected: usize,
+    first_reject: Option<String>,
+}
+
+impl AssetCtxLoader {
+    /// Loader over `asset_ctxs/` of the bucket [`open_archive`] opens
+    pub async fn from_config(config: &Config, store: Arc<Store>, dir: Option<String>) -> Result<Self> {
+        let (objects, billed) = open_archive(config, dir).await?;
+
+        Ok(Self {
+            objects,
+            store,
+            budget: DownloadBudget::new(&config.ingest, billed),
+        })
+    }
+
+    /// Load every day from the one containing `start_from` up to `end_at`, resuming
+    /// after the last day checkpointed inside that range. Stops at the first day
+    /// not published yet, so it is loaded by a later run.
+    pub async fn run(&self, start_from: DateTime<Utc>, end_at: DateTime<Utc>) -> Result<AssetCtxSummary> {
+        let mut checkpoint = LoaderCheckpoint::load(Arc::clone(&self.store), SOURCE_ID).await?;
+
+        let mut day = start_of_day(start_from);
+        if let Some(cursor) = checkpoint.cursor().filter(|cursor| cursor.hour > day && cursor.hour < end_at) {
+            info!(
+                "📍 Resuming asset contexts from checkpoint at {}",
+                cursor.hour.format("%Y-%m-%d")
+            );
+            day = cursor.hour;
+        }
+
+        info!(start = %day, end = %end_at, "📈 Loading asset contexts");
+
+        let listing = self.objects.list_objects(PREFIX).await?;
+        let last_published = listing.keys().filter_map(|key| key_date(key)).max();
+
+        let mut summary = AssetCtxSummary::default();
+
+        while day < end_at {
+            let key = day_key(day);
+            let inserted = match listing.get(&key) {
+                Some(size) => self.load_day(&key, *size, &mut summary).await?,
+                None if last_published.is_some_and(|last| last > day.date_naive()) => {
+                    warn!(key = %key, "Asset contexts missing upstream, later days are published");
+                    summary.missing_days += 1;
+                    0
+                }
+                None => {
+                    info!(
+                        "⏸️ Asset contexts for {} not published yet, stopping",
+                        day.format("%Y-%m-%d")
+                    );
+                    break;
+                }
+            };
+
+            let next = day + Duration::days(1);
+            let cursor = Cursor::hourly(SourceKind::AssetCtxs, Some("csv".to_string()), next);
+            checkpoint
+                .save(&self.budget, Some(cursor), Some(next), inserted, summary.bytes)
+                .await?;
+
+            summary.days += 1;
+            day = next;
+        }
+
+        info!(
+            "✨ Asset contexts loaded: {} days, {} rows, {} inserted | Missing upstream: {} | Downloaded: {} (~${:.2}) | Rejected rows: {}",
+            summary.days,
+            summary.rows,
+            summary.rows_inserted,
+            summary.missing_days,
+            format_bytes(summary.bytes),
+            self.budget.cost_usd(summary.bytes),
+            summary.rejected_rows
+        );
+
+        Ok(summary)
+    }
+
+    /// Download, parse and insert one daily file, returning the rows inserted
+    async fn load_day(&self, key: &str, size: u64, summary: &mut AssetCtxSummary) -> Result<usize> {
+        self.budget.check(summary.bytes + size)?;
+
+        let Some(object) = self.objects.open_object(key).await? else {
+            warn!(key = %key, "Asset context file disappeared after it was listed");
+            return Ok(0);
+        };
+        if !object.cached {
+            summary.bytes += size;
+        }
+
+        let store_name = self.objects.store_name().to_string();
+        let parsed = {
+            let key = key.to_string();
+            tokio::task::spawn_blocking(move || decode_ctxs(object.reader, &key, &store_name))
+                .await
+                .map_err(|e| Error::Internal(format!("Asset context decoder panicked: {}", e)))??
+        };
+
+        if let Some(reason) = &parsed.first_reject {
+            warn!(key = %key, rejected = parsed.rejected, first = %reason, "Rejected rows in asset context file");
+        }
+        counter!("indexer_asset_ctx_rejected_rows").increment(parsed.rejected as u64);
+
+        let mut inserted = 0;
+        for chunk in parsed.rows.chunks(INSERT_CHUNK_ROWS) {
+            inserted += self.store.insert_asset_ctxs(chunk).await?;
+        }
+
+        summary.rows += parsed.rows.len();
+        summary.rows_inserted += inserted;
+        summary.rejected_rows += parsed.rejected;
+
+        info!(
+            "📈 {} | {} rows, {} inserted | Downloaded so far: {}",
+            key,
+            parsed.rows.len(),
+            inserted,
+            format_bytes(summary.bytes)
+        );
+
+        Ok(inserted)
+    }
+}
+
+/// `asset_ctxs/YYYYMMDD.csv.lz4`
+fn day_key(day: DateTime<Utc>) -> String {
+    format!("{}{}.csv.lz4", PREFIX, day.format("%Y%m%d"))
+}
+
+/// Date of a daily file from its key
+fn key_date(key: &str) -> Option<NaiveDate> {
+    let date = key.strip_prefix(PREFIX)?.strip_suffix(".csv.lz4")?;
+    NaiveDate::parse_from_str(date, "%Y%m%d").ok()
+}
+
+fn start_of_day(date: DateTime<Utc>) -> DateTime<Utc> {
+    date.date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc()
+}
+
+/// Column positions in a file's header row. Only `time` and `coin` are required;
+/// value columns a file doesn't have are stored as NULL.
+struct Columns {
+    time: usize,
+    coin: usize,
+    funding: Option<usize>,
+    open_interest: Option<usize>,
+    prev_day_px: Option<usize>,
+    day_ntl_vlm: Option<usize>,
+    premium: Option<usize>,
+    oracle_px: Option<usize>,
+    mark_px: Option<usize>,
+    mid_px: Option<usize>,
+    impact_bid_px: Option<usize>,
+    impact_ask_px: Option<usize>,
+}
+
+impl Columns {
+    fn from_headers(headers: &csv::StringRecord) -> Result<Self> {
+        let find = |name: &str| headers.iter().position(|header| header.trim() == name);
+        let require = |name: &str| {
+            find(name).ok_or_else(|| Error::Validation(format!("Asset context file has no '{}' column", name)))
+        };
+
+        Ok(Self {
+            time: require("time")?,
+            coin: require("coin")?,
+            funding: find("funding"),
+            open_interest: find("open_interest"),
+            prev_day_px: find("prev_day_px"),
+            day_ntl_vlm: find("day_ntl_vlm"),
+            premium: find("premium"),
+            oracle_px: find("oracle_px"),
+            mark_px: find("mark_px"),
+            mid_px: find("mid_px"),
+            impact_bid_px: find("impact_bid_px"),
+            impact_ask_px: find("impact_ask_px"),
+        })
+    }
+
+    fn parse(&self, record: &csv::StringRecord) -> Result<AssetCtx> {
+        let field = |index: usize| record.get(index).map(str::trim).unwrap_or("");
+        let decimal = |name: &str, index: Option<usize>| -> Result<Option<Decimal>> {
+            match index.map(field) {
+                None | Some("") => Ok(None),
+                Some(value) => parse_decimal(name, value).map(Some),
+            }
+        };
+
+        let time = field(self.time);
+        let time = match time.parse::<i64>() {
+            Ok(ms) => Utc.timestamp_millis_opt(ms).single(),
+            Err(_) => parse_node_time(time),
+        }
+        .ok_or_else(|| Error::Validation(format!("Invalid time: {}", time)))?;
+
+        let coin = field(self.coin);
+        if coin.is_empty() {
+            return Err(Error::Validation("Missing coin".to_string()));
+        }
+
+        Ok(AssetCtx {
+            coin: coin.to_string(),
+            time,
+            funding: decimal("funding", self.funding)?,
+            open_interest: decimal("open_interest", self.open_interest)?,
+            prev_day_px: decimal("prev_day_px", self.prev_day_px)?,
+            day_ntl_vlm: decimal("day_ntl_vlm", self.day_ntl_vlm)?,
+            premium: decimal("premium", self.premium)?,
+            oracle_px: decimal("oracle_px", self.oracle_px)?,
+            mark_px: decimal("mark_px", self.mark_px)?,
+            mid_px: decimal("mid_px", self.mid_px)?,
+            impact_bid_px: decimal("impact_bid_px", self.impact_bid_px)?,
+            impact_ask_px: decimal("impact_ask_px", self.impact_ask_px)?,
+        })
+    }
+}
+
+/// Decompress a daily CSV file and parse its rows. Malformed rows are counted and skipped.
+fn decode_ctxs(reader: Box<dyn Read + Send>, key: &str, store_name: &str) -> Result<ParsedCtxs> {
+    let mut csv = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(lz4_flex::frame::FrameDecoder::new(reader));
+
+    let read_error = |e: csv::Error| Error::Ingest {
+        source_name: store_name.to_string(),
+        details: format!("Failed to read '{}': {}", key, e),
+    };
+
+    let columns = Columns::from_headers(csv.headers().map_err(read_error)?)?;
+    let mut parsed = ParsedCtxs {
+        rows: Vec::new(),
+        rejected: 0,
+        first_reject: None,
+    };
+
+    for (index, record) in csv.records().enumerate() {
+        let result = match record {
+            Ok(record) => columns.parse(&record),
+            // A stream that fails to decompress can't be skipped row by row
+            Err(e) if e.is_io_error() => return Err(read_error(e)),
+            Err(e) => Err(Error::Validation(e.to_string())),
+        };
+
+        match result {
+            Ok(row) => parsed.rows.push(row),
+            Err(e) => {
+                parsed.rejected += 1;
+                // Row numbers count the header as row 1
+                parsed.first_reject.get_or_insert_with(|| format!("row {}: {}", index + 2, e));
+            }
+        }
+    }
+
+    debug!(key = %key, rows = parsed.rows.len(), rejected = parsed.rejected, "Parsed asset context file");
+    Ok(parsed)
+}
@@ -0,0 +1,122 @@
+use indexer_core::config::IngestConfig;
+use indexer_core::{Error, Result};
+use serde_json::json;
+
+/// Bytes in the GB that S3 transfer is priced in
+pub const BYTES_PER_GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Key of the download accounting in checkpoint metadata
+const METADATA_KEY: &str = "download_budget";
+
+/// Caps on what one backfill may download, with the requester-pays price used to
+/// turn bytes into an estimated cost
+#[derive(Debug, Clone)]
+pub struct DownloadBudget {
+    max_bytes: Option<u64>,
+    max_cost_usd: Option<f64>,
+    cost_per_gb: f64,
+    /// Whether the source charges us for transfer; local files and most
+    /// S3-compatible stores don't
+    billed: bool,
+}
+
+impl DownloadBudget {
+    pub fn new(config: &IngestConfig, billed: bool) -> Self {
+        Self {
+            max_bytes: config.max_download_gb.map(|gb| (gb * BYTES_PER_GB) as u64),
+            max_cost_usd: config.max_cost_usd,
+            cost_per_gb: config.transfer_cost_per_gb,
+            billed,
+        }
+    }
+
+    /// Estimated transfer cost of `bytes`
+    pub fn cost_usd(&self, bytes: u64) -> f64 {
+        if self.billed {
+            bytes as f64 / BYTES_PER_GB * self.cost_per_gb
+        } else {
+            0.0
+        }
+    }
+
+    /// Fail if downloading `bytes` in total breaks a cap
+    pub fn check(&self, bytes: u64) -> Result<()> {
+        if let Some(max_bytes) = self.max_bytes.filter(|max| bytes > *max) {
+            return Err(Error::Validation(format!(
+                "download budget exceeded: {} over max_download_gb {}",
+                format_bytes(bytes),
+                format_bytes(max_bytes)
+            )));
+        }
+
+        let cost = self.cost_usd(bytes);
+        if let Some(max_cost) = self.max_cost_usd.filter(|max| cost > *max) {
+            return Err(Error::Validation(format!(
+                "download budget exceeded: estimated ${:.2} over max_cost_usd ${:.2}",
+                cost, max_cost
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Record this run's download in checkpoint metadata, keeping totals of earlier
+    /// runs and any other metadata keys
+    pub fn record(
+        &self,
+        metadata: Option<&serde_json::Value>,
+        previous: &serde_json::Value,
+        run_bytes: u64,
+        exhausted: bool,
+    ) -> serde_json::Value {
+        let previous_bytes = previous.get("total_bytes").and_then(|v| v.as_u64()).unwrap_or(0);
+        let previous_cost = previous.get("total_cost_usd").and_then(|v| v.as_f64()).unwrap_or(0.0);
+
+        let mut metadata = metadata
+            .filter(|metadata| metadata.is_object())
+            .cloned()
+            .unwrap_or_else(|| json!({}));
+        metadata[METADATA_KEY] = json!({
+            "max_download_gb": self.max_bytes.map(|bytes| bytes as f64 / BYTES_PER_GB),
+            "max_cost_usd": self.max_cost_usd,
+            "run_bytes": run_bytes,
+            "run_cost_usd": self.cost_usd(run_bytes),
+            "total_bytes": previous_bytes + run_bytes,
+            "total_cost_usd": previous_cost + self.cost_usd(run_bytes),
+            "exhausted": exhausted,
+        });
+
+        metadata
+    }
+
+    /// Download accounting of earlier runs, as stored by `record`
+    pub fn previous(metadata: Option<&serde_json::Value>) -> serde_json::Value {
+        metadata
+            .and_then(|metadata| metadata.get(METADATA_KEY))
+            .cloned()
+            .unwrap_or_else(|| json!({}))
+    }
+}
+
+/// Human-readable size, in MB below a GB
+pub fn format_bytes(bytes: u64) -> String {
+    let gb = bytes as f64 / BYTES_PER_GB;
+    if gb >= 1.0 {
+        format!("{:.2} GB", gb)
+    } else {
+        format!("{:.1} MB", gb * 1024.0)
+    }
+}
+
+/// What a backfill would download, from listing the bucket
+#[derive(Debug, Clone, Default)]
+pub struct DownloadPlan {
+    /// Hours that still need loading
+    pub hours: usize,
+    pub objects: usize,
+    pub bytes: u64,
+    /// Hours with no file although later hours exist
+    pub missing_upstream: usize,
+    /// Hours after the last published file
+    pub unpublished: usize,
+}
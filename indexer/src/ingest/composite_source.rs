@@ -0,0 +1,175 @@
+use super::budget::DownloadPlan;
+use super::cursor::Cursor;
+use super::IngestSource;
+use crate::model::{IngestBatch, PageSummary};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use indexer_core::config::LiveConfig;
+use indexer_core::Result;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::Instant;
+use tracing::{debug, info, instrument};
+
+/// Hour files for closed hours, the live source for everything newer. Pages load
+/// hour files until none is left to load, then tail the live source, checking for
+/// newly published hours every `history_poll_secs`. Checkpoints only track the hour
+/// files, under the history source's id, so `backfill` and `run` share one position
+/// and a restart loads every hour not yet published before tailing again. Live
+/// fills are provisional: loading an hour file replaces them with its own fills.
+pub struct CompositeSource {
+    history: Arc<dyn IngestSource>,
+    live: Arc<dyn IngestSource>,
+    history_poll: Duration,
+    state: Mutex<TailState>,
+}
+
+/// Where the live source is, kept in memory only
+#[derive(Debug, Default)]
+struct TailState {
+    /// Newest fill seen from the live source
+    live_from: Option<DateTime<Utc>>,
+    /// When to look for new hour files again; `None` looks on the next page
+    next_history_check: Option<Instant>,
+}
+
+impl CompositeSource {
+    pub fn new(history: Arc<dyn IngestSource>, live: Arc<dyn IngestSource>, config: &LiveConfig) -> Self {
+        Self {
+            history,
+            live,
+            history_poll: Duration::from_secs(config.history_poll_secs),
+            state: Mutex::new(TailState::default()),
+        }
+    }
+}
+
+#[async_trait]
+impl IngestSource for CompositeSource {
+    async fn fetch_page(
+        &self,
+        start_from: DateTime<Utc>,
+        cursor: Option<Cursor>,
+    ) -> Result<IngestBatch> {
+        let (tx, mut rx) = mpsc::channel(1);
+        let stream = async move { self.stream_page(start_from, cursor, usize::MAX, &tx).await };
+        let collect = async {
+            let mut batches = Vec::new();
+            while let Some(batch) = rx.recv().await {
+                batches.push(batch);
+            }
+            batches
+        };
+        let (summary, batches) = tokio::join!(stream, collect);
+        let summary = summary?;
+
+        let mut page = IngestBatch {
+            fills: vec![],
+            cursor: summary.cursor,
+            has_more: summary.has_more,
+            bytes_downloaded: Some(summary.bytes),
+            hours: vec![],
+            rejects: vec![],
+            blocks: vec![],
+        };
+        for mut batch in batches {
+            page.fills.append(&mut batch.fills);
+            page.hours.append(&mut batch.hours);
+            page.rejects.append(&mut batch.rejects);
+            page.blocks.append(&mut batch.blocks);
+        }
+        Ok(page)
+    }
+
+    /// Load the next hour files if any are published, otherwise one page of the live
+    /// source. Live pages keep the history cursor and report no last timestamp, so
+    /// the checkpoint never moves past an hour that was not loaded from its file.
+    #[instrument(skip(self, tx))]
+    async fn stream_page(
+        &self,
+        start_from: DateTime<Utc>,
+        cursor: Option<Cursor>,
+        batch_size: usize,
+        tx: &mpsc::Sender<IngestBatch>,
+    ) -> Result<PageSummary> {
+        let mut state = self.state.lock().await;
+        let history_cursor = cursor.clone().unwrap_or_else(|| self.history.hour_cursor(start_from));
+
+        if state.next_history_check.map_or(true, |at| Instant::now() >= at) {
+            let page = self.history.stream_page(start_from, cursor, batch_size, tx).await?;
+            if !page.has_more {
+                state.next_history_check = Some(Instant::now() + self.history_poll);
+            }
+
+            if page.cursor.is_some() {
+                if state.live_from.is_some() {
+                    info!(
+                        source = self.history.source_id(),
+                        fills = page.fills,
+                        "Loaded newly published hour files"
+                    );
+                }
+                // Tail the live source next if the hour files ran out
+                return Ok(PageSummary { has_more: true, ..page });
+            }
+        }
+
+        // Live sources start from the first hour without a loaded file
+        let live_from = match state.live_from {
+            Some(live_from) => live_from,
+            None => {
+                info!(
+                    source = self.history.source_id(),
+                    live = self.live.source_id(),
+                    from = %history_cursor.hour.format("%Y-%m-%d %H:00"),
+                    "🔀 Hour files caught up, tailing live source"
+                );
+                *state.live_from.insert(history_cursor.hour)
+            }
+        };
+        let page = self.live.stream_page(live_from, None, batch_size, tx).await?;
+        if let Some(last_timestamp) = page.last_timestamp {
+            state.live_from = Some(last_timestamp.max(live_from));
+        }
+
+        debug!(
+            live = self.live.source_id(),
+            fills = page.fills,
+            next_hour = %history_cursor.hour.format("%Y-%m-%d %H:00"),
+            "Tailed live source"
+        );
+
+        Ok(PageSummary {
+            cursor: Some(history_cursor),
+            has_more: page.has_more,
+            fills: page.fills,
+            bytes: page.bytes,
+            last_timestamp: None,
+        })
+    }
+
+    async fn plan_download(&self, hours: &[DateTime<Utc>]) -> Result<Option<DownloadPlan>> {
+        self.history.plan_download(hours).await
+    }
+
+    fn transfer_billed(&self) -> bool {
+        self.history.transfer_billed()
+    }
+
+    fn poll_interval(&self) -> Duration {
+        self.live.poll_interval()
+    }
+
+    fn hour_cursor(&self, hour: DateTime<Utc>) -> Cursor {
+        self.history.hour_cursor(hour)
+    }
+
+    fn source_id(&self) -> &str {
+        self.history.source_id()
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        self.live.health_check().await
+    }
+}
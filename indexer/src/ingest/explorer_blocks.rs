@@ -0,0 +1,362 @@
+use super::budget::{format_bytes, DownloadBudget};
+use super::cursor::{Cursor, SourceKind};
+use super::hourly::HourlyObjectStore;
+use super::{file_finished, open_node_data, LoaderCheckpoint};
+use super::schema::parse_node_time;
+use crate::model::{L1Block, L1Transaction};
+use crate::store::Store;
+use chrono::{DateTime, TimeZone, Utc};
+use indexer_core::{Config, Error, Result};
+use metrics::counter;
+use rmpv::Value;
+use std::io::{BufReader, ErrorKind, Read};
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+/// Checkpoint source of the explorer block loader
+pub const SOURCE_ID: &str = "explorer_blocks";
+
+/// Directory of the block files in the node data bucket
+const PREFIX: &str = "explorer_blocks/";
+
+/// Blocks written per insert
+const INSERT_CHUNK_BLOCKS: usize = 1_000;
+
+/// Loads the MessagePack block files under `explorer_blocks/` of the node data bucket
+/// into `l1_blocks` and `l1_transactions`, one file at a time, checkpointing the
+/// highest block stored under its own source id
+pub struct ExplorerBlockLoader {
+    objects: Arc<dyn HourlyObjectStore>,
+    store: Arc<Store>,
+    budget: DownloadBudget,
+}
+
+/// Files and blocks one run loaded, and what it downloaded
+#[derive(Debug, Default)]
+pub struct ExplorerBlockSummary {
+    pub files: usize,
+    pub blocks: usize,
+    pub blocks_inserted: usize,
+    pub transactions_inserted: usize,
+    pub rejected_blocks: usize,
+    pub bytes: u64,
+}
+
+/// A block file and the numbers in its key, e.g. `[0, 1000000, 1001000]` for
+/// `explorer_blocks/0/1000000/1001000.rmp.lz4`, which order the files by height
+struct BlockFile {
+    key: String,
+    size: u64,
+    position: Vec<u64>,
+}
+
+/// What loading one file did
+struct FileLoad {
+    /// Highest block stored from the file and its time
+    highest: Option<(i64, Option<DateTime<Utc>>)>,
+    inserted: usize,
+    /// Whether the file holds blocks at or past the end height
+    reached_end: bool,
+}
+
+/// Blocks and transactions parsed from one file
+struct ParsedBlocks {
+    blocks: Vec<L1Block>,
+    transactions: Vec<L1Transaction>,
+    rejected: usize,
+    first_reject: Option<String>,
+}
+
+impl ExplorerBlockLoader {
+    /// Loader over `explorer_blocks/` of the bucket [`open_node_data`] opens
+    pub async fn from_config(config: &Config, store: Arc<Store>, dir: Option<String>) -> Result<Self> {
+        let (objects, billed) = open_node_data(config, dir).await?;
+
+        Ok(Self {
+            objects,
+            store,
+            budget: DownloadBudget::new(&config.ingest, billed),
+        })
+    }
+
+    /// Load blocks from `start_height` up to `end_height` (both open-ended if `None`),
+    /// resuming after the highest block checkpointed inside that range
+    pub async fn run(&self, start_height: Option<i64>, end_height: Option<i64>) -> Result<ExplorerBlockSummary> {
+        let mut checkpoint = LoaderCheckpoint::load(Arc::clone(&self.store), SOURCE_ID).await?;
+
+        // Highest block already loaded; blocks up to it are skipped
+        let mut after = start_height.map(|height| height - 1);
+        let resume = checkpoint.cursor().and_then(|cursor| cursor.block).filter(|block| {
+            after.map_or(true, |after| *block > after) && end_height.map_or(true, |end| *block < end)
+        });
+        if let Some(block) = resume {
+            info!("📍 Resuming explorer blocks from checkpoint after block {}", block);
+            after = Some(block);
+        }
+
+        let files = self.list_files().await?;
+        info!(
+            after = ?after,
+            end = ?end_height,
+            files = files.len(),
+            "🧱 Loading explorer blocks"
+        );
+
+        let mut summary = ExplorerBlockSummary::default();
+
+        for (index, file) in files.iter().enumerate() {
+            let next = files.get(index + 1).and_then(|next| next.position.last());
+            if file_finished(next.map(|next| *next as i64), after) {
+                continue;
+            }
+
+            let load = self.load_file(file, after, end_height, &mut summary).await?;
+
+            let mut cursor = None;
+            let mut last_record_ts = None;
+            if let Some((height, last_time)) = load.highest.filter(|(height, _)| after.map_or(true, |after| *height > after)) {
+                after = Some(height);
+
+                let mut highest = Cursor::hourly(
+                    SourceKind::ExplorerBlocks,
+                    Some("rmp".to_string()),
+                    last_time.unwrap_or(DateTime::UNIX_EPOCH),
+                );
+                highest.block = Some(height);
+                cursor = Some(highest);
+                last_record_ts = last_time;
+            }
+            checkpoint
+                .save(&self.budget, cursor, last_record_ts, load.inserted, summary.bytes)
+                .await?;
+
+            if load.reached_end {
+                break;
+            }
+        }
+
+        info!(
+            "✨ Explorer blocks loaded: {} files, {} blocks, {} inserted, {} transactions inserted | Downloaded: {} (~${:.2}) | Rejected blocks: {}",
+            summary.files,
+            summary.blocks,
+            summary.blocks_inserted,
+            summary.transactions_inserted,
+            format_bytes(summary.bytes),
+            self.budget.cost_usd(summary.bytes),
+            summary.rejected_blocks
+        );
+
+        Ok(summary)
+    }
+
+    /// Block files in the bucket in height order. Keys whose path isn't all numbers are ignored.
+    async fn list_files(&self) -> Result<Vec<BlockFile>> {
+        let mut files: Vec<BlockFile> = self.objects
+            .list_objects(PREFIX)
+            .await?
+            .into_iter()
+            .filter_map(|(key, size)| {
+                let path = key.strip_prefix(PREFIX)?;
+                let path = path.strip_suffix(".rmp.lz4").or_else(|| path.strip_suffix(".lz4"))?;
+                let position = path.split('/').map(|part| part.parse().ok()).collect::<Option<Vec<u64>>>()?;
+                Some(BlockFile { key, size, position })
+            })
+            .collect();
+        files.sort_by(|a, b| a.position.cmp(&b.position));
+
+        Ok(files)
+    }
+
+    /// Download, parse and insert the blocks of one file above `after` and below `end`.
+    async fn load_file(
+        &self,
+        file: &BlockFile,
+        after: Option<i64>,
+        end: Option<i64>,
+        summary: &mut ExplorerBlockSummary,
+    ) -> Result<FileLoad> {
+        self.budget.check(summary.bytes + file.size)?;
+
+        let Some(object) = self.objects.open_object(&file.key).await? else {
+            warn!(key = %file.key, "Explorer block file disappeared after it was listed");
+            return Ok(FileLoad {
+                highest: None,
+                inserted: 0,
+                reached_end: false,
+            });
+        };
+        if !object.cached {
+            summary.bytes += file.size;
+        }
+
+        let store_name = self.objects.store_name().to_string();
+        let mut parsed = {
+            let key = file.key.clone();
+            tokio::task::spawn_blocking(move || decode_blocks(object.reader, &key, &store_name))
+                .await
+                .map_err(|e| Error::Internal(format!("Explorer block decoder panicked: {}", e)))??
+        };
+
+        if let Some(reason) = &parsed.first_reject {
+            warn!(key = %file.key, rejected = parsed.rejected, first = %reason, "Rejected blocks in explorer block file");
+        }
+        counter!("indexer_l1_rejected_blocks").increment(parsed.rejected as u64);
+
+        let reached_end = end.is_some_and(|end| parsed.blocks.iter().any(|block| block.height >= end));
+        let in_range = |height: i64| after.map_or(true, |after| height > after) && end.map_or(true, |end| height < end);
+        parsed.blocks.retain(|block| in_range(block.height));
+        parsed.transactions.retain(|tx| in_range(tx.block_height));
+        parsed.blocks.sort_by_key(|block| block.height);
+
+        let mut blocks_inserted = 0;
+        let mut transactions_inserted = 0;
+        let mut transactions = parsed.transactions.as_slice();
+        for chunk in parsed.blocks.chunks(INSERT_CHUNK_BLOCKS) {
+            let last = chunk.last().map(|block| block.height).unwrap_or_default();
+            let split = transactions.partition_point(|tx| tx.block_height <= last);
+            let (block_txs, rest) = transactions.split_at(split);
+            transactions = rest;
+
+            let (blocks, txs) = self.store.insert_l1_blocks(chunk, block_txs).await?;
+            blocks_inserted += blocks;
+            transactions_inserted += txs;
+        }
+
+        summary.files += 1;
+        summary.blocks += parsed.blocks.len();
+        summary.blocks_inserted += blocks_inserted;
+        summary.transactions_inserted += transactions_inserted;
+        summary.rejected_blocks += parsed.rejected;
+
+        let highest = parsed.blocks.last();
+        info!(
+            "🧱 {} | {} blocks, {} inserted, {} transactions | Downloaded so far: {}",
+            file.key,
+            parsed.blocks.len(),
+            blocks_inserted,
+            transactions_inserted,
+            format_bytes(summary.bytes)
+        );
+
+        Ok(FileLoad {
+            highest: highest.map(|block| (block.height, block.block_time)),
+            inserted: blocks_inserted,
+            reached_end,
+        })
+    }
+}
+
+/// Decompress a block file and parse the MessagePack values in it, each a block or an
+/// array of blocks. Malformed blocks are counted and skipped.
+fn decode_blocks(reader: Box<dyn Read + Send>, key: &str, store_name: &str) -> Result<ParsedBlocks> {
+    let mut reader = BufReader::new(lz4_flex::frame::FrameDecoder::new(reader));
+    let mut parsed = ParsedBlocks {
+        blocks: Vec::new(),
+        transactions: Vec::new(),
+        rejected: 0,
+        first_reject: None,
+    };
+
+    let mut index = 0;
+    loop {
+        let value = match rmpv::decode::read_value(&mut reader) {
+            Ok(value) => value,
+            Err(rmpv::decode::Error::InvalidMarkerRead(e)) if e.kind() == ErrorKind::UnexpectedEof => break,
+            Err(e) => {
+                return Err(Error::Ingest {
+                    source_name: store_name.to_string(),
+                    details: format!("Failed to read '{}': {}", key, e),
+                })
+            }
+        };
+
+        let values = match value {
+            Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            match parse_block(&value, key) {
+                Ok((block, mut transactions)) => {
+                    parsed.blocks.push(block);
+                    parsed.transactions.append(&mut transactions);
+                }
+                Err(e) => {
+                    parsed.rejected += 1;
+                    parsed.first_reject.get_or_insert_with(|| format!("block {}: {}", index, e));
+                }
+            }
+            index += 1;
+        }
+    }
+
+    // Inserts split transactions by block, so both follow block order
+    parsed.transactions.sort_by_key(|tx| (tx.block_height, tx.tx_index));
+
+    debug!(key = %key, blocks = parsed.blocks.len(), rejected = parsed.rejected, "Parsed explorer block file");
+    Ok(parsed)
+}
+
+/// Parse one `{"header": {"height", "block_time", "hash", "proposer"}, "txs": [..]}`
+/// block. Header fields at the top level of the block are read too.
+fn parse_block(value: &Value, key: &str) -> Result<(L1Block, Vec<L1Transaction>)> {
+    let header = if value["header"].is_map() { &value["header"] } else { value };
+
+    let height = header["height"]
+        .as_i64()
+        .ok_or_else(|| Error::Validation("Explorer block without a height".to_string()))?;
+    let block_time = time(&header["block_time"]).or_else(|| time(&header["blockTime"]));
+
+    let txs = value["txs"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let transactions = txs
+        .iter()
+        .enumerate()
+        .map(|(index, tx)| {
+            let action = match &tx["action"] {
+                Value::Nil => &tx["actions"][0],
+                action => action,
+            };
+
+            L1Transaction {
+                block_height: height,
+                tx_index: index as i32,
+                hash: text(&tx["hash"]),
+                time: time(&tx["time"]).or(block_time),
+                action_type: text(&action["type"]),
+                user_address: text(&tx["user"]),
+                error: text(&tx["error"]),
+            }
+        })
+        .collect();
+
+    let block = L1Block {
+        height,
+        block_time,
+        hash: text(&header["hash"]),
+        proposer: text(&header["proposer"]),
+        tx_count: txs.len() as i32,
+        key: key.to_string(),
+    };
+
+    Ok((block, transactions))
+}
+
+/// A string field, or a binary one as `0x` hex
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => s.as_str().map(str::to_string),
+        Value::Binary(bytes) => Some(format!(
+            "0x{}",
+            bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>()
+        )),
+        _ => None,
+    }
+}
+
+/// A time given as milliseconds since the epoch or as a node timestamp string
+fn time(value: &Value) -> Option<DateTime<Utc>> {
+    match value {
+        Value::Integer(ms) => ms.as_i64().and_then(|ms| Utc.timestamp_millis_opt(ms).single()),
+        Value::String(s) => s.as_str().and_then(parse_node_time),
+        _ => None,
+    }
+}
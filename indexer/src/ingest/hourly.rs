@@ -0,0 +1,914 @@
+use super::budget::DownloadPlan;
+use super::cursor::{Cursor, SourceKind};
+use super::parse_pool::ParsePool;
+use super::schema::{self, HourParser, LineBlock, LineReject, SchemaRegistry};
+use crate::model::{Block, Fill, FillReject, HourReport, IngestBatch, PageSummary};
+use async_trait::async_trait;
+use bytes::{Buf, Bytes};
+use chrono::{DateTime, DurationRound, NaiveDate, Timelike, Utc};
+use futures::future::join_all;
+use futures::stream::{self, StreamExt};
+use indexer_core::backoff::retry_with_backoff;
+use indexer_core::{Error, Result};
+use metrics::counter;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::atomic::{AtomicI64, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// Number of hours fetched concurrently per page
+const HOURS_PER_BATCH: usize = 8;
+
+/// Downloaded body chunks buffered ahead of the decoder of each hour file
+pub const STREAM_BUFFER_CHUNKS: usize = 16;
+
+/// Read buffer between the LZ4 decoder and the line splitter
+const LINE_BUFFER_BYTES: usize = 256 * 1024;
+
+/// Decompressed bytes per parse job; blocks end on a line boundary
+const PARSE_BLOCK_BYTES: usize = 512 * 1024;
+
+/// Decompressed blocks buffered ahead of the parse pool for each hour file
+const DECODED_BLOCKS_AHEAD: usize = 2;
+
+/// Attempts at listing a directory or opening an object before giving up. Only these
+/// requests are retried: once an hour starts streaming, its chunks are already in the
+/// pipeline, so a failure is recorded in the manifest and the hour is loaded again later.
+const FETCH_ATTEMPTS: u32 = 3;
+
+/// First wait between fetch attempts; doubles with every retry
+const FETCH_RETRY_BASE_DELAY_MS: u64 = 1000;
+
+/// Day directories are cached once the day ended this long ago; until then an hour
+/// can still be published late
+const LISTING_SEAL_HOURS: i64 = 24;
+
+/// Storage backend holding the `<prefix>/hourly/YYYYMMDD/H.lz4` layout used by the
+/// Hyperliquid node data bucket. Implemented by the S3 bucket itself and by local mirrors.
+#[async_trait]
+pub trait HourlyObjectStore: Send + Sync {
+    /// Name used in logs and errors
+    fn store_name(&self) -> &str;
+
+    /// Kind recorded in this store's cursors
+    fn source_kind(&self) -> SourceKind;
+
+    /// Open the compressed object stored under `key` for reading, or `None` if it does not exist
+    async fn open_object(&self, key: &str) -> Result<Option<ObjectStream>>;
+
+    /// Every key stored under `prefix`, e.g. one day directory of hour files, with
+    /// its size in bytes
+    async fn list_objects(&self, prefix: &str) -> Result<HashMap<String, u64>>;
+
+    /// Names of the directories directly under `prefix`, e.g. the day directories
+    /// of one epoch, or `None` if the store can't list them
+    async fn list_dirs(&self, prefix: &str) -> Result<Option<Vec<String>>>;
+
+    /// Day listings kept between pages
+    fn listing_cache(&self) -> &ListingCache;
+
+    /// Format epochs used to resolve keys and parsers for each hour
+    fn schemas(&self) -> &Arc<SchemaRegistry>;
+
+    /// Threads that parse decompressed lines into fills
+    fn parse_pool(&self) -> &ParsePool;
+}
+
+/// A compressed object being read. `reader` blocks, so it is only read from
+/// blocking tasks.
+pub struct ObjectStream {
+    pub reader: Box<dyn Read + Send>,
+    pub etag: Option<String>,
+    /// Served from the local object cache, so reading it downloads nothing
+    pub cached: bool,
+}
+
+/// Blocking `Read` over body chunks delivered by an async download task
+pub struct ChannelReader {
+    rx: mpsc::Receiver<std::io::Result<Bytes>>,
+    current: Bytes,
+}
+
+impl ChannelReader {
+    pub fn new(rx: mpsc::Receiver<std::io::Result<Bytes>>) -> Self {
+        Self {
+            rx,
+            current: Bytes::new(),
+        }
+    }
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.current.is_empty() {
+            match self.rx.blocking_recv() {
+                Some(Ok(chunk)) => self.current = chunk,
+                Some(Err(e)) => return Err(e),
+                None => return Ok(0),
+            }
+        }
+
+        let len = buf.len().min(self.current.len());
+        buf[..len].copy_from_slice(&self.current[..len]);
+        self.current.advance(len);
+        Ok(len)
+    }
+}
+
+/// Counts the compressed bytes pulled through by the decoder
+struct CountingReader<R> {
+    inner: R,
+    count: Arc<AtomicU64>,
+    /// `download`, or `cache` for objects read from the object cache
+    stage: &'static str,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.count.fetch_add(len as u64, Ordering::Relaxed);
+        counter!("indexer_stage_bytes", "stage" => self.stage).increment(len as u64);
+        Ok(len)
+    }
+}
+
+/// Listings of day directories that are old enough not to change any more
+#[derive(Debug, Default)]
+pub struct ListingCache {
+    sealed: Mutex<HashMap<String, Arc<HashMap<String, u64>>>>,
+}
+
+#[cfg(test)]
+impl ListingCache {
+    /// Number of day directories listed so far
+    pub(crate) fn len(&self) -> usize {
+        self.sealed.lock().unwrap().len()
+    }
+}
+
+/// Which hour files exist, from listing the day directories of a store. Each
+/// directory is listed at most once per page.
+struct KeyListing<'a, S: ?Sized> {
+    store: &'a S,
+    dirs: HashMap<String, Arc<HashMap<String, u64>>>,
+}
+
+impl<'a, S: HourlyObjectStore + ?Sized> KeyListing<'a, S> {
+    fn new(store: &'a S) -> Self {
+        Self {
+            store,
+            dirs: HashMap::new(),
+        }
+    }
+
+    /// Key and size of the hour file published for `date`, trying alternate
+    /// prefixes near schema cutovers, or `None` if none of them was listed
+    async fn key_for(&mut self, date: DateTime<Utc>) -> Result<Option<(String, u64)>> {
+        for key in self.store.schemas().candidate_keys(date, date.hour())? {
+            let dir = match key.rfind('/') {
+                Some(end) => &key[..=end],
+                None => "",
+            };
+            if let Some(size) = self.dir(dir, date).await?.get(&key) {
+                return Ok(Some((key, *size)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Last hour that can have been published: the end of the latest day directory
+    /// under any epoch prefix, capped at `now`. Scans past the data stop there
+    /// instead of listing every day up to the present.
+    async fn data_end(&self, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+        let mut last_day = None;
+        for prefix in self.store.schemas().prefixes() {
+            let prefix = format!("{}/", prefix);
+            let dirs = retry_with_backoff(
+                || self.store.list_dirs(&prefix),
+                FETCH_ATTEMPTS,
+                FETCH_RETRY_BASE_DELAY_MS,
+                "list_dirs",
+            )
+            .await?;
+            let Some(dirs) = dirs else {
+                return Ok(now);
+            };
+
+            let days = dirs.iter().filter_map(|name| NaiveDate::parse_from_str(name, "%Y%m%d").ok());
+            last_day = days.chain(last_day).max();
+        }
+
+        Ok(match last_day {
+            Some(day) => day.and_hms_opt(23, 0, 0).unwrap().and_utc().min(now),
+            None => DateTime::<Utc>::MIN_UTC,
+        })
+    }
+
+    async fn dir(&mut self, dir: &str, date: DateTime<Utc>) -> Result<Arc<HashMap<String, u64>>> {
+        if let Some(keys) = self.dirs.get(dir) {
+            return Ok(Arc::clone(keys));
+        }
+
+        let cache = self.store.listing_cache();
+        let cached = cache.sealed.lock().unwrap_or_else(|e| e.into_inner()).get(dir).cloned();
+        let keys = match cached {
+            Some(keys) => keys,
+            None => {
+                debug!(store = self.store.store_name(), prefix = %dir, "Listing hour files");
+                let keys = Arc::new(
+                    retry_with_backoff(
+                        || self.store.list_objects(dir),
+                        FETCH_ATTEMPTS,
+                        FETCH_RETRY_BASE_DELAY_MS,
+                        "list_objects",
+                    )
+                    .await?,
+                );
+
+                let day_end = date.date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc() + chrono::Duration::days(1);
+                if Utc::now() - day_end > chrono::Duration::hours(LISTING_SEAL_HOURS) {
+                    cache.sealed.lock().unwrap_or_else(|e| e.into_inner()).insert(dir.to_string(), Arc::clone(&keys));
+                }
+                keys
+            }
+        };
+
+        self.dirs.insert(dir.to_string(), Arc::clone(&keys));
+        Ok(keys)
+    }
+}
+
+/// Hours of one page, planned from a listing instead of the wall clock
+struct PagePlan {
+    /// Hours to load with their key, `None` if the hour is missing upstream
+    hours: Vec<(DateTime<Utc>, Option<String>)>,
+    /// Whether a later hour has been published
+    has_more: bool,
+}
+
+/// Plan up to `max_hours` hours from `start`. An absent hour is missing upstream
+/// when a later hour exists; absent hours at the end of the data are left out, so
+/// they are fetched once published.
+async fn plan_page<S: HourlyObjectStore + ?Sized>(
+    store: &S,
+    start: DateTime<Utc>,
+    max_hours: usize,
+) -> Result<PagePlan> {
+    let mut listing = KeyListing::new(store);
+    let end = listing.data_end(Utc::now()).await?;
+
+    let mut hours = Vec::new();
+    let mut published = 0;
+    let mut date = truncate_hour(start);
+    while hours.len() < max_hours && date <= end {
+        let key = listing.key_for(date).await?.map(|(key, _)| key);
+        if key.is_some() {
+            published = hours.len() + 1;
+        }
+        hours.push((date, key));
+        date += chrono::Duration::hours(1);
+    }
+
+    // Look past the page for the next published hour
+    let mut has_more = false;
+    while date <= end {
+        if listing.key_for(date).await?.is_some() {
+            has_more = true;
+            break;
+        }
+        date += chrono::Duration::hours(1);
+    }
+
+    if !has_more {
+        hours.truncate(published);
+    }
+
+    Ok(PagePlan { hours, has_more })
+}
+
+/// List what loading `hours` would download: the objects found and their total
+/// size, and the hours that have no object
+pub async fn plan_download<S: HourlyObjectStore + ?Sized>(
+    store: &S,
+    hours: &[DateTime<Utc>],
+) -> Result<DownloadPlan> {
+    let mut listing = KeyListing::new(store);
+    let mut plan = DownloadPlan {
+        hours: hours.len(),
+        ..DownloadPlan::default()
+    };
+
+    // Absent hours since the last object found; missing upstream if a later one exists
+    let mut absent = 0;
+    for date in hours {
+        match listing.key_for(*date).await? {
+            Some((_, size)) => {
+                plan.objects += 1;
+                plan.bytes += size;
+                plan.missing_upstream += absent;
+                absent = 0;
+            }
+            None => absent += 1,
+        }
+    }
+
+    if absent > 0 {
+        let end = listing.data_end(Utc::now()).await?;
+        let mut date = hours[hours.len() - 1] + chrono::Duration::hours(1);
+        while date <= end && listing.key_for(date).await?.is_none() {
+            date += chrono::Duration::hours(1);
+        }
+        if date <= end {
+            plan.missing_upstream += absent;
+        } else {
+            plan.unpublished = absent;
+        }
+    }
+
+    Ok(plan)
+}
+
+/// Cursor at the start of the hour containing `date`, tagged with the format of
+/// its schema epoch
+pub fn hour_cursor<S: HourlyObjectStore + ?Sized>(store: &S, date: DateTime<Utc>) -> Cursor {
+    let format = store.schemas().epoch_for(date).ok().map(|epoch| epoch.parser.name().to_string());
+    Cursor::hourly(store.source_kind(), format, date)
+}
+
+/// Where the batches of a streamed page go, and what has been sent so far
+#[derive(Clone)]
+struct BatchSink {
+    tx: mpsc::Sender<IngestBatch>,
+    /// Fills per batch before a chunk is cut
+    batch_size: usize,
+    /// Cursor of the page being streamed. Carried by every batch except the page's
+    /// last, so a checkpoint never skips an hour that is still being loaded.
+    cursor: Cursor,
+    fills_sent: Arc<AtomicUsize>,
+    bytes_sent: Arc<AtomicU64>,
+    last_timestamp_ms: Arc<AtomicI64>,
+}
+
+impl BatchSink {
+    fn new(tx: mpsc::Sender<IngestBatch>, batch_size: usize, cursor: Cursor) -> Self {
+        Self {
+            tx,
+            batch_size,
+            cursor,
+            fills_sent: Arc::new(AtomicUsize::new(0)),
+            bytes_sent: Arc::new(AtomicU64::new(0)),
+            last_timestamp_ms: Arc::new(AtomicI64::new(i64::MIN)),
+        }
+    }
+
+    fn record(&self, batch: &IngestBatch) {
+        self.fills_sent.fetch_add(batch.fills.len(), Ordering::Relaxed);
+        self.bytes_sent.fetch_add(batch.bytes_downloaded.unwrap_or(0), Ordering::Relaxed);
+        if let Some(ts) = batch.fills.iter().map(|fill| fill.timestamp.timestamp_millis()).max() {
+            self.last_timestamp_ms.fetch_max(ts, Ordering::Relaxed);
+        }
+    }
+
+    async fn send(&self, batch: IngestBatch) -> Result<()> {
+        self.record(&batch);
+        self.tx
+            .send(batch)
+            .await
+            .map_err(|_| Error::Internal("Pipeline channel closed".to_string()))
+    }
+
+    fn summary(&self, cursor: Option<Cursor>, has_more: bool) -> PageSummary {
+        let last_timestamp_ms = self.last_timestamp_ms.load(Ordering::Relaxed);
+        PageSummary {
+            cursor,
+            has_more,
+            fills: self.fills_sent.load(Ordering::Relaxed),
+            bytes: self.bytes_sent.load(Ordering::Relaxed),
+            last_timestamp: (last_timestamp_ms != i64::MIN)
+                .then(|| DateTime::from_timestamp_millis(last_timestamp_ms))
+                .flatten(),
+        }
+    }
+}
+
+/// One hour file being decoded. Fills and rejects are handed out in chunks so
+/// only about `batch_size` fills are held at a time.
+struct HourStream {
+    hour: DateTime<Utc>,
+    key: String,
+    etag: Option<String>,
+    cached: bool,
+    parser: HourParser,
+    read_bytes: Arc<AtomicU64>,
+    reported_bytes: u64,
+    reported_lines: usize,
+    reported_blocks: usize,
+    /// Blocks at or below this one are loaded, for the cursor of the page's first hour
+    loaded_block: Option<i64>,
+    chunk: usize,
+    fills: Vec<Fill>,
+    rejects: Vec<LineReject>,
+    headers: Vec<Block>,
+}
+
+impl HourStream {
+    /// Cut up to `sink.batch_size` fills, and the rejects gathered since the last
+    /// chunk, into a batch
+    fn take_batch(&mut self, sink: &BatchSink, last_chunk: bool, error: Option<String>) -> IngestBatch {
+        let bytes = self.read_bytes.load(Ordering::Relaxed);
+        let lines = self.parser.lines();
+        let format = self.parser.detected_format().unwrap_or(self.parser.expected_format());
+
+        // Tag fills with their key so the store can count inserts per hour file
+        let count = self.fills.len().min(sink.batch_size);
+        let mut fills: Vec<Fill> = self.fills.drain(..count).collect();
+        for fill in &mut fills {
+            fill.source_id = Some(self.key.clone());
+        }
+
+        if let Some(first) = self.rejects.first() {
+            warn!(
+                key = %self.key,
+                rejected = self.rejects.len(),
+                first = %first.reason,
+                "Rejected lines in hour file"
+            );
+        }
+
+        let rejects: Vec<FillReject> = self
+            .rejects
+            .drain(..)
+            .map(|reject| FillReject {
+                hour: Some(self.hour),
+                key: Some(self.key.clone()),
+                line_number: Some(reject.line_number as i32),
+                raw_line: Some(reject.raw_line),
+                reason: reject.reason,
+                format: Some(format.to_string()),
+                payload: None,
+                fill_timestamp: None,
+            })
+            .collect();
+
+        // Headers go with the first batch after their line was parsed
+        let headers: Vec<Block> = self
+            .headers
+            .drain(..)
+            .map(|header| Block {
+                hour: Some(self.hour),
+                key: Some(self.key.clone()),
+                ..header
+            })
+            .collect();
+
+        let blocks = self.parser.blocks();
+        let report = HourReport {
+            hour: self.hour,
+            key: self.key.clone(),
+            chunk: self.chunk,
+            last_chunk,
+            missing_upstream: false,
+            expected_format: self.parser.expected_format().to_string(),
+            detected_format: self.parser.detected_format().map(str::to_string),
+            etag: self.etag.clone(),
+            compressed_bytes: bytes - self.reported_bytes,
+            lines: lines - self.reported_lines,
+            first_block: blocks.first,
+            last_block: blocks.last,
+            block_count: blocks.count - self.reported_blocks,
+            fills: fills.len(),
+            rejected: rejects.len(),
+            error,
+        };
+
+        self.chunk += 1;
+        self.reported_bytes = bytes;
+        self.reported_lines = lines;
+        self.reported_blocks = blocks.count;
+
+        // The page's first hour resumes by block. A block's fills come from one line, so
+        // every block before the first one still buffered is loaded once this batch is.
+        let mut cursor = sink.cursor.clone();
+        if self.hour == cursor.hour {
+            let last = fills.last().and_then(|fill| fill.block_number);
+            let next = self.fills.first().and_then(|fill| fill.block_number);
+            let loaded = match (last, next) {
+                (Some(last), Some(next)) if next == last => Some(last - 1),
+                (last, _) => last,
+            };
+            self.loaded_block = self.loaded_block.max(loaded);
+            cursor.block = self.loaded_block;
+        }
+
+        IngestBatch {
+            fills,
+            cursor: Some(cursor),
+            has_more: true,
+            bytes_downloaded: Some(if self.cached { 0 } else { report.compressed_bytes }),
+            hours: vec![report],
+            rejects,
+            blocks: headers,
+        }
+    }
+}
+
+/// Decompress an hour file into blocks of whole lines for the parse pool. Runs
+/// on a blocking thread and stops early, without error, once `tx` is dropped.
+fn decompress_hour(reader: impl Read, store_name: &str, tx: mpsc::Sender<LineBlock>) -> Result<()> {
+    let mut reader = BufReader::with_capacity(
+        LINE_BUFFER_BYTES,
+        lz4_flex::frame::FrameDecoder::new(reader),
+    );
+    let mut line_number = 1;
+
+    loop {
+        let mut block = LineBlock {
+            first_line: line_number,
+            data: Vec::with_capacity(PARSE_BLOCK_BYTES),
+        };
+
+        let mut result = Ok(true);
+        while block.data.len() < PARSE_BLOCK_BYTES {
+            let complete = block.data.len();
+            match reader.read_until(b'\n', &mut block.data) {
+                Ok(0) => {
+                    result = Ok(false);
+                    break;
+                }
+                Ok(_) => line_number += 1,
+                Err(e) => {
+                    // Drop the partial line; only whole lines are parsed
+                    block.data.truncate(complete);
+                    result = Err(Error::Ingest {
+                        source_name: store_name.to_string(),
+                        details: format!("Failed to decompress LZ4 data: {}", e),
+                    });
+                    break;
+                }
+            }
+        }
+
+        counter!("indexer_stage_bytes", "stage" => "decompress").increment(block.data.len() as u64);
+        if !block.data.is_empty() && tx.blocking_send(block).is_err() {
+            return Ok(());
+        }
+        if !result? {
+            return Ok(());
+        }
+    }
+}
+
+/// Decode one hour file: decompress it on a blocking thread, parse its blocks on
+/// the parse pool and send a batch whenever `batch_size` fills are ready. Returns
+/// the hour's last batch instead of sending it, so pages can order them by hour.
+async fn decode_hour<S: HourlyObjectStore + ?Sized>(
+    store: &S,
+    date: DateTime<Utc>,
+    key: String,
+    object: ObjectStream,
+    sink: &BatchSink,
+    skip_through: Option<i64>,
+) -> Result<IngestBatch> {
+    let read_bytes = Arc::new(AtomicU64::new(0));
+    let compressed = CountingReader {
+        inner: object.reader,
+        count: Arc::clone(&read_bytes),
+        stage: if object.cached { "cache" } else { "download" },
+    };
+
+    let mut hour = HourStream {
+        hour: truncate_hour(date),
+        key,
+        etag: object.etag,
+        cached: object.cached,
+        parser: store.schemas().hour_parser(date)?,
+        read_bytes,
+        reported_bytes: 0,
+        reported_lines: 0,
+        reported_blocks: 0,
+        loaded_block: skip_through,
+        chunk: 0,
+        fills: Vec::new(),
+        rejects: Vec::new(),
+        headers: Vec::new(),
+    };
+
+    let (block_tx, mut block_rx) = mpsc::channel(DECODED_BLOCKS_AHEAD);
+    let store_name = store.store_name().to_string();
+    let decoder = tokio::task::spawn_blocking(move || decompress_hour(compressed, &store_name, block_tx));
+
+    // Blocks are parsed concurrently on the pool and taken back in file order
+    let parsed: Result<()> = match block_rx.recv().await {
+        None => Ok(()),
+        Some(first) => {
+            let parser = hour.parser.parser_for(&first);
+            let pool = store.parse_pool();
+            let mut blocks = stream::iter([first])
+                .chain(stream::poll_fn(move |cx| block_rx.poll_recv(cx)))
+                .map(|block| {
+                    let parser = Arc::clone(&parser);
+                    pool.run("parse", move || schema::parse_block(parser.as_ref(), &block, skip_through))
+                })
+                .buffered(pool.workers());
+
+            loop {
+                let Some(block) = blocks.next().await else {
+                    break Ok(());
+                };
+                let mut block = block?;
+
+                let accepted = hour.parser.accept(&mut block);
+                counter!("indexer_stage_lines", "stage" => "parse").increment(block.lines as u64);
+                counter!("indexer_stage_fills", "stage" => "parse").increment(block.fills.len() as u64);
+                hour.fills.append(&mut block.fills);
+                hour.rejects.append(&mut block.rejects);
+                hour.headers.append(&mut block.headers);
+
+                while hour.fills.len() >= sink.batch_size {
+                    let batch = hour.take_batch(sink, false, None);
+                    sink.send(batch).await?;
+                }
+
+                if let Err(e) = accepted {
+                    break Err(e);
+                }
+            }
+        }
+    };
+
+    // Dropping the blocks above closed the channel, so the decoder has stopped
+    let decoded = decoder
+        .await
+        .map_err(|e| Error::Internal(format!("Hour decoder panicked: {}", e)))?;
+    let result = parsed.and(decoded);
+
+    if let Err(e) = &result {
+        warn!(key = %hour.key, error = %e, "Failed to load hour file");
+    }
+
+    debug!(
+        hour = %hour.hour.format("%Y-%m-%d %H:00"),
+        key = %hour.key,
+        format = hour.parser.detected_format().unwrap_or("unknown"),
+        lines = hour.parser.lines(),
+        skipped = hour.parser.skipped(),
+        chunks = hour.chunk + 1,
+        "Parsed hour file"
+    );
+
+    Ok(hour.take_batch(sink, true, result.err().map(|e| e.to_string())))
+}
+
+/// Stream the hour file stored under `key` into `sink`, skipping blocks at or below
+/// `skip_through`. Returns the hour's last batch, not yet sent, or `None` if the file
+/// disappeared after it was listed.
+async fn stream_hour<S: HourlyObjectStore + ?Sized>(
+    store: &S,
+    date: DateTime<Utc>,
+    key: &str,
+    sink: &BatchSink,
+    skip_through: Option<i64>,
+) -> Result<Option<IngestBatch>> {
+    let expected = store.schemas().hour_key(date, date.hour())?;
+    if key != expected {
+        warn!(
+            store = store.store_name(),
+            expected = %expected,
+            found = %key,
+            "Hour file found under alternate prefix"
+        );
+    }
+
+    match skip_through {
+        Some(block) => info!(
+            store = store.store_name(),
+            key = %key,
+            block,
+            "⏯️ Resuming hour file after block {}",
+            block
+        ),
+        None => debug!(
+            store = store.store_name(),
+            key = %key,
+            "Fetching hour file"
+        ),
+    }
+
+    let opened = retry_with_backoff(|| store.open_object(key), FETCH_ATTEMPTS, FETCH_RETRY_BASE_DELAY_MS, "open_object");
+    let Some(object) = opened.await? else {
+        return Ok(None);
+    };
+
+    decode_hour(store, date, key.to_string(), object, sink, skip_through).await.map(Some)
+}
+
+/// Manifest entry for an hour without a file to load: missing upstream, or failed
+/// to open when `error` is set
+fn empty_hour_batch<S: HourlyObjectStore + ?Sized>(
+    store: &S,
+    date: DateTime<Utc>,
+    key: Option<&str>,
+    cursor: &Cursor,
+    error: Option<String>,
+) -> Result<IngestBatch> {
+    let report = HourReport {
+        hour: truncate_hour(date),
+        key: match key {
+            Some(key) => key.to_string(),
+            None => store.schemas().hour_key(date, date.hour())?,
+        },
+        chunk: 0,
+        last_chunk: true,
+        missing_upstream: error.is_none(),
+        expected_format: store.schemas().epoch_for(date)?.parser.name().to_string(),
+        detected_format: None,
+        etag: None,
+        compressed_bytes: 0,
+        lines: 0,
+        first_block: None,
+        last_block: None,
+        block_count: 0,
+        fills: 0,
+        rejected: 0,
+        error,
+    };
+
+    Ok(IngestBatch {
+        fills: vec![],
+        cursor: Some(cursor.clone()),
+        has_more: true,
+        bytes_downloaded: Some(0),
+        hours: vec![report],
+        rejects: vec![],
+        blocks: vec![],
+    })
+}
+
+fn truncate_hour(date: DateTime<Utc>) -> DateTime<Utc> {
+    date.duration_trunc(chrono::Duration::hours(1)).unwrap_or(date)
+}
+
+/// Stream hour files starting at the cursor position into `tx`, at most
+/// `batch_size` fills per batch. Shared by every `IngestSource` backed by an
+/// `HourlyObjectStore`.
+pub async fn stream_page<S: HourlyObjectStore + ?Sized>(
+    store: &S,
+    start_from: DateTime<Utc>,
+    cursor: Option<Cursor>,
+    batch_size: usize,
+    tx: &mpsc::Sender<IngestBatch>,
+) -> Result<PageSummary> {
+    // Resume at the cursor's hour, or at `start_from` on a fresh start
+    let resume_block = cursor.as_ref().and_then(|cursor| cursor.block);
+    let current_date = match &cursor {
+        Some(cursor) => {
+            let resolved = hour_cursor(store, cursor.hour);
+            if cursor.format.is_some() && cursor.format != resolved.format {
+                warn!(
+                    store = store.store_name(),
+                    hour = %cursor.hour,
+                    cursor_format = ?cursor.format,
+                    format = ?resolved.format,
+                    "Cursor was written for another format epoch than the hour resolves to now"
+                );
+            }
+            cursor.hour
+        }
+        None => start_from,
+    };
+    let sink = BatchSink::new(tx.clone(), batch_size, hour_cursor(store, current_date));
+
+    // Only hours the store actually has are fetched
+    let plan = plan_page(store, current_date, HOURS_PER_BATCH).await?;
+    let Some(&(last_hour, _)) = plan.hours.last() else {
+        debug!(
+            store = store.store_name(),
+            from = %current_date.format("%Y-%m-%d %H:00"),
+            "Reached end of available data"
+        );
+        return Ok(sink.summary(None, false));
+    };
+
+    // Fetch every published hour in parallel; chunks are sent as they are decoded
+    let fetches = plan.hours.iter().map(|(date, key)| {
+        let sink = &sink;
+        // Only the cursor's own hour was partly loaded
+        let skip_through = resume_block.filter(|_| truncate_hour(*date) == sink.cursor.hour);
+        async move {
+            match key {
+                Some(key) => stream_hour(store, *date, key, sink, skip_through).await,
+                None => Ok(None),
+            }
+        }
+    });
+    let results = join_all(fetches).await;
+
+    let mut last_batches = Vec::new();
+    let mut missing_upstream = 0;
+    let mut failed = Vec::new();
+    for ((date, key), result) in plan.hours.iter().zip(results) {
+        let batch = match result {
+            Ok(Some(batch)) => batch,
+            Ok(None) => {
+                if key.is_some() {
+                    debug!("Hour file for {} vanished after listing", date.format("%Y-%m-%d %H:00"));
+                    continue;
+                }
+                missing_upstream += 1;
+                empty_hour_batch(store, *date, None, &sink.cursor, None)?
+            }
+            Err(e) => {
+                debug!("Failed to fetch {}: {}", date.format("%Y-%m-%d %H:00"), e);
+                if tx.is_closed() {
+                    return Err(e);
+                }
+                empty_hour_batch(store, *date, key.as_deref(), &sink.cursor, Some(e.to_string()))?
+            }
+        };
+        failed.extend(batch.hours.iter().filter_map(|report| report.error.clone()));
+        last_batches.push(batch);
+    }
+
+    if missing_upstream > 0 {
+        warn!(
+            store = store.store_name(),
+            from = %current_date.format("%Y-%m-%d %H:00"),
+            missing_upstream,
+            "Hours missing upstream while later hours exist"
+        );
+    }
+
+    // Calculate cursor for next batch; only the page's last batch moves the checkpoint past it
+    let next_date = last_hour + chrono::Duration::hours(1);
+    let next_cursor = hour_cursor(store, next_date);
+    let has_more = plan.has_more;
+
+    // A page whose every hour failed is recorded, then retried by the caller
+    let all_failed = failed.len() == plan.hours.len();
+    if let Some(last) = last_batches.last_mut().filter(|_| !all_failed) {
+        last.cursor = Some(next_cursor.clone());
+        last.has_more = has_more;
+    }
+    for batch in last_batches {
+        sink.send(batch).await?;
+    }
+
+    if all_failed {
+        return Err(Error::Ingest {
+            source_name: store.store_name().to_string(),
+            details: failed.join("; "),
+        });
+    }
+
+    let summary = sink.summary(Some(next_cursor), has_more);
+    info!(
+        store = store.store_name(),
+        "Fetched {} total fills from {} hours",
+        summary.fills,
+        plan.hours.len()
+    );
+
+    Ok(summary)
+}
+
+/// Fetch a whole page as a single batch, for callers that don't stream
+pub async fn fetch_page<S: HourlyObjectStore + ?Sized>(
+    store: &S,
+    start_from: DateTime<Utc>,
+    cursor: Option<Cursor>,
+) -> Result<IngestBatch> {
+    let (tx, mut rx) = mpsc::channel(STREAM_BUFFER_CHUNKS);
+
+    let stream = async move { stream_page(store, start_from, cursor, usize::MAX, &tx).await };
+    let collect = async {
+        let mut page = IngestBatch {
+            fills: vec![],
+            cursor: None,
+            has_more: false,
+            bytes_downloaded: Some(0),
+            hours: vec![],
+            rejects: vec![],
+            blocks: vec![],
+        };
+        while let Some(batch) = rx.recv().await {
+            page.fills.extend(batch.fills);
+            page.hours.extend(batch.hours);
+            page.rejects.extend(batch.rejects);
+            page.blocks.extend(batch.blocks);
+            page.bytes_downloaded = Some(
+                page.bytes_downloaded.unwrap_or(0) + batch.bytes_downloaded.unwrap_or(0),
+            );
+        }
+        page
+    };
+
+    let (summary, mut page) = tokio::join!(stream, collect);
+    let summary = summary?;
+    page.cursor = summary.cursor;
+    page.has_more = summary.has_more;
+
+    Ok(page)
+}
@@ -0,0 +1,342 @@
+use super::budget::{format_bytes, DownloadBudget};
+use super::cursor::{Cursor, SourceKind};
+use super::hourly::HourlyObjectStore;
+use super::{open_archive, LoaderCheckpoint};
+use super::schema::{parse_decimal, parse_node_time};
+use crate::model::{BookLevel, BookSide};
+use crate::store::Store;
+use chrono::{DateTime, Datelike, Duration, DurationRound, TimeZone, Timelike, Utc};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use indexer_core::{Config, Error, Result};
+use metrics::counter;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+/// Checkpoint source of the L2 book loader
+pub const SOURCE_ID: &str = "l2_book";
+
+/// Data type directory of book snapshots under `market_data/<date>/<hour>/`
+const DATA_TYPE: &str = "l2Book";
+
+/// Levels written per insert
+const INSERT_CHUNK_LEVELS: usize = 10_000;
+
+/// Loads the hourly `market_data/<date>/<hour>/l2Book/<coin>.lz4` snapshots of the
+/// archive bucket into `l2_book_levels`, one hour at a time, checkpointing after
+/// every hour under its own source id
+pub struct L2BookLoader {
+    objects: Arc<dyn HourlyObjectStore>,
+    store: Arc<Store>,
+    /// Coins to load; every coin listed for the hour if empty
+    coins: HashSet<String>,
+    concurrency: usize,
+    budget: DownloadBudget,
+}
+
+/// Hours, files and levels one run loaded, and what it downloaded
+#[derive(Debug, Default)]
+pub struct L2BookSummary {
+    pub hours: usize,
+    pub files: usize,
+    pub snapshots: usize,
+    pub levels_inserted: usize,
+    pub rejected_lines: usize,
+    pub bytes: u64,
+}
+
+/// Snapshots and levels parsed from one coin file
+struct ParsedBook {
+    levels: Vec<BookLevel>,
+    snapshots: usize,
+    rejected: usize,
+    first_reject: Option<String>,
+}
+
+impl L2BookLoader {
+    pub fn new(
+        objects: Arc<dyn HourlyObjectStore>,
+        store: Arc<Store>,
+        coins: impl IntoIterator<Item = String>,
+        concurrency: usize,
+        budget: DownloadBudget,
+    ) -> Self {
+        Self {
+            objects,
+            store,
+            coins: coins.into_iter().collect(),
+            concurrency: concurrency.max(1),
+            budget,
+        }
+    }
+
+    /// Loader over the `l2Book` snapshots of the bucket [`open_archive`] opens.
+    /// `coins` overrides `ingest.archive.l2_book_coins` when not empty.
+    pub async fn from_config(config: &Config, store: Arc<Store>, coins: Vec<String>, dir: Option<String>) -> Result<Self> {
+        let archive = &config.ingest.archive;
+        let (objects, billed) = open_archive(config, dir).await?;
+        let coins = if coins.is_empty() { archive.l2_book_coins.clone() } else { coins };
+
+        Ok(Self::new(
+            objects,
+            store,
+            coins,
+            archive.l2_book_concurrency,
+            DownloadBudget::new(&config.ingest, billed),
+        ))
+    }
+
+    /// Load every hour from `start_from` up to `end_at`, resuming after the last
+    /// hour checkpointed inside that range
+    pub async fn run(&self, start_from: DateTime<Utc>, end_at: DateTime<Utc>) -> Result<L2BookSummary> {
+        let mut checkpoint = LoaderCheckpoint::load(Arc::clone(&self.store), SOURCE_ID).await?;
+
+        let mut hour = truncate_hour(start_from);
+        if let Some(cursor) = checkpoint.cursor().filter(|cursor| cursor.hour > hour && cursor.hour < end_at) {
+            info!(
+                "📍 Resuming L2 books from checkpoint at {}",
+                cursor.hour.format("%Y-%m-%d %H:00")
+            );
+            hour = cursor.hour;
+        }
+
+        info!(
+            start = %hour,
+            end = %end_at,
+            coins = self.coins.len(),
+            "📚 Loading L2 book snapshots"
+        );
+
+        let mut summary = L2BookSummary::default();
+
+        while hour < end_at {
+            let levels = self.load_hour(hour, &mut summary).await?;
+
+            let next = hour + Duration::hours(1);
+            let cursor = Cursor::hourly(SourceKind::L2Book, Some(DATA_TYPE.to_string()), next);
+            checkpoint
+                .save(&self.budget, Some(cursor), Some(next), levels, summary.bytes)
+                .await?;
+
+            summary.hours += 1;
+            hour = next;
+        }
+
+        info!(
+            "✨ L2 books loaded: {} hours, {} files, {} snapshots, {} levels inserted | Downloaded: {} (~${:.2}) | Rejected lines: {}",
+            summary.hours,
+            summary.files,
+            summary.snapshots,
+            summary.levels_inserted,
+            format_bytes(summary.bytes),
+            self.budget.cost_usd(summary.bytes),
+            summary.rejected_lines
+        );
+
+        Ok(summary)
+    }
+
+    /// Load the coin files of one hour, returning the levels inserted
+    async fn load_hour(&self, hour: DateTime<Utc>, summary: &mut L2BookSummary) -> Result<usize> {
+        let prefix = hour_prefix(hour);
+        let mut files: Vec<(String, String, u64)> = self.objects
+            .list_objects(&prefix)
+            .await?
+            .into_iter()
+            .filter_map(|(key, size)| {
+                let coin = key.strip_prefix(&prefix)?.strip_suffix(".lz4")?.to_string();
+                (self.coins.is_empty() || self.coins.contains(&coin)).then_some((key, coin, size))
+            })
+            .collect();
+        files.sort();
+
+        if files.is_empty() {
+            info!(hour = %hour.format("%Y-%m-%d %H:00"), prefix = %prefix, "No L2 book snapshots published");
+            return Ok(0);
+        }
+
+        let hour_bytes: u64 = files.iter().map(|(_, _, size)| size).sum();
+        self.budget.check(summary.bytes + hour_bytes)?;
+
+        let results: Vec<(usize, usize, usize, u64)> = stream::iter(files)
+            .map(|(key, coin, size)| async move { self.load_file(&key, &coin, size).await })
+            .buffer_unordered(self.concurrency)
+            .try_collect()
+            .await?;
+
+        let mut levels = 0;
+        for (snapshots, inserted, rejected, bytes) in results {
+            summary.files += 1;
+            summary.snapshots += snapshots;
+            summary.levels_inserted += inserted;
+            summary.rejected_lines += rejected;
+            summary.bytes += bytes;
+            levels += inserted;
+        }
+
+        info!(
+            "📚 {} | {} levels inserted | Downloaded so far: {}",
+            hour.format("%Y-%m-%d %H:00"),
+            levels,
+            format_bytes(summary.bytes)
+        );
+
+        Ok(levels)
+    }
+
+    /// Download, parse and insert one coin file. Returns snapshots, levels inserted,
+    /// rejected lines and bytes downloaded.
+    async fn load_file(&self, key: &str, coin: &str, size: u64) -> Result<(usize, usize, usize, u64)> {
+        let Some(object) = self.objects.open_object(key).await? else {
+            warn!(key = %key, "L2 book file disappeared after it was listed");
+            return Ok((0, 0, 0, 0));
+        };
+        let bytes = if object.cached { 0 } else { size };
+
+        let store_name = self.objects.store_name().to_string();
+        let coin = coin.to_string();
+        let parsed = tokio::task::spawn_blocking(move || decode_book(object.reader, &coin, &store_name))
+            .await
+            .map_err(|e| Error::Internal(format!("L2 book decoder panicked: {}", e)))??;
+
+        if let Some(reason) = &parsed.first_reject {
+            warn!(key = %key, rejected = parsed.rejected, first = %reason, "Rejected lines in L2 book file");
+        }
+        counter!("indexer_l2_book_rejected_lines").increment(parsed.rejected as u64);
+
+        let mut inserted = 0;
+        for chunk in parsed.levels.chunks(INSERT_CHUNK_LEVELS) {
+            inserted += self.store.insert_book_levels(chunk).await?;
+        }
+
+        debug!(
+            key = %key,
+            snapshots = parsed.snapshots,
+            levels = parsed.levels.len(),
+            inserted,
+            "Loaded L2 book file"
+        );
+
+        Ok((parsed.snapshots, inserted, parsed.rejected, bytes))
+    }
+}
+
+/// `market_data/YYYYMMDD/H/l2Book/`, the directory of an hour's coin files
+fn hour_prefix(hour: DateTime<Utc>) -> String {
+    format!(
+        "market_data/{:04}{:02}{:02}/{}/{}/",
+        hour.year(),
+        hour.month(),
+        hour.day(),
+        hour.hour(),
+        DATA_TYPE
+    )
+}
+
+fn truncate_hour(date: DateTime<Utc>) -> DateTime<Utc> {
+    date.duration_trunc(Duration::hours(1)).unwrap_or(date)
+}
+
+/// Decompress a coin file and parse each line into book levels. Malformed lines
+/// are counted and skipped.
+fn decode_book(reader: Box<dyn Read + Send>, coin: &str, store_name: &str) -> Result<ParsedBook> {
+    let reader = BufReader::new(lz4_flex::frame::FrameDecoder::new(reader));
+    let mut parsed = ParsedBook {
+        levels: Vec::new(),
+        snapshots: 0,
+        rejected: 0,
+        first_reject: None,
+    };
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| Error::Ingest {
+            source_name: store_name.to_string(),
+            details: format!("Failed to decompress L2 book for {}: {}", coin, e),
+        })?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match parse_snapshot(&line) {
+            Ok(mut levels) => {
+                parsed.snapshots += 1;
+                parsed.levels.append(&mut levels);
+            }
+            Err(e) => {
+                parsed.rejected += 1;
+                parsed.first_reject.get_or_insert_with(|| format!("line {}: {}", index + 1, e));
+            }
+        }
+    }
+
+    Ok(parsed)
+}
+
+#[derive(Debug, Deserialize)]
+struct SnapshotLine {
+    /// Time the snapshot was recorded, as a fallback for the exchange time
+    #[serde(default)]
+    time: Option<String>,
+    raw: RawSnapshot,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawSnapshot {
+    data: BookData,
+}
+
+#[derive(Debug, Deserialize)]
+struct BookData {
+    coin: String,
+    #[serde(default)]
+    time: Option<i64>,
+    /// Bids, then asks, best price first
+    levels: Vec<Vec<RawLevel>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawLevel {
+    px: String,
+    sz: String,
+    n: i32,
+}
+
+/// Parse one `{"time": .., "raw": {"channel": "l2Book", "data": {..}}}` line
+fn parse_snapshot(line: &str) -> Result<Vec<BookLevel>> {
+    let snapshot: SnapshotLine = serde_json::from_str(line)
+        .map_err(|e| Error::Validation(format!("Failed to parse L2 book snapshot: {}", e)))?;
+    let data = snapshot.raw.data;
+
+    let time = match data.time {
+        Some(ms) => Utc.timestamp_millis_opt(ms).single(),
+        None => snapshot.time.as_deref().and_then(parse_node_time),
+    }
+    .ok_or_else(|| Error::Validation("L2 book snapshot without a valid time".to_string()))?;
+
+    if data.levels.len() != 2 {
+        return Err(Error::Validation(format!(
+            "Expected bid and ask levels, got {} sides",
+            data.levels.len()
+        )));
+    }
+
+    let mut levels = Vec::with_capacity(data.levels.iter().map(Vec::len).sum());
+    for (side, side_levels) in [BookSide::Bid, BookSide::Ask].into_iter().zip(data.levels) {
+        for (level, raw) in side_levels.into_iter().enumerate() {
+            levels.push(BookLevel {
+                coin: data.coin.clone(),
+                time,
+                side,
+                level: i16::try_from(level)
+                    .map_err(|_| Error::Validation(format!("Too many L2 book levels: {}", level)))?,
+                price: parse_decimal("px", &raw.px)?,
+                size: parse_decimal("sz", &raw.sz)?,
+                order_count: raw.n,
+            });
+        }
+    }
+
+    Ok(levels)
+}
@@ -0,0 +1,289 @@
+use super::hourly::{self, HourlyObjectStore, ListingCache, ObjectStream};
+use super::parse_pool::ParsePool;
+use super::schema::SchemaRegistry;
+use super::cursor::{Cursor, SourceKind};
+use super::budget::DownloadPlan;
+use super::IngestSource;
+use crate::model::{IngestBatch, PageSummary};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use indexer_core::{Error, Result};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::{debug, instrument};
+
+/// Replays hour files from a local directory that mirrors the layout of the
+/// `hl-mainnet-node-data` bucket (e.g. `<root>/node_fills_by_block/hourly/20250801/13.lz4`).
+pub struct LocalSource {
+    root: PathBuf,
+    schemas: Arc<SchemaRegistry>,
+    parse_pool: ParsePool,
+    listing_cache: ListingCache,
+}
+
+impl LocalSource {
+    pub fn new(
+        root: impl Into<PathBuf>,
+        schemas: Arc<SchemaRegistry>,
+        parse_pool: ParsePool,
+    ) -> Result<Self> {
+        let root = root.into();
+        if !root.is_dir() {
+            return Err(Error::Config(format!(
+                "local source directory '{}' does not exist",
+                root.display()
+            )));
+        }
+
+        Ok(Self {
+            root,
+            schemas,
+            parse_pool,
+            listing_cache: ListingCache::default(),
+        })
+    }
+}
+
+#[async_trait]
+impl HourlyObjectStore for LocalSource {
+    fn store_name(&self) -> &str {
+        "local"
+    }
+
+    fn source_kind(&self) -> SourceKind {
+        SourceKind::Local
+    }
+
+    async fn open_object(&self, key: &str) -> Result<Option<ObjectStream>> {
+        let path = self.root.join(key);
+
+        debug!(path = %path.display(), "Opening local hour file");
+
+        match tokio::fs::File::open(&path).await {
+            Ok(file) => Ok(Some(ObjectStream {
+                reader: Box::new(file.into_std().await),
+                // Plain files carry no ETag
+                etag: None,
+                cached: false,
+            })),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::Ingest {
+                source_name: "local".to_string(),
+                details: format!("Failed to open '{}': {}", path.display(), e),
+            }),
+        }
+    }
+
+    async fn list_objects(&self, prefix: &str) -> Result<HashMap<String, u64>> {
+        let list_error = |dir: &std::path::Path, e: std::io::Error| Error::Ingest {
+            source_name: "local".to_string(),
+            details: format!("Failed to list '{}': {}", dir.display(), e),
+        };
+
+        // Walk subdirectories too, so nested keys are listed like an S3 prefix
+        let mut objects = HashMap::new();
+        let mut pending = vec![prefix.to_string()];
+        while let Some(dir_prefix) = pending.pop() {
+            let dir = self.root.join(&dir_prefix);
+            let mut entries = match tokio::fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(list_error(&dir, e)),
+            };
+
+            while let Some(entry) = entries.next_entry().await.map_err(|e| list_error(&dir, e))? {
+                let metadata = entry.metadata().await.map_err(|e| list_error(&dir, e))?;
+                let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                    continue;
+                };
+                if metadata.is_dir() {
+                    pending.push(format!("{}{}/", dir_prefix, name));
+                } else {
+                    objects.insert(format!("{}{}", dir_prefix, name), metadata.len());
+                }
+            }
+        }
+
+        Ok(objects)
+    }
+
+    async fn list_dirs(&self, prefix: &str) -> Result<Option<Vec<String>>> {
+        let dir = self.root.join(prefix);
+        let list_error = |e: std::io::Error| Error::Ingest {
+            source_name: "local".to_string(),
+            details: format!("Failed to list '{}': {}", dir.display(), e),
+        };
+
+        let mut entries = match tokio::fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Some(Vec::new())),
+            Err(e) => return Err(list_error(e)),
+        };
+
+        let mut dirs = Vec::new();
+        while let Some(entry) = entries.next_entry().await.map_err(list_error)? {
+            if entry.file_type().await.map_err(list_error)?.is_dir() {
+                dirs.extend(entry.file_name().to_str().map(str::to_string));
+            }
+        }
+
+        Ok(Some(dirs))
+    }
+
+    fn listing_cache(&self) -> &ListingCache {
+        &self.listing_cache
+    }
+
+    fn schemas(&self) -> &Arc<SchemaRegistry> {
+        &self.schemas
+    }
+
+    fn parse_pool(&self) -> &ParsePool {
+        &self.parse_pool
+    }
+}
+
+#[async_trait]
+impl IngestSource for LocalSource {
+    #[instrument(skip(self))]
+    async fn fetch_page(
+        &self,
+        start_from: DateTime<Utc>,
+        cursor: Option<Cursor>,
+    ) -> Result<IngestBatch> {
+        hourly::fetch_page(self, start_from, cursor).await
+    }
+
+    async fn stream_page(
+        &self,
+        start_from: DateTime<Utc>,
+        cursor: Option<Cursor>,
+        batch_size: usize,
+        tx: &mpsc::Sender<IngestBatch>,
+    ) -> Result<PageSummary> {
+        hourly::stream_page(self, start_from, cursor, batch_size, tx).await
+    }
+
+    async fn plan_download(&self, hours: &[DateTime<Utc>]) -> Result<Option<DownloadPlan>> {
+        hourly::plan_download(self, hours).await.map(Some)
+    }
+
+    fn hour_cursor(&self, hour: DateTime<Utc>) -> Cursor {
+        hourly::hour_cursor(self, hour)
+    }
+
+    fn source_id(&self) -> &str {
+        "local"
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        if self.root.is_dir() {
+            Ok(())
+        } else {
+            Err(Error::Ingest {
+                source_name: self.source_id().to_string(),
+                details: format!("local directory '{}' is not accessible", self.root.display()),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{HourReport, TradeSide};
+
+    /// One hour file per schema epoch, each holding both sides of one trade
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/hourly");
+
+    fn fixture_source() -> LocalSource {
+        LocalSource::new(FIXTURES, Arc::new(SchemaRegistry::default()), ParsePool::new(Some(1))).unwrap()
+    }
+
+    /// Stream the page starting at `hour` and collect its batches
+    async fn stream_hour(source: &LocalSource, hour: DateTime<Utc>) -> (PageSummary, Vec<IngestBatch>) {
+        let (tx, mut rx) = mpsc::channel(16);
+        let summary = source.stream_page(hour, None, 1000, &tx).await.unwrap();
+        drop(tx);
+
+        let mut batches = Vec::new();
+        while let Some(batch) = rx.recv().await {
+            batches.push(batch);
+        }
+        (summary, batches)
+    }
+
+    /// Pages of the first two epochs run into later epochs' hour files, so the hours
+    /// after their own file are missing upstream and the page reports more data
+    #[tokio::test]
+    async fn streams_fixture_hour_of_every_epoch() {
+        let source = fixture_source();
+        let epochs = [
+            ("2025-04-01T12:00:00Z", "node_trades", "node_trades/hourly/20250401/12.lz4", None, true),
+            ("2025-06-15T12:00:00Z", "node_fills", "node_fills/hourly/20250615/12.lz4", None, true),
+            (
+                "2025-08-01T12:00:00Z",
+                "node_fills_by_block",
+                "node_fills_by_block/hourly/20250801/12.lz4",
+                Some(680000000),
+                false,
+            ),
+        ];
+
+        for (hour, format, key, block, has_more) in epochs {
+            let hour: DateTime<Utc> = hour.parse().unwrap();
+            let (summary, batches) = stream_hour(&source, hour).await;
+
+            let reports: Vec<_> = batches.iter().flat_map(|batch| &batch.hours).collect();
+            let (loaded, missing): (Vec<&HourReport>, Vec<&HourReport>) = reports.iter().partition(|report| report.key == key);
+            assert_eq!(loaded.len(), 1, "{}", key);
+            assert_eq!(loaded[0].hour, hour);
+            assert_eq!(loaded[0].detected_format.as_deref(), Some(format));
+            assert_eq!(loaded[0].status(), "complete");
+            assert_eq!(loaded[0].rejected, 0);
+            assert_eq!(loaded[0].first_block, block);
+            assert!(missing.iter().all(|report| report.status() == "missing_upstream"));
+
+            assert_eq!(summary.has_more, has_more, "{}", key);
+            assert_eq!(summary.fills, 2, "{}", key);
+            assert_eq!(summary.last_timestamp, Some(hour + chrono::Duration::minutes(30)), "{}", key);
+            let next = summary.cursor.expect("page loaded an hour");
+            assert_eq!(next.kind, SourceKind::Local);
+            assert_eq!(next.hour, hour + chrono::Duration::hours(reports.len() as i64), "{}", key);
+
+            let fills: Vec<_> = batches.iter().flat_map(|batch| &batch.fills).collect();
+            assert_eq!(fills.len(), 2, "{}", key);
+            assert!(fills.iter().all(|fill| fill.source_id.as_deref() == Some(key) && fill.block_number == block));
+            assert_eq!(fills.iter().filter(|fill| fill.side == TradeSide::Buy).count(), 1, "{}", key);
+            assert_eq!(fills[0].tid, fills[1].tid, "{}", key);
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_end_of_data_after_last_fixture() {
+        let source = fixture_source();
+        let (summary, batches) = stream_hour(&source, "2025-08-01T13:00:00Z".parse().unwrap()).await;
+
+        assert!(summary.cursor.is_none());
+        assert!(!summary.has_more);
+        assert_eq!(summary.fills, 0);
+        assert!(batches.is_empty());
+    }
+
+    /// The look-ahead stops at the last day directory instead of walking every day
+    /// up to the present
+    #[tokio::test]
+    async fn stops_scanning_at_last_listed_day() {
+        let source = fixture_source();
+        let (summary, _) = stream_hour(&source, "2025-08-01T13:00:00Z".parse().unwrap()).await;
+        assert!(!summary.has_more);
+        assert_eq!(source.listing_cache.len(), 1);
+
+        let hours = ["2025-08-01T13:00:00Z".parse().unwrap()];
+        let plan = source.plan_download(&hours).await.unwrap().unwrap();
+        assert_eq!(plan.unpublished, 1);
+        assert_eq!(source.listing_cache.len(), 1);
+    }
+}
@@ -0,0 +1,230 @@
+use super::hourly::HourlyObjectStore;
+use super::s3_source::S3Source;
+use chrono::{DateTime, DurationRound, Timelike, Utc};
+use futures::stream::{self, StreamExt};
+use indexer_core::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Number of objects downloaded concurrently
+const PARALLEL_DOWNLOADS: usize = 8;
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Save the manifest after this many downloads
+const MANIFEST_SAVE_INTERVAL: usize = 16;
+
+/// Record of every object stored in a local mirror, persisted as `manifest.json`
+/// in the mirror root.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MirrorManifest {
+    pub bucket: String,
+    pub objects: BTreeMap<String, MirrorEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MirrorEntry {
+    pub etag: Option<String>,
+    pub size: u64,
+    pub downloaded_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default)]
+pub struct MirrorSummary {
+    pub downloaded: usize,
+    pub skipped: usize,
+    pub missing: usize,
+    pub failed: usize,
+    pub bytes_downloaded: u64,
+}
+
+enum HourOutcome {
+    Downloaded(String, MirrorEntry),
+    UpToDate,
+    Missing,
+}
+
+/// Downloads raw `.lz4` hour files from S3 into a local directory that `LocalSource`
+/// can replay, so each hour is only paid for once.
+pub struct Mirror {
+    source: S3Source,
+    root: PathBuf,
+    manifest: MirrorManifest,
+}
+
+impl Mirror {
+    pub async fn open(source: S3Source, root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        tokio::fs::create_dir_all(&root).await?;
+
+        let manifest_path = root.join(MANIFEST_FILE);
+        let manifest = match tokio::fs::read(&manifest_path).await {
+            Ok(data) => serde_json::from_slice(&data)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => MirrorManifest {
+                bucket: source.bucket().to_string(),
+                objects: BTreeMap::new(),
+            },
+            Err(e) => return Err(e.into()),
+        };
+
+        if manifest.bucket != source.bucket() {
+            return Err(Error::Config(format!(
+                "mirror at '{}' was created from bucket '{}', not '{}'",
+                root.display(),
+                manifest.bucket,
+                source.bucket()
+            )));
+        }
+
+        info!(
+            dir = %root.display(),
+            objects = manifest.objects.len(),
+            "Opened local mirror"
+        );
+
+        Ok(Self {
+            source,
+            root,
+            manifest,
+        })
+    }
+
+    /// Mirror every hour file in `[start, end)`
+    pub async fn sync_range(&mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<MirrorSummary> {
+        let start = start.duration_trunc(chrono::Duration::hours(1))
+            .map_err(|e| Error::Validation(format!("Invalid mirror start: {}", e)))?;
+
+        let mut hours = Vec::new();
+        let mut hour = start;
+        while hour < end {
+            hours.push(hour);
+            hour += chrono::Duration::hours(1);
+        }
+
+        info!(
+            start = %start,
+            end = %end,
+            hours = hours.len(),
+            "Mirroring hour files"
+        );
+
+        let mut summary = MirrorSummary::default();
+        let source = &self.source;
+        let root = &self.root;
+        let known = self.manifest.objects.clone();
+        let known = &known;
+
+        let mut results = stream::iter(hours)
+            .map(|hour| async move {
+                let keys = match source.schemas().candidate_keys(hour, hour.hour()) {
+                    Ok(keys) => keys,
+                    Err(e) => return (hour.to_string(), Err(e)),
+                };
+
+                // Near schema cutovers the hour may live under a neighbouring prefix
+                for key in &keys {
+                    match mirror_object(source, root, known.get(key), key).await {
+                        Ok(HourOutcome::Missing) => continue,
+                        result => return (key.clone(), result),
+                    }
+                }
+                (keys[0].clone(), Ok(HourOutcome::Missing))
+            })
+            .buffer_unordered(PARALLEL_DOWNLOADS);
+
+        while let Some((key, result)) = results.next().await {
+            match result {
+                Ok(HourOutcome::Downloaded(key, entry)) => {
+                    summary.downloaded += 1;
+                    summary.bytes_downloaded += entry.size;
+                    self.manifest.objects.insert(key, entry);
+
+                    // Persist regularly so an interrupted run doesn't pay for the same objects again
+                    if summary.downloaded % MANIFEST_SAVE_INTERVAL == 0 {
+                        self.save_manifest().await?;
+                    }
+                }
+                Ok(HourOutcome::UpToDate) => summary.skipped += 1,
+                Ok(HourOutcome::Missing) => {
+                    debug!(key = %key, "Object not found in bucket");
+                    summary.missing += 1;
+                }
+                Err(e) => {
+                    warn!(key = %key, error = %e, "Failed to mirror object");
+                    summary.failed += 1;
+                }
+            }
+        }
+        drop(results);
+
+        self.save_manifest().await?;
+
+        info!(
+            downloaded = summary.downloaded,
+            skipped = summary.skipped,
+            missing = summary.missing,
+            failed = summary.failed,
+            "Mirrored {:.1} MB",
+            summary.bytes_downloaded as f64 / (1024.0 * 1024.0)
+        );
+
+        Ok(summary)
+    }
+
+    async fn save_manifest(&self) -> Result<()> {
+        let data = serde_json::to_vec_pretty(&self.manifest)?;
+        let path = self.root.join(MANIFEST_FILE);
+        write_atomic(&path, &data).await
+    }
+}
+
+async fn mirror_object(
+    source: &S3Source,
+    root: &Path,
+    known: Option<&MirrorEntry>,
+    key: &str,
+) -> Result<HourOutcome> {
+    let meta = match source.head_object(key).await? {
+        Some(meta) => meta,
+        None => return Ok(HourOutcome::Missing),
+    };
+
+    let path = root.join(key);
+    if let Some(entry) = known {
+        let on_disk = tokio::fs::metadata(&path).await.map(|m| m.len()).ok();
+        if entry.etag.is_some() && entry.etag == meta.etag && on_disk == Some(meta.size) {
+            return Ok(HourOutcome::UpToDate);
+        }
+    }
+
+    let (data, etag) = match source.download_object(key).await? {
+        Some(object) => object,
+        None => return Ok(HourOutcome::Missing),
+    };
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    write_atomic(&path, &data).await?;
+
+    debug!(key = %key, bytes = data.len(), "Mirrored object");
+
+    Ok(HourOutcome::Downloaded(
+        key.to_string(),
+        MirrorEntry {
+            etag,
+            size: data.len() as u64,
+            downloaded_at: Utc::now(),
+        },
+    ))
+}
+
+/// Write through a temporary file so an interrupted run never leaves a truncated object
+async fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("partial");
+    tokio::fs::write(&tmp, data).await?;
+    tokio::fs::rename(&tmp, path).await?;
+    Ok(())
+}
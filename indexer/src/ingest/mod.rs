@@ -1,9 +1,36 @@
+pub mod api_source;
+pub mod asset_ctxs;
+pub mod budget;
+pub mod composite_source;
+pub mod cursor;
+pub mod explorer_blocks;
+pub mod hourly;
+pub mod l2_book;
+pub mod local_source;
+pub mod mirror;
+pub mod object_cache;
+pub mod parse_pool;
+pub mod rejects;
+pub mod replica_cmds;
 pub mod s3_source;
+pub mod schema;
+pub mod ws_source;
 
-use crate::model::IngestBatch;
+use crate::model::{Checkpoint, IngestBatch, PageSummary};
+use crate::store::Store;
+use budget::{DownloadBudget, DownloadPlan};
+use cursor::Cursor;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use indexer_core::Result;
+use hourly::HourlyObjectStore;
+use indexer_core::config::IngestSourceConfig;
+use indexer_core::{Config, Error, Result};
+use parse_pool::ParsePool;
+use schema::SchemaRegistry;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+use tokio::sync::mpsc;
 
 #[async_trait]
 pub trait IngestSource: Send + Sync {
@@ -11,9 +38,59 @@ pub trait IngestSource: Send + Sync {
     async fn fetch_page(
         &self,
         start_from: DateTime<Utc>,
-        cursor: Option<String>,
+        cursor: Option<Cursor>,
     ) -> Result<IngestBatch>;
 
+    /// Send a page to `tx` as batches of at most `batch_size` fills, so large pages
+    /// are never held in memory at once. Sources that can't stream send the whole
+    /// page as one batch.
+    async fn stream_page(
+        &self,
+        start_from: DateTime<Utc>,
+        cursor: Option<Cursor>,
+        _batch_size: usize,
+        tx: &mpsc::Sender<IngestBatch>,
+    ) -> Result<PageSummary> {
+        let batch = self.fetch_page(start_from, cursor).await?;
+        let summary = PageSummary {
+            cursor: batch.cursor.clone(),
+            has_more: batch.has_more,
+            fills: batch.fills.len(),
+            bytes: batch.bytes_downloaded.unwrap_or(0),
+            last_timestamp: batch.fills.iter().map(|fill| fill.timestamp).max(),
+        };
+
+        tx.send(batch)
+            .await
+            .map_err(|_| Error::Internal("Pipeline channel closed".to_string()))?;
+
+        Ok(summary)
+    }
+
+    /// What loading `hours` would download, for sources that can list their objects
+    async fn plan_download(&self, _hours: &[DateTime<Utc>]) -> Result<Option<DownloadPlan>> {
+        Ok(None)
+    }
+
+    /// Whether downloads from this source are billed to us
+    fn transfer_billed(&self) -> bool {
+        false
+    }
+
+    /// Whether the source can fetch past hours. Live sources only serve recent
+    /// fills, so gaps in the hour manifest are not theirs to fill.
+    fn serves_history(&self) -> bool {
+        true
+    }
+
+    /// Wait between polls once a page reports no more data
+    fn poll_interval(&self) -> Duration {
+        Duration::from_secs(60)
+    }
+
+    /// Cursor that resumes this source at the start of the hour containing `hour`
+    fn hour_cursor(&self, hour: DateTime<Utc>) -> Cursor;
+
     /// Get the source identifier
     fn source_id(&self) -> &str;
 
@@ -21,4 +98,110 @@ pub trait IngestSource: Send + Sync {
     async fn health_check(&self) -> Result<()>;
 }
 
-pub use s3_source::S3Source;
\ No newline at end of file
+pub use api_source::ApiSource;
+pub use composite_source::CompositeSource;
+pub use local_source::LocalSource;
+pub use s3_source::S3Source;
+pub use ws_source::WebSocketSource;
+
+/// The `hyperliquid-archive` bucket, read with the settings of `ingest.source`, or a
+/// local copy of it in `dir`. Also returns whether downloads from it are billed.
+pub async fn open_archive(config: &Config, dir: Option<String>) -> Result<(Arc<dyn HourlyObjectStore>, bool)> {
+    let source = IngestSourceConfig {
+        s3_bucket: config.ingest.archive.s3_bucket.clone(),
+        ..config.ingest.source.clone()
+    };
+    open_bucket(config, &source, dir, "archive").await
+}
+
+/// The node data bucket of `ingest.source` itself, or a local copy of it in `dir`,
+/// for loaders of node data other than fills. Also returns whether downloads from it are billed.
+pub async fn open_node_data(config: &Config, dir: Option<String>) -> Result<(Arc<dyn HourlyObjectStore>, bool)> {
+    open_bucket(config, &config.ingest.source, dir, "node data").await
+}
+
+async fn open_bucket(
+    config: &Config,
+    source: &IngestSourceConfig,
+    dir: Option<String>,
+    name: &str,
+) -> Result<(Arc<dyn HourlyObjectStore>, bool)> {
+    let schemas = Arc::new(SchemaRegistry::from_config(&config.ingest.source)?);
+    let parse_pool = ParsePool::new(config.ingest.parse_workers);
+
+    match dir {
+        Some(dir) => {
+            info!(dir = %dir, "Using local directory as {} source", name);
+            Ok((Arc::new(LocalSource::new(dir, schemas, parse_pool)?), false))
+        }
+        None => Ok((
+            Arc::new(S3Source::new(source, schemas, parse_pool).await?),
+            source.s3_requester_pays,
+        )),
+    }
+}
+
+/// Progress of a loader of data other than fills, checkpointed under its own source
+/// id. Keeps the download totals of earlier runs, so the budget recorded on every
+/// save covers all runs.
+pub struct LoaderCheckpoint {
+    store: Arc<Store>,
+    checkpoint: Checkpoint,
+    previous_download: serde_json::Value,
+}
+
+impl LoaderCheckpoint {
+    /// The progress saved under `source_id`, empty if there is none
+    pub async fn load(store: Arc<Store>, source_id: &str) -> Result<Self> {
+        let checkpoint = store
+            .get_checkpoint(source_id)
+            .await?
+            .unwrap_or_else(|| Checkpoint::new(source_id.to_string()));
+        let previous_download = DownloadBudget::previous(checkpoint.metadata.as_ref());
+
+        Ok(Self {
+            store,
+            checkpoint,
+            previous_download,
+        })
+    }
+
+    /// Where the last run stopped
+    pub fn cursor(&self) -> Option<&Cursor> {
+        self.checkpoint.cursor.as_ref()
+    }
+
+    /// Save progress after a unit of work. `cursor` and `last_record_ts` replace the
+    /// saved ones unless `None`, `inserted` adds to the records processed, and
+    /// `run_bytes`, what this run downloaded so far, is recorded against `budget`.
+    pub async fn save(
+        &mut self,
+        budget: &DownloadBudget,
+        cursor: Option<Cursor>,
+        last_record_ts: Option<DateTime<Utc>>,
+        inserted: usize,
+        run_bytes: u64,
+    ) -> Result<()> {
+        let checkpoint = &mut self.checkpoint;
+        if cursor.is_some() {
+            checkpoint.cursor = cursor;
+        }
+        checkpoint.last_record_ts = last_record_ts.or(checkpoint.last_record_ts);
+        checkpoint.records_processed += inserted as i64;
+        checkpoint.metadata = Some(budget.record(
+            checkpoint.metadata.as_ref(),
+            &self.previous_download,
+            run_bytes,
+            false,
+        ));
+
+        self.store.save_checkpoint(checkpoint).await
+    }
+}
+
+/// Whether a file of a loader whose files are named by the first block they hold is
+/// loaded already: it is once the next file starts at or below `after`, the highest
+/// block loaded
+pub fn file_finished(next_start: Option<i64>, after: Option<i64>) -> bool {
+    after.is_some_and(|after| next_start.is_some_and(|next| next <= after))
+}
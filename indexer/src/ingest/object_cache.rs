@@ -0,0 +1,327 @@
+use indexer_core::{Error, Result};
+use metrics::{counter, gauge};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info, warn};
+
+const OBJECTS_DIR: &str = "objects";
+
+const LISTINGS_DIR: &str = "listings";
+
+/// Suffix of objects still being downloaded; left over ones are removed on open
+const PARTIAL_SUFFIX: &str = ".partial";
+
+/// An object as listed by the bucket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListedObject {
+    pub size: u64,
+    pub etag: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CachedObject {
+    size: u64,
+    last_used: SystemTime,
+}
+
+#[derive(Debug, Default)]
+struct CacheIndex {
+    /// Keyed by bucket key and sanitized ETag
+    objects: HashMap<(String, String), CachedObject>,
+    total_bytes: u64,
+}
+
+/// On-disk cache of compressed objects downloaded from one bucket, stored as
+/// `objects/<bucket>/<key>/<etag>` so a changed object never matches a stale copy.
+/// Least recently used objects are evicted once the cache outgrows `max_bytes`.
+/// Day listings are kept under `listings/<bucket>/` so offline runs can plan pages.
+#[derive(Debug)]
+pub struct ObjectCache {
+    objects_root: PathBuf,
+    listings_root: PathBuf,
+    max_bytes: u64,
+    index: Mutex<CacheIndex>,
+}
+
+impl ObjectCache {
+    /// Open the cache under `root`, indexing the objects already stored there
+    pub fn open(root: impl AsRef<Path>, bucket: &str, max_bytes: u64) -> Result<Self> {
+        let objects_root = root.as_ref().join(OBJECTS_DIR).join(bucket);
+        let listings_root = root.as_ref().join(LISTINGS_DIR).join(bucket);
+        std::fs::create_dir_all(&objects_root)?;
+        std::fs::create_dir_all(&listings_root)?;
+
+        let mut index = CacheIndex::default();
+        index_dir(&objects_root, &objects_root, &mut index)?;
+
+        info!(
+            dir = %objects_root.display(),
+            objects = index.objects.len(),
+            "Opened object cache with {:.1} MB of {:.1} MB",
+            index.total_bytes as f64 / (1024.0 * 1024.0),
+            max_bytes as f64 / (1024.0 * 1024.0)
+        );
+        gauge!("indexer_object_cache_bytes").set(index.total_bytes as f64);
+
+        let cache = Self {
+            objects_root,
+            listings_root,
+            max_bytes,
+            index: Mutex::new(index),
+        };
+        cache.evict(None);
+
+        Ok(cache)
+    }
+
+    /// Path of the cached copy of `key` with `etag`, counting a hit or a miss. Without
+    /// an ETag there's nothing to match, so it's always a miss.
+    pub fn get(&self, key: &str, etag: Option<&str>) -> Option<PathBuf> {
+        let etag = etag.map(sanitize_etag);
+        let hit = etag.as_ref().is_some_and(|etag| self.touch(key, etag));
+        if hit {
+            counter!("indexer_object_cache_hits").increment(1);
+        } else {
+            counter!("indexer_object_cache_misses").increment(1);
+        }
+
+        etag.filter(|_| hit).map(|etag| self.object_path(key, &etag))
+    }
+
+    /// Most recently used copy of `key` whatever its ETag, for when the bucket
+    /// can't be asked which one is current
+    pub fn latest(&self, key: &str) -> Option<PathBuf> {
+        let etag = {
+            let index = self.index.lock().unwrap();
+            index.objects.iter()
+                .filter(|((cached_key, _), _)| cached_key == key)
+                .max_by_key(|(_, object)| object.last_used)
+                .map(|((_, etag), _)| etag.clone())
+        };
+
+        match etag {
+            Some(etag) if self.touch(key, &etag) => {
+                counter!("indexer_object_cache_hits").increment(1);
+                Some(self.object_path(key, &etag))
+            }
+            _ => {
+                counter!("indexer_object_cache_misses").increment(1);
+                None
+            }
+        }
+    }
+
+    /// Whether any copy of `key` is cached
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.index.lock().unwrap().objects.keys().any(|(cached_key, _)| cached_key == key)
+    }
+
+    /// Start caching `key` with `etag` as it downloads. Nothing is visible in the
+    /// cache until the writer is finished.
+    pub async fn writer(self: &Arc<Self>, key: &str, etag: &str) -> Result<CacheWriter> {
+        let etag = sanitize_etag(etag);
+        let path = self.object_path(key, &etag);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let mut partial = path.into_os_string();
+        partial.push(PARTIAL_SUFFIX);
+        let partial = PathBuf::from(partial);
+
+        Ok(CacheWriter {
+            file: tokio::fs::File::create(&partial).await?,
+            cache: Arc::clone(self),
+            key: key.to_string(),
+            etag,
+            partial,
+            size: 0,
+        })
+    }
+
+    /// Store the listing of `prefix` for offline runs
+    pub async fn save_listing(&self, prefix: &str, objects: &BTreeMap<String, ListedObject>) -> Result<()> {
+        let path = self.listing_path(prefix);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let tmp = path.with_extension("partial");
+        tokio::fs::write(&tmp, serde_json::to_vec(objects)?).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    /// Listing of `prefix` saved by an earlier online run, if any
+    pub async fn load_listing(&self, prefix: &str) -> Result<Option<BTreeMap<String, ListedObject>>> {
+        match tokio::fs::read(self.listing_path(prefix)).await {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn insert(&self, key: &str, etag: &str, size: u64) {
+        {
+            let mut index = self.index.lock().unwrap();
+            let previous = index.objects.insert(
+                (key.to_string(), etag.to_string()),
+                CachedObject {
+                    size,
+                    last_used: SystemTime::now(),
+                },
+            );
+            index.total_bytes = index.total_bytes - previous.map_or(0, |object| object.size) + size;
+        }
+
+        debug!(key = %key, etag = %etag, bytes = size, "Cached object");
+        self.evict(Some((key, etag)));
+    }
+
+    fn object_path(&self, key: &str, etag: &str) -> PathBuf {
+        self.objects_root.join(key).join(etag)
+    }
+
+    fn listing_path(&self, prefix: &str) -> PathBuf {
+        self.listings_root.join(format!("{}.json", prefix.trim_end_matches('/')))
+    }
+
+    /// Mark a cached object as used now, in the index and in its mtime so recency
+    /// survives restarts
+    fn touch(&self, key: &str, etag: &str) -> bool {
+        let now = SystemTime::now();
+        {
+            let mut index = self.index.lock().unwrap();
+            match index.objects.get_mut(&(key.to_string(), etag.to_string())) {
+                Some(object) => object.last_used = now,
+                None => return false,
+            }
+        }
+
+        let path = self.object_path(key, etag);
+        if let Err(e) = std::fs::File::options().write(true).open(&path).and_then(|file| file.set_modified(now)) {
+            debug!(path = %path.display(), error = %e, "Failed to update cached object mtime");
+        }
+        true
+    }
+
+    /// Remove least recently used objects until the cache fits, never `keep`
+    fn evict(&self, keep: Option<(&str, &str)>) {
+        let mut evicted = Vec::new();
+        {
+            let mut index = self.index.lock().unwrap();
+            while index.total_bytes > self.max_bytes {
+                let victim = index.objects.iter()
+                    .filter(|((key, etag), _)| keep != Some((key.as_str(), etag.as_str())))
+                    .min_by_key(|(_, object)| object.last_used)
+                    .map(|(id, _)| id.clone());
+
+                let Some(victim) = victim else { break };
+                if let Some(object) = index.objects.remove(&victim) {
+                    index.total_bytes -= object.size;
+                    evicted.push(victim);
+                }
+            }
+            gauge!("indexer_object_cache_bytes").set(index.total_bytes as f64);
+        }
+
+        for (key, etag) in evicted {
+            let path = self.object_path(&key, &etag);
+            if let Err(e) = std::fs::remove_file(&path) {
+                warn!(path = %path.display(), error = %e, "Failed to evict cached object");
+            }
+            counter!("indexer_object_cache_evictions").increment(1);
+            debug!(key = %key, etag = %etag, "Evicted cached object");
+        }
+    }
+}
+
+/// An object being written into the cache while it downloads
+pub struct CacheWriter {
+    cache: Arc<ObjectCache>,
+    key: String,
+    etag: String,
+    partial: PathBuf,
+    file: tokio::fs::File,
+    size: u64,
+}
+
+impl CacheWriter {
+    pub async fn write(&mut self, chunk: &[u8]) -> Result<()> {
+        self.file.write_all(chunk).await?;
+        self.size += chunk.len() as u64;
+        Ok(())
+    }
+
+    /// Move the complete object into the cache, evicting older objects if needed
+    pub async fn finish(mut self) -> Result<()> {
+        self.file.flush().await?;
+        drop(self.file);
+
+        tokio::fs::rename(&self.partial, self.cache.object_path(&self.key, &self.etag)).await?;
+        self.cache.insert(&self.key, &self.etag, self.size);
+        Ok(())
+    }
+
+    /// Drop the partial object, e.g. after the download failed
+    pub async fn abort(self) {
+        drop(self.file);
+        if let Err(e) = tokio::fs::remove_file(&self.partial).await {
+            debug!(path = %self.partial.display(), error = %e, "Failed to remove partial cached object");
+        }
+    }
+}
+
+/// Walk `dir` and add every complete object under `root` to `index`
+fn index_dir(root: &Path, dir: &Path, index: &mut CacheIndex) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let metadata = entry.metadata()?;
+
+        if metadata.is_dir() {
+            index_dir(root, &path, index)?;
+            continue;
+        }
+
+        // Interrupted downloads
+        if path.to_string_lossy().ends_with(PARTIAL_SUFFIX) {
+            std::fs::remove_file(&path)?;
+            continue;
+        }
+
+        let (Some(key), Some(etag)) = (
+            path.parent().and_then(|parent| parent.strip_prefix(root).ok()),
+            path.file_name(),
+        ) else {
+            continue;
+        };
+
+        let key = key.to_str().ok_or_else(|| {
+            Error::Internal(format!("Non UTF-8 path in object cache: {}", path.display()))
+        })?;
+
+        index.total_bytes += metadata.len();
+        index.objects.insert(
+            (key.to_string(), etag.to_string_lossy().into_owned()),
+            CachedObject {
+                size: metadata.len(),
+                last_used: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            },
+        );
+    }
+
+    Ok(())
+}
+
+/// ETags come quoted and may contain `-`; keep them safe as file names
+fn sanitize_etag(etag: &str) -> String {
+    etag.trim_matches('"')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect()
+}
@@ -0,0 +1,62 @@
+use indexer_core::{Error, Result};
+use metrics::{gauge, histogram};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Semaphore;
+
+/// Bounded set of blocking threads for CPU-heavy ingest work. Jobs run on
+/// `spawn_blocking` so they never stall the async runtime, and at most `workers`
+/// of them run at once, however many downloads are in flight.
+#[derive(Debug, Clone)]
+pub struct ParsePool {
+    permits: Arc<Semaphore>,
+    workers: usize,
+}
+
+impl ParsePool {
+    /// Pool with `workers` threads, or one per CPU core if unset
+    pub fn new(workers: Option<usize>) -> Self {
+        let workers = workers
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()))
+            .max(1);
+
+        Self {
+            permits: Arc::new(Semaphore::new(workers)),
+            workers,
+        }
+    }
+
+    /// Jobs that can run at the same time
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    /// Run `job` on a pool thread once one is free. `stage` labels the queue time,
+    /// busy workers and job duration metrics.
+    pub async fn run<T, F>(&self, stage: &'static str, job: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let queued = Instant::now();
+        let permit = Arc::clone(&self.permits)
+            .acquire_owned()
+            .await
+            .map_err(|_| Error::Internal("Parse pool closed".to_string()))?;
+        histogram!("indexer_stage_queue_ms", "stage" => stage).record(queued.elapsed().as_millis() as f64);
+
+        gauge!("indexer_stage_busy_workers", "stage" => stage).increment(1.0);
+        let started = Instant::now();
+
+        let result = tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            job()
+        })
+        .await;
+
+        gauge!("indexer_stage_busy_workers", "stage" => stage).decrement(1.0);
+        histogram!("indexer_stage_duration_ms", "stage" => stage).record(started.elapsed().as_millis() as f64);
+
+        result.map_err(|e| Error::Internal(format!("{} worker panicked: {}", stage, e)))
+    }
+}
@@ -0,0 +1,95 @@
+use super::schema::{parser_by_name, SchemaRegistry};
+use crate::model::{Fill, StoredReject};
+use crate::store::Store;
+use indexer_core::{Error, Result};
+use tracing::{debug, info};
+
+/// Rejects loaded per query
+const PAGE_SIZE: i64 = 1000;
+
+#[derive(Debug, Default)]
+pub struct ReprocessSummary {
+    pub examined: usize,
+    pub resolved: usize,
+    pub still_failing: usize,
+    pub fills_inserted: usize,
+}
+
+/// Run unresolved rows of `fill_rejects` through the current parsers and schema
+/// again. Rejects whose fills can now be stored are inserted and marked resolved;
+/// the rest stay in the table.
+pub async fn reprocess_rejects(
+    store: &Store,
+    schemas: &SchemaRegistry,
+    limit: Option<usize>,
+) -> Result<ReprocessSummary> {
+    let mut summary = ReprocessSummary::default();
+    let mut after_id = 0;
+
+    loop {
+        let remaining = limit.map_or(PAGE_SIZE, |limit| (limit - summary.examined) as i64);
+        if remaining <= 0 {
+            break;
+        }
+
+        let page = store.get_unresolved_rejects(after_id, remaining.min(PAGE_SIZE)).await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        after_id = last.id;
+
+        let mut fills = Vec::new();
+        let mut resolved = Vec::new();
+
+        for reject in &page {
+            summary.examined += 1;
+            match reparse(schemas, reject) {
+                Ok(reparsed) => {
+                    fills.extend(reparsed);
+                    resolved.push(reject.id);
+                }
+                Err(e) => {
+                    debug!(id = reject.id, error = %e, "Reject still fails");
+                    summary.still_failing += 1;
+                }
+            }
+        }
+
+        Fill::assign_dup_seqs(&mut fills);
+        summary.fills_inserted += store.insert_fills(&fills).await?;
+        store.resolve_rejects(&resolved).await?;
+        summary.resolved += resolved.len();
+    }
+
+    info!(
+        examined = summary.examined,
+        resolved = summary.resolved,
+        still_failing = summary.still_failing,
+        fills_inserted = summary.fills_inserted,
+        "Re-processed rejects"
+    );
+
+    Ok(summary)
+}
+
+/// Parse a reject again. Succeeds only if every fill of the line can be stored.
+fn reparse(schemas: &SchemaRegistry, reject: &StoredReject) -> Result<Vec<Fill>> {
+    let fills = match (&reject.raw_line, &reject.payload) {
+        (Some(line), _) => {
+            let parser = schemas
+                .detect_format(line.as_bytes())
+                .or_else(|| reject.schema_format.as_deref().and_then(parser_by_name))
+                .ok_or_else(|| Error::Validation("Unrecognized record format".to_string()))?;
+
+            parser.parse_line(line)?.into_iter().collect::<Result<Vec<_>>>()?
+        }
+        (None, Some(payload)) => vec![serde_json::from_value::<Fill>(payload.clone())?],
+        (None, None) => return Err(Error::Validation("Reject has no content".to_string())),
+    };
+
+    if let Some(reason) = fills.iter().find_map(Fill::numeric_overflow) {
+        return Err(Error::Validation(reason));
+    }
+
+    Ok(fills)
+}
@@ -0,0 +1,486 @@
+use super::budget::{format_bytes, DownloadBudget};
+use super::cursor::{Cursor, SourceKind};
+use super::hourly::HourlyObjectStore;
+use super::{file_finished, open_node_data, LoaderCheckpoint};
+use super::schema::{parse_decimal, parse_node_time};
+use crate::model::OrderAction;
+use crate::store::Store;
+use chrono::{DateTime, NaiveDate, Utc};
+use indexer_core::{Config, Error, Result};
+use metrics::counter;
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// Checkpoint source of the replica_cmds loader
+pub const SOURCE_ID: &str = "replica_cmds";
+
+/// Directory of the `<session>/<date>/<height>` block files in the node data bucket
+const PREFIX: &str = "replica_cmds/";
+
+/// Actions sent to the store at once; batches always end on a block boundary
+const BATCH_ACTIONS: usize = 5_000;
+
+/// Loads the L1 action stream under `replica_cmds/` of the node data bucket into
+/// `order_actions`. Files are read in height order and streamed in batches that end
+/// on a block; the highest block stored is checkpointed after every batch under its
+/// own source id, so an interrupted run resumes inside the file it stopped in.
+pub struct ReplicaCmdsLoader {
+    objects: Arc<dyn HourlyObjectStore>,
+    store: Arc<Store>,
+    budget: DownloadBudget,
+}
+
+/// Files, blocks and actions one run loaded, and what it downloaded
+#[derive(Debug, Default)]
+pub struct ReplicaCmdsSummary {
+    pub files: usize,
+    pub blocks: usize,
+    pub actions: usize,
+    pub actions_inserted: usize,
+    pub rejected_lines: usize,
+    pub bytes: u64,
+}
+
+/// A block file, e.g. `replica_cmds/<session>/20250727/666200000`. Node restarts start a
+/// new session directory, so sessions can overlap in height.
+struct CmdFile {
+    key: String,
+    size: u64,
+    date: NaiveDate,
+    height: u64,
+}
+
+/// Actions of consecutive blocks of one file, up to and including `last_block`
+struct ActionBatch {
+    actions: Vec<OrderAction>,
+    blocks: usize,
+    last_block: i64,
+    last_time: Option<DateTime<Utc>>,
+}
+
+/// Lines of a file that failed to parse
+#[derive(Default)]
+struct Rejects {
+    count: usize,
+    first: Option<String>,
+}
+
+impl ReplicaCmdsLoader {
+    /// Loader over `replica_cmds/` of the bucket [`open_node_data`] opens
+    pub async fn from_config(config: &Config, store: Arc<Store>, dir: Option<String>) -> Result<Self> {
+        let (objects, billed) = open_node_data(config, dir).await?;
+
+        Ok(Self {
+            objects,
+            store,
+            budget: DownloadBudget::new(&config.ingest, billed),
+        })
+    }
+
+    /// Load the files dated from the day of `start_from` up to `end_at`, resuming after
+    /// the highest block checkpointed
+    pub async fn run(&self, start_from: DateTime<Utc>, end_at: DateTime<Utc>) -> Result<ReplicaCmdsSummary> {
+        let mut checkpoint = LoaderCheckpoint::load(Arc::clone(&self.store), SOURCE_ID).await?;
+
+        let first_day = start_from.date_naive();
+        let day_start = first_day.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        let files: Vec<CmdFile> = self.list_files()
+            .await?
+            .into_iter()
+            .filter(|file| file.date >= first_day && file.date.and_hms_opt(0, 0, 0).unwrap().and_utc() < end_at)
+            .collect();
+
+        // Highest block already loaded; blocks up to it are skipped
+        let mut after = checkpoint
+            .cursor()
+            .filter(|cursor| cursor.hour >= day_start && cursor.hour < end_at)
+            .and_then(|cursor| cursor.block);
+        if let Some(block) = after {
+            info!("📍 Resuming replica_cmds from checkpoint after block {}", block);
+        }
+
+        info!(
+            start = %first_day,
+            end = %end_at,
+            files = files.len(),
+            "📜 Loading replica_cmds actions"
+        );
+
+        let mut summary = ReplicaCmdsSummary::default();
+
+        for (index, file) in files.iter().enumerate() {
+            let next = files.get(index + 1).map(|next| next.height as i64);
+            if file_finished(next, after) {
+                continue;
+            }
+
+            self.budget.check(summary.bytes + file.size)?;
+            let Some(object) = self.objects.open_object(&file.key).await? else {
+                warn!(key = %file.key, "replica_cmds file disappeared after it was listed");
+                continue;
+            };
+            if !object.cached {
+                summary.bytes += file.size;
+            }
+
+            let (sender, mut batches) = mpsc::channel(2);
+            let decoder = {
+                let key = file.key.clone();
+                let store_name = self.objects.store_name().to_string();
+                tokio::task::spawn_blocking(move || decode_actions(object.reader, &key, &store_name, after, sender))
+            };
+
+            let mut file_actions = 0;
+            let mut file_inserted = 0;
+            while let Some(batch) = batches.recv().await {
+                let inserted = self.store.insert_order_actions(&batch.actions).await?;
+
+                summary.blocks += batch.blocks;
+                summary.actions += batch.actions.len();
+                summary.actions_inserted += inserted;
+                file_actions += batch.actions.len();
+                file_inserted += inserted;
+
+                after = Some(batch.last_block);
+                let mut cursor = Cursor::hourly(
+                    SourceKind::ReplicaCmds,
+                    Some(SOURCE_ID.to_string()),
+                    batch.last_time.unwrap_or_else(|| file.date.and_hms_opt(0, 0, 0).unwrap().and_utc()),
+                );
+                cursor.block = Some(batch.last_block);
+                checkpoint
+                    .save(&self.budget, Some(cursor), batch.last_time, inserted, summary.bytes)
+                    .await?;
+            }
+
+            let rejects = decoder
+                .await
+                .map_err(|e| Error::Internal(format!("replica_cmds decoder panicked: {}", e)))??;
+            if let Some(reason) = &rejects.first {
+                warn!(key = %file.key, rejected = rejects.count, first = %reason, "Rejected lines in replica_cmds file");
+            }
+            counter!("indexer_order_action_rejected_lines").increment(rejects.count as u64);
+
+            summary.files += 1;
+            summary.rejected_lines += rejects.count;
+
+            info!(
+                "📜 {} | {} actions, {} inserted | Downloaded so far: {}",
+                file.key,
+                file_actions,
+                file_inserted,
+                format_bytes(summary.bytes)
+            );
+        }
+
+        info!(
+            "✨ replica_cmds loaded: {} files, {} blocks, {} actions, {} inserted | Downloaded: {} (~${:.2}) | Rejected lines: {}",
+            summary.files,
+            summary.blocks,
+            summary.actions,
+            summary.actions_inserted,
+            format_bytes(summary.bytes),
+            self.budget.cost_usd(summary.bytes),
+            summary.rejected_lines
+        );
+
+        Ok(summary)
+    }
+
+    /// Block files in the bucket in height order. Keys not shaped `<session>/<YYYYMMDD>/<height>` are ignored.
+    async fn list_files(&self) -> Result<Vec<CmdFile>> {
+        let mut files: Vec<CmdFile> = self.objects
+            .list_objects(PREFIX)
+            .await?
+            .into_iter()
+            .filter_map(|(key, size)| {
+                let path = key.strip_prefix(PREFIX)?;
+                let path = path.strip_suffix(".lz4").unwrap_or(path);
+                let mut parts = path.rsplit('/');
+                let height = parts.next()?.parse().ok()?;
+                let date = NaiveDate::parse_from_str(parts.next()?, "%Y%m%d").ok()?;
+                parts.next()?;
+                Some(CmdFile { key, size, date, height })
+            })
+            .collect();
+        files.sort_by(|a, b| (a.height, &a.key).cmp(&(b.height, &b.key)));
+
+        Ok(files)
+    }
+}
+
+/// Read a file's block lines, skipping blocks up to `after`, and send their actions in
+/// batches. Malformed lines are counted and skipped.
+fn decode_actions(
+    reader: Box<dyn Read + Send>,
+    key: &str,
+    store_name: &str,
+    after: Option<i64>,
+    sender: mpsc::Sender<ActionBatch>,
+) -> Result<Rejects> {
+    let reader: Box<dyn Read + Send> = if key.ends_with(".lz4") {
+        Box::new(lz4_flex::frame::FrameDecoder::new(reader))
+    } else {
+        reader
+    };
+
+    let mut rejects = Rejects::default();
+    let mut batch = ActionBatch {
+        actions: Vec::new(),
+        blocks: 0,
+        last_block: 0,
+        last_time: None,
+    };
+
+    for (index, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line.map_err(|e| Error::Ingest {
+            source_name: store_name.to_string(),
+            details: format!("Failed to read '{}': {}", key, e),
+        })?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (block_number, block_time, mut actions) = match parse_block(&line) {
+            Ok(block) => block,
+            Err(e) => {
+                rejects.count += 1;
+                rejects.first.get_or_insert_with(|| format!("line {}: {}", index + 1, e));
+                continue;
+            }
+        };
+        if after.is_some_and(|after| block_number <= after) {
+            continue;
+        }
+
+        batch.actions.append(&mut actions);
+        batch.blocks += 1;
+        batch.last_block = block_number;
+        batch.last_time = block_time;
+
+        if batch.actions.len() >= BATCH_ACTIONS {
+            let full = std::mem::replace(&mut batch, ActionBatch {
+                actions: Vec::new(),
+                blocks: 0,
+                last_block: 0,
+                last_time: None,
+            });
+            if sender.blocking_send(full).is_err() {
+                // The loader stopped on an error of its own
+                return Ok(rejects);
+            }
+        }
+    }
+
+    if batch.blocks > 0 {
+        let _ = sender.blocking_send(batch);
+    }
+
+    debug!(key = %key, rejected = rejects.count, "Parsed replica_cmds file");
+    Ok(rejects)
+}
+
+/// Parse one block line, `{"abci_block": {"round", "time", "signed_action_bundles"}, "resps": {"Full": ..}}`,
+/// into its block number, time and actions. Responses are matched to actions by
+/// position and give the user each action was attributed to and its outcome.
+fn parse_block(line: &str) -> Result<(i64, Option<DateTime<Utc>>, Vec<OrderAction>)> {
+    let value: Value = serde_json::from_str(line)
+        .map_err(|e| Error::Validation(format!("Failed to parse replica_cmds block: {}", e)))?;
+    let block = &value["abci_block"];
+
+    let block_number = block["round"]
+        .as_i64()
+        .ok_or_else(|| Error::Validation("replica_cmds block without a round".to_string()))?;
+    let block_time = block["time"].as_str().and_then(parse_node_time);
+
+    let bundles = block["signed_action_bundles"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let responses = &value["resps"]["Full"];
+
+    let mut actions = Vec::new();
+    let mut tx_index = 0;
+    for (bundle_index, bundle) in bundles.iter().enumerate() {
+        let signed_actions = bundle[1]["signed_actions"].as_array().map(Vec::as_slice).unwrap_or_default();
+        let bundle_responses = &responses[bundle_index][1];
+
+        for (action_index, signed) in signed_actions.iter().enumerate() {
+            let response = &bundle_responses[action_index];
+            let base = OrderAction {
+                block_number,
+                block_time,
+                tx_index,
+                item_index: 0,
+                user_address: text(&response["user"]),
+                vault_address: text(&signed["vaultAddress"]),
+                action_type: text(&signed["action"]["type"]).unwrap_or_else(|| "unknown".to_string()),
+                asset: None,
+                oid: None,
+                cloid: None,
+                is_buy: None,
+                price: None,
+                size: None,
+                reduce_only: None,
+                order_type: None,
+                status: None,
+                error: None,
+                details: None,
+            };
+
+            expand_action(base, &signed["action"], &response["res"], &mut actions);
+            tx_index += 1;
+        }
+    }
+
+    Ok((block_number, block_time, actions))
+}
+
+/// Split one action into rows: one per order, cancel or modify of the order family,
+/// one for any other type
+fn expand_action(base: OrderAction, action: &Value, res: &Value, actions: &mut Vec<OrderAction>) {
+    let statuses = &res["response"]["data"]["statuses"];
+    let (status, error) = outcome(res);
+    let base = OrderAction { status, error, ..base };
+
+    let items = |name: &str| action[name].as_array().map(Vec::as_slice).unwrap_or_default().iter().enumerate();
+
+    match base.action_type.as_str() {
+        "order" => {
+            for (index, order) in items("orders") {
+                actions.push(with_status(order_row(&base, index, order, None), &statuses[index]));
+            }
+        }
+        "cancel" => {
+            for (index, cancel) in items("cancels") {
+                let row = OrderAction {
+                    item_index: index as i32,
+                    asset: int(&cancel["a"]),
+                    oid: cancel["o"].as_i64(),
+                    ..base.clone()
+                };
+                actions.push(with_status(row, &statuses[index]));
+            }
+        }
+        "cancelByCloid" => {
+            for (index, cancel) in items("cancels") {
+                let row = OrderAction {
+                    item_index: index as i32,
+                    asset: int(&cancel["asset"]),
+                    cloid: text(&cancel["cloid"]),
+                    ..base.clone()
+                };
+                actions.push(with_status(row, &statuses[index]));
+            }
+        }
+        "modify" => {
+            actions.push(order_row(&base, 0, &action["order"], action["oid"].as_i64()));
+        }
+        "batchModify" => {
+            for (index, modify) in items("modifies") {
+                actions.push(with_status(
+                    order_row(&base, index, &modify["order"], modify["oid"].as_i64()),
+                    &statuses[index],
+                ));
+            }
+        }
+        "twapOrder" => {
+            let twap = &action["twap"];
+            let status = &res["response"]["data"]["status"];
+            actions.push(OrderAction {
+                asset: int(&twap["a"]),
+                is_buy: twap["b"].as_bool(),
+                size: decimal(&twap["s"]),
+                reduce_only: twap["r"].as_bool(),
+                oid: status["running"]["twapId"].as_i64(),
+                details: Some(twap.clone()),
+                ..base
+            });
+        }
+        "twapCancel" => {
+            actions.push(OrderAction {
+                asset: int(&action["a"]),
+                oid: action["t"].as_i64(),
+                ..base
+            });
+        }
+        _ => {
+            actions.push(OrderAction {
+                asset: int(&action["asset"]),
+                details: Some(action.clone()),
+                ..base
+            });
+        }
+    }
+}
+
+/// Row for an order `{"a", "b", "p", "s", "r", "t", "c"}` placed new or as a modify of `oid`
+fn order_row(base: &OrderAction, index: usize, order: &Value, oid: Option<i64>) -> OrderAction {
+    let order_type = match &order["t"] {
+        t if t["limit"].is_object() => text(&t["limit"]["tif"]),
+        t if t["trigger"].is_object() => Some("Trigger".to_string()),
+        _ => None,
+    };
+
+    OrderAction {
+        item_index: index as i32,
+        asset: int(&order["a"]),
+        oid,
+        cloid: text(&order["c"]),
+        is_buy: order["b"].as_bool(),
+        price: decimal(&order["p"]),
+        size: decimal(&order["s"]),
+        reduce_only: order["r"].as_bool(),
+        order_type,
+        ..base.clone()
+    }
+}
+
+/// Apply one entry of an action's `statuses`: `"success"`, `{"resting": {"oid"}}`,
+/// `{"filled": {"oid", ..}}` or `{"error": ".."}`
+fn with_status(row: OrderAction, status: &Value) -> OrderAction {
+    match status {
+        Value::String(status) => OrderAction { status: Some(status.clone()), ..row },
+        Value::Object(map) => match map.iter().next() {
+            Some((name, detail)) => OrderAction {
+                status: Some(name.clone()),
+                oid: row.oid.or_else(|| detail["oid"].as_i64()),
+                error: detail.as_str().filter(|_| name == "error").map(str::to_string).or(row.error),
+                ..row
+            },
+            None => row,
+        },
+        _ => row,
+    }
+}
+
+/// Status of a whole response, `{"status": "ok" | "err", "response": ..}`, and its error
+fn outcome(res: &Value) -> (Option<String>, Option<String>) {
+    let status = text(&res["status"]);
+    let error = match status.as_deref() {
+        Some("err") => Some(match &res["response"] {
+            Value::String(message) => message.clone(),
+            other => other.to_string(),
+        }),
+        _ => None,
+    };
+    (status, error)
+}
+
+fn text(value: &Value) -> Option<String> {
+    value.as_str().map(str::to_string)
+}
+
+fn int(value: &Value) -> Option<i32> {
+    value.as_i64().and_then(|value| i32::try_from(value).ok())
+}
+
+/// A decimal given as a string or a number; malformed values are dropped
+fn decimal(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::String(value) => parse_decimal("decimal", value).ok(),
+        Value::Number(value) => parse_decimal("decimal", &value.to_string()).ok(),
+        _ => None,
+    }
+}
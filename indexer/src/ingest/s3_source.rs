@@ -1,114 +1,178 @@
+use super::hourly::{self, ChannelReader, HourlyObjectStore, ListingCache, ObjectStream};
+use super::object_cache::{ListedObject, ObjectCache};
+use super::parse_pool::ParsePool;
+use super::schema::SchemaRegistry;
+use super::cursor::{Cursor, SourceKind};
+use super::budget::{DownloadPlan, BYTES_PER_GB};
 use super::IngestSource;
-use crate::model::{Fill, IngestBatch, TradeSide};
+use crate::model::{IngestBatch, PageSummary};
 use async_trait::async_trait;
 use aws_sdk_s3::Client as S3Client;
-use aws_sdk_s3::operation::get_object::GetObjectError;
-use chrono::{DateTime, NaiveDate, Timelike, Utc};
+use aws_sdk_s3::operation::get_object::{GetObjectError, GetObjectOutput};
+use aws_sdk_s3::operation::head_object::HeadObjectError;
+use aws_sdk_s3::types::RequestPayer;
+use chrono::{DateTime, Utc};
+use indexer_core::config::IngestSourceConfig;
 use indexer_core::{Error, Result};
-use serde::Deserialize;
-use tracing::{debug, info, instrument};
-use futures::future::join_all;
-use std::sync::Arc;
-
-// Schema v3: node_fills_by_block (July 27, 2025 onwards)
-#[derive(Debug, Clone, Deserialize)]
-struct FillByBlock {
-    events: Vec<FillEvent>,
-    block_number: i64,
-    #[serde(default)]
-    block_time: Option<String>,
-    #[serde(default)]
-    local_time: Option<String>,
-}
-
-#[derive(Debug, Clone, Deserialize)]
-struct FillEvent(String, FillData); // (user_address, fill_data)
-
-#[derive(Debug, Clone, Deserialize)]
-struct FillData {
-    px: String,
-    sz: String,
-    coin: String,
-    side: String,
-    time: i64,
-    fee: Option<String>,
-    #[serde(rename = "closedPnl")]
-    closed_pnl: Option<String>,
-}
-
-// Schema v2: node_fills (May 25, 2025 to July 26, 2025)
-#[derive(Debug, Clone, Deserialize)]
-struct NodeFill {
-    user: String,
-    px: String,
-    sz: String,
-    coin: String,
-    side: String,
-    time: i64,
-    fee: Option<String>,
-    #[serde(rename = "closedPnl")]
-    closed_pnl: Option<String>,
-}
-
-// Schema v1: node_trades (March 22, 2025 to May 24, 2025)
-#[derive(Debug, Clone, Deserialize)]
-struct NodeTrade {
-    px: String,
-    sz: String,
-    coin: String,
-    time: i64,
-    side_info: Vec<SideInfo>,
-}
-
-#[derive(Debug, Clone, Deserialize)]
-struct SideInfo {
-    user: String,
-    side: String,
-    fee: Option<String>,
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tracing::{debug, instrument, warn};
+
+/// Size and ETag of an object in the bucket
+#[derive(Debug, Clone)]
+pub struct ObjectMeta {
+    pub etag: Option<String>,
+    pub size: u64,
 }
 
 pub struct S3Source {
     client: S3Client,
     bucket: String,
-    aws_profile: Option<String>,
+    requester_pays: bool,
+    schemas: Arc<SchemaRegistry>,
+    parse_pool: ParsePool,
+    listing_cache: ListingCache,
+    object_cache: Option<Arc<ObjectCache>>,
+    /// Never send a request to the bucket; serve everything from `object_cache`
+    offline: bool,
+    /// ETags from the latest listings, to find cached objects without a HEAD
+    listed_etags: Mutex<HashMap<String, String>>,
 }
 
 impl S3Source {
-    pub async fn new(bucket: String, aws_profile: Option<String>) -> Result<Self> {
+    /// Client for the bucket in `config`, which may be AWS or any S3-compatible store
+    pub async fn new(
+        config: &IngestSourceConfig,
+        schemas: Arc<SchemaRegistry>,
+        parse_pool: ParsePool,
+    ) -> Result<Self> {
         let mut config_loader = aws_config::defaults(aws_config::BehaviorVersion::latest())
-            .region(aws_config::Region::new("ap-northeast-1"));
+            .region(aws_config::Region::new(config.s3_region.clone()));
 
-        if let Some(profile) = &aws_profile {
+        if let Some(profile) = &config.aws_profile {
             config_loader = config_loader.profile_name(profile);
         }
 
-        let config = config_loader.load().await;
+        match (&config.aws_access_key_id, &config.aws_secret_access_key) {
+            (Some(access_key_id), Some(secret_access_key)) => {
+                config_loader = config_loader.credentials_provider(aws_sdk_s3::config::Credentials::new(
+                    access_key_id,
+                    secret_access_key,
+                    config.aws_session_token.clone(),
+                    None,
+                    "indexer-config",
+                ));
+            }
+            (None, None) => {}
+            _ => {
+                return Err(Error::Config(
+                    "aws_access_key_id and aws_secret_access_key must be set together".to_string(),
+                ))
+            }
+        }
+
+        let sdk_config = config_loader.load().await;
+
+        let mut s3_config = aws_sdk_s3::config::Builder::from(&sdk_config)
+            .force_path_style(config.s3_force_path_style);
+        if let Some(endpoint_url) = &config.s3_endpoint_url {
+            s3_config = s3_config.endpoint_url(endpoint_url);
+        }
+
+        let client = S3Client::from_conf(s3_config.build());
+
+        let object_cache = match &config.cache_dir {
+            Some(dir) => Some(Arc::new(ObjectCache::open(
+                dir,
+                &config.s3_bucket,
+                (config.cache_max_gb * BYTES_PER_GB) as u64,
+            )?)),
+            None => None,
+        };
+
+        if config.offline && object_cache.is_none() {
+            return Err(Error::Config(
+                "offline mode reads from the object cache, set ingest.source.cache_dir".to_string(),
+            ));
+        }
 
-        let client = S3Client::new(&config);
+        debug!(
+            bucket = %config.s3_bucket,
+            region = %config.s3_region,
+            endpoint = ?config.s3_endpoint_url,
+            path_style = config.s3_force_path_style,
+            requester_pays = config.s3_requester_pays,
+            cache_dir = ?config.cache_dir,
+            offline = config.offline,
+            "S3 client configured"
+        );
 
         Ok(Self {
             client,
-            bucket,
-            aws_profile,
+            bucket: config.s3_bucket.clone(),
+            requester_pays: config.s3_requester_pays,
+            schemas,
+            parse_pool,
+            listing_cache: ListingCache::default(),
+            object_cache,
+            offline: config.offline,
+            listed_etags: Mutex::new(HashMap::new()),
         })
     }
 
-    fn determine_data_path(&self, date: DateTime<Utc>) -> &'static str {
-        if date >= DateTime::parse_from_rfc3339("2025-07-27T00:00:00Z").unwrap().with_timezone(&Utc) {
-            "node_fills_by_block/hourly"
-        } else if date >= DateTime::parse_from_rfc3339("2025-05-25T00:00:00Z").unwrap().with_timezone(&Utc) {
-            "node_fills/hourly"
-        } else if date >= DateTime::parse_from_rfc3339("2025-03-22T00:00:00Z").unwrap().with_timezone(&Utc) {
-            "node_trades/hourly"
-        } else {
-            "node_trades/hourly" // Default to oldest format
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    /// Request payer header to send, `None` for buckets that don't charge the requester
+    fn request_payer(&self) -> Option<RequestPayer> {
+        self.requester_pays.then_some(RequestPayer::Requester)
+    }
+
+    /// Fail in offline mode instead of sending `request` to the bucket
+    fn ensure_online(&self, request: &str, key: &str) -> Result<()> {
+        if self.offline {
+            return Err(Error::Ingest {
+                source_name: "s3".to_string(),
+                details: format!("Offline: not sending {} for '{}', which is not in the object cache", request, key),
+            });
         }
+        Ok(())
     }
 
-    async fn fetch_hour_data(&self, date: DateTime<Utc>, hour: u32) -> Result<(Vec<u8>, u64)> {
-        let data_path = self.determine_data_path(date);
-        let date_str = date.format("%Y%m%d").to_string();
-        let key = format!("{}/{}/{}.lz4", data_path, date_str, hour);
+    /// Look up size and ETag of an object without downloading it
+    pub async fn head_object(&self, key: &str) -> Result<Option<ObjectMeta>> {
+        self.ensure_online("HEAD", key)?;
+
+        match self.client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .set_request_payer(self.request_payer())
+            .send()
+            .await
+        {
+            Ok(response) => Ok(Some(ObjectMeta {
+                etag: response.e_tag().map(str::to_string),
+                size: response.content_length().unwrap_or(0).max(0) as u64,
+            })),
+            Err(aws_sdk_s3::error::SdkError::ServiceError(err))
+                if matches!(err.err(), HeadObjectError::NotFound(_)) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(Error::Ingest {
+                source_name: "s3".to_string(),
+                details: format!("Failed to HEAD S3 key '{}': {}", key, e),
+            }),
+        }
+    }
+
+    /// Start a GET of `key`, or `None` if the key is missing
+    async fn request_object(&self, key: &str) -> Result<Option<GetObjectOutput>> {
+        self.ensure_online("GET", key)?;
 
         debug!(
             bucket = %self.bucket,
@@ -116,29 +180,43 @@ impl S3Source {
             "Fetching S3 object"
         );
 
-        let response = self.client
+        match self.client
             .get_object()
             .bucket(&self.bucket)
-            .key(&key)
-            .request_payer(aws_sdk_s3::types::RequestPayer::Requester)
+            .key(key)
+            .set_request_payer(self.request_payer())
             .send()
             .await
-            .map_err(|e| {
+        {
+            Ok(response) => Ok(Some(response)),
+            Err(aws_sdk_s3::error::SdkError::ServiceError(err))
+                if matches!(err.err(), GetObjectError::NoSuchKey(_)) =>
+            {
+                // Missing hourly files are expected at the end of available data
+                Ok(None)
+            }
+            Err(e) => {
                 let details = match e {
                     aws_sdk_s3::error::SdkError::ServiceError(ref err) => {
-                        // Check if this is a NoSuchKey error - this is expected for missing hourly files
-                        if let GetObjectError::NoSuchKey(_) = err.err() {
-                            return Error::Validation(format!("S3 key '{}' does not exist (end of available data)", key));
-                        }
                         format!("S3 service error for key '{}': {:?}", key, err)
                     }
                     _ => format!("Failed to fetch S3 key '{}': {}", key, e),
                 };
-                Error::Ingest {
+                Err(Error::Ingest {
                     source_name: "s3".to_string(),
                     details,
-                }
-            })?;
+                })
+            }
+        }
+    }
+
+    /// Download the raw (still compressed) object and its ETag, or `None` if the key is missing
+    pub async fn download_object(&self, key: &str) -> Result<Option<(Vec<u8>, Option<String>)>> {
+        let Some(response) = self.request_object(key).await? else {
+            return Ok(None);
+        };
+
+        let etag = response.e_tag().map(str::to_string);
 
         let body = response.body.collect().await
             .map_err(|e| Error::Ingest {
@@ -146,250 +224,259 @@ impl S3Source {
                 details: format!("Failed to read S3 body: {}", e),
             })?;
 
-        let compressed_data = body.into_bytes();
-        let compressed_size = compressed_data.len() as u64;
+        Ok(Some((body.into_bytes().to_vec(), etag)))
+    }
 
-        // Decompress LZ4 data using the frame decoder which handles the LZ4 frame format
-        use std::io::Read;
-        let mut decoder = lz4_flex::frame::FrameDecoder::new(&compressed_data[..]);
-        let mut decompressed = Vec::new();
-        decoder.read_to_end(&mut decompressed)
-            .map_err(|e| Error::Ingest {
-                source_name: "s3".to_string(),
-                details: format!("Failed to decompress LZ4 data: {}", e),
-            })?;
+    /// List `prefix` in the bucket, following continuation tokens
+    async fn list_bucket(&self, prefix: &str) -> Result<BTreeMap<String, ListedObject>> {
+        self.ensure_online("LIST", prefix)?;
+
+        let mut objects = BTreeMap::new();
+        let mut continuation_token = None;
+
+        loop {
+            let response = self.client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(prefix)
+                .set_continuation_token(continuation_token)
+                .set_request_payer(self.request_payer())
+                .send()
+                .await
+                .map_err(|e| Error::Ingest {
+                    source_name: "s3".to_string(),
+                    details: format!("Failed to list S3 prefix '{}': {}", prefix, e),
+                })?;
 
-        Ok((decompressed, compressed_size))
+            objects.extend(response.contents().iter().filter_map(|object| {
+                let listed = ListedObject {
+                    size: object.size().unwrap_or(0).max(0) as u64,
+                    etag: object.e_tag().map(str::to_string),
+                };
+                object.key().map(|key| (key.to_string(), listed))
+            }));
+
+            match response.next_continuation_token() {
+                Some(token) if response.is_truncated() == Some(true) => {
+                    continuation_token = Some(token.to_string());
+                }
+                _ => break,
+            }
+        }
+
+        Ok(objects)
     }
 
-    fn parse_fills(&self, data: &[u8], date: DateTime<Utc>) -> Result<Vec<Fill>> {
-        let mut fills = Vec::new();
-        let data_str = std::str::from_utf8(data)
-            .map_err(|e| Error::Validation(format!("Invalid UTF-8 data: {}", e)))?;
+    /// Names of the common prefixes one level below `prefix`
+    async fn list_bucket_dirs(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut dirs = Vec::new();
+        let mut continuation_token = None;
+
+        loop {
+            let response = self.client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(prefix)
+                .delimiter("/")
+                .set_continuation_token(continuation_token)
+                .set_request_payer(self.request_payer())
+                .send()
+                .await
+                .map_err(|e| Error::Ingest {
+                    source_name: "s3".to_string(),
+                    details: format!("Failed to list S3 prefix '{}': {}", prefix, e),
+                })?;
 
-        // Each line is a JSON object
-        for line in data_str.lines() {
-            if line.is_empty() {
-                continue;
-            }
+            dirs.extend(response.common_prefixes().iter().filter_map(|common| {
+                let name = common.prefix()?.strip_prefix(prefix)?.trim_end_matches('/');
+                Some(name.to_string())
+            }));
 
-            if date >= DateTime::parse_from_rfc3339("2025-07-27T00:00:00Z").unwrap().with_timezone(&Utc) {
-                // Parse as FillByBlock
-                let block_data: FillByBlock = serde_json::from_str(line)
-                    .map_err(|e| Error::Validation(format!("Failed to parse FillByBlock: {}", e)))?;
-
-                for event in block_data.events {
-                    match self.parse_fill_data(
-                        event.0,
-                        event.1,
-                        Some(block_data.block_number),
-                    ) {
-                        Ok(fill) => fills.push(fill),
-                        Err(e) => {
-                            // Skip records with unknown side values
-                            debug!("Skipping fill: {}", e);
-                        }
-                    }
-                }
-            } else if date >= DateTime::parse_from_rfc3339("2025-05-25T00:00:00Z").unwrap().with_timezone(&Utc) {
-                // Parse as NodeFill
-                let node_fill: NodeFill = serde_json::from_str(line)
-                    .map_err(|e| Error::Validation(format!("Failed to parse NodeFill: {}", e)))?;
-
-                let fill = self.parse_node_fill(node_fill)?;
-                fills.push(fill);
-            } else {
-                // Parse as NodeTrade
-                let node_trade: NodeTrade = serde_json::from_str(line)
-                    .map_err(|e| Error::Validation(format!("Failed to parse NodeTrade: {}", e)))?;
-
-                for side_info in &node_trade.side_info {
-                    let fill = self.parse_trade_fill(
-                        &node_trade,
-                        side_info.clone(),
-                    )?;
-                    fills.push(fill);
+            match response.next_continuation_token() {
+                Some(token) if response.is_truncated() == Some(true) => {
+                    continuation_token = Some(token.to_string());
                 }
+                _ => break,
             }
         }
 
-        Ok(fills)
+        Ok(dirs)
     }
 
-    fn parse_fill_data(
-        &self,
-        user_address: String,
-        fill: FillData,
-        block_number: Option<i64>,
-    ) -> Result<Fill> {
-        let side = match fill.side.to_uppercase().as_str() {
-            "BUY" | "B" => TradeSide::Buy,
-            "SELL" | "S" | "A" => TradeSide::Sell,  // A = Ask = Sell
-            _ => return Err(Error::Validation(format!("Invalid side: {}", fill.side))),
+    /// Cached copy of `key` and its ETag. Online, the copy must match the bucket's
+    /// current ETag; offline, the most recently used copy is taken.
+    async fn cached_object(&self, cache: &ObjectCache, key: &str) -> Result<Option<(std::path::PathBuf, Option<String>)>> {
+        let listed = self.listed_etags.lock().unwrap().get(key).cloned();
+
+        if self.offline {
+            let path = cache.latest(key);
+            if path.is_none() {
+                self.ensure_online("GET", key)?;
+            }
+            return Ok(path.map(|path| (path, listed)));
+        }
+
+        let etag = match listed {
+            Some(etag) => Some(etag),
+            // Only ask the bucket when there's a copy to compare against
+            None if cache.contains_key(key) => self.head_object(key).await?.and_then(|meta| meta.etag),
+            None => None,
         };
 
-        let price = fill.px.parse::<f64>()
-            .map_err(|_| Error::Validation(format!("Invalid price: {}", fill.px)))?;
-
-        let size = fill.sz.parse::<f64>()
-            .map_err(|_| Error::Validation(format!("Invalid size: {}", fill.sz)))?;
-
-        let fee = fill.fee.and_then(|f| f.parse::<f64>().ok());
-        let closed_pnl = fill.closed_pnl.and_then(|p| p.parse::<f64>().ok());
-
-        let timestamp = DateTime::<Utc>::from_timestamp_millis(fill.time)
-            .ok_or_else(|| Error::Validation(format!("Invalid timestamp: {}", fill.time)))?;
-
-        Ok(Fill {
-            user_address,
-            coin: fill.coin,
-            side,
-            price,
-            size,
-            fee,
-            closed_pnl,
-            timestamp,
-            block_number,
-            source_id: None,
-        })
+        Ok(cache.get(key, etag.as_deref()).map(|path| (path, etag)))
     }
 
-    fn parse_node_fill(&self, node_fill: NodeFill) -> Result<Fill> {
-        let side = match node_fill.side.to_uppercase().as_str() {
-            "BUY" | "B" => TradeSide::Buy,
-            "SELL" | "S" | "A" => TradeSide::Sell,  // A = Ask = Sell
-            _ => return Err(Error::Validation(format!("Invalid side: {}", node_fill.side))),
-        };
+    /// Open a cached object for the decoder
+    async fn open_cached(&self, path: &Path, etag: Option<String>) -> Result<ObjectStream> {
+        debug!(path = %path.display(), "Reading object from cache");
 
-        let price = node_fill.px.parse::<f64>()
-            .map_err(|_| Error::Validation(format!("Invalid price: {}", node_fill.px)))?;
-
-        let size = node_fill.sz.parse::<f64>()
-            .map_err(|_| Error::Validation(format!("Invalid size: {}", node_fill.sz)))?;
-
-        let fee = node_fill.fee.and_then(|f| f.parse::<f64>().ok());
-        let closed_pnl = node_fill.closed_pnl.and_then(|p| p.parse::<f64>().ok());
-
-        let timestamp = DateTime::<Utc>::from_timestamp_millis(node_fill.time)
-            .ok_or_else(|| Error::Validation(format!("Invalid timestamp: {}", node_fill.time)))?;
-
-        Ok(Fill {
-            user_address: node_fill.user,
-            coin: node_fill.coin,
-            side,
-            price,
-            size,
-            fee,
-            closed_pnl,
-            timestamp,
-            block_number: None,
-            source_id: None,
+        let file = tokio::fs::File::open(path).await.map_err(|e| Error::Ingest {
+            source_name: "s3".to_string(),
+            details: format!("Failed to open cached object '{}': {}", path.display(), e),
+        })?;
+
+        Ok(ObjectStream {
+            reader: Box::new(file.into_std().await),
+            etag,
+            cached: true,
         })
     }
+}
 
-    fn parse_trade_fill(
-        &self,
-        trade: &NodeTrade,
-        side_info: SideInfo,
-    ) -> Result<Fill> {
-        let side = match side_info.side.to_uppercase().as_str() {
-            "BUY" | "B" => TradeSide::Buy,
-            "SELL" | "S" | "A" => TradeSide::Sell,  // A = Ask = Sell
-            _ => return Err(Error::Validation(format!("Invalid side: {}", side_info.side))),
-        };
+#[async_trait]
+impl HourlyObjectStore for S3Source {
+    fn store_name(&self) -> &str {
+        "s3"
+    }
 
-        let price = trade.px.parse::<f64>()
-            .map_err(|_| Error::Validation(format!("Invalid price: {}", trade.px)))?;
+    fn source_kind(&self) -> SourceKind {
+        SourceKind::S3
+    }
 
-        let size = trade.sz.parse::<f64>()
-            .map_err(|_| Error::Validation(format!("Invalid size: {}", trade.sz)))?;
+    async fn open_object(&self, key: &str) -> Result<Option<ObjectStream>> {
+        if let Some(cache) = &self.object_cache {
+            if let Some((path, etag)) = self.cached_object(cache, key).await? {
+                return self.open_cached(&path, etag).await.map(Some);
+            }
+        }
 
-        let fee = side_info.fee.and_then(|f| f.parse::<f64>().ok());
+        let Some(response) = self.request_object(key).await? else {
+            return Ok(None);
+        };
 
-        let timestamp = DateTime::<Utc>::from_timestamp_millis(trade.time)
-            .ok_or_else(|| Error::Validation(format!("Invalid timestamp: {}", trade.time)))?;
+        let etag = response.e_tag().map(str::to_string);
 
-        Ok(Fill {
-            user_address: side_info.user,
-            coin: trade.coin.clone(),
-            side,
-            price,
-            size,
-            fee,
-            closed_pnl: None,
-            timestamp,
-            block_number: None,
-            source_id: None,
-        })
-    }
+        // Objects are cached under their ETag, so untagged ones can't be
+        let mut cache_writer = match (&self.object_cache, &etag) {
+            (Some(cache), Some(etag)) => match cache.writer(key, etag).await {
+                Ok(writer) => Some(writer),
+                Err(e) => {
+                    warn!(key = %key, error = %e, "Failed to start caching object");
+                    None
+                }
+            },
+            _ => None,
+        };
 
-    /// Fetch multiple hours of data in parallel for faster backfill
-    pub async fn fetch_parallel_batch(
-        &self,
-        start_date: DateTime<Utc>,
-        hours_to_fetch: usize,
-    ) -> Result<Vec<Fill>> {
-        const PARALLEL_FETCHES: usize = 8; // Fetch 8 hours concurrently
-
-        let mut all_fills = Vec::new();
-        let client = Arc::new(self.client.clone());
-        let bucket = self.bucket.clone();
-
-        // Process in batches of PARALLEL_FETCHES
-        for batch_start in (0..hours_to_fetch).step_by(PARALLEL_FETCHES) {
-            let batch_end = (batch_start + PARALLEL_FETCHES).min(hours_to_fetch);
-            let mut fetch_futures = Vec::new();
-
-            for hour_offset in batch_start..batch_end {
-                let fetch_date = start_date + chrono::Duration::hours(hour_offset as i64);
-                let hour = fetch_date.hour();
-                let client_clone = client.clone();
-                let bucket_clone = bucket.clone();
-
-                // Create async task for parallel fetching
-                let fetch_future = async move {
-                    let s3_source = S3Source {
-                        client: (*client_clone).clone(),
-                        bucket: bucket_clone,
-                        aws_profile: None,
-                    };
-                    s3_source.fetch_hour_data(fetch_date, hour).await
+        // Pump the body into a bounded channel; the decoder pulls from it on a blocking thread.
+        // A cached object is read to the end even if the decoder stops early, so a later run
+        // (e.g. after a parser fix) doesn't download it again.
+        let (tx, rx) = mpsc::channel(hourly::STREAM_BUFFER_CHUNKS);
+        let mut body = response.body;
+        let key = key.to_string();
+        tokio::spawn(async move {
+            let mut decoding = true;
+            while let Some(chunk) = body.next().await {
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        let _ = tx.send(Err(std::io::Error::other(format!(
+                            "Failed to read S3 body for '{}': {}", key, e
+                        )))).await;
+                        if let Some(writer) = cache_writer.take() {
+                            writer.abort().await;
+                        }
+                        return;
+                    }
                 };
 
-                fetch_futures.push(fetch_future);
-            }
-
-            // Execute all fetches in parallel
-            let results = join_all(fetch_futures).await;
-
-            // Process results
-            for (idx, result) in results.into_iter().enumerate() {
-                let fetch_date = start_date + chrono::Duration::hours((batch_start + idx) as i64);
-
-                match result {
-                    Ok((data, _bytes)) => {
-                        match self.parse_fills(&data, fetch_date) {
-                            Ok(fills) => {
-                                debug!("Fetched {} fills from {}", fills.len(), fetch_date.format("%Y-%m-%d %H:00"));
-                                all_fills.extend(fills);
-                            }
-                            Err(e) => {
-                                debug!("Failed to parse fills for {}: {}", fetch_date.format("%Y-%m-%d %H:00"), e);
-                            }
+                if let Some(writer) = &mut cache_writer {
+                    if let Err(e) = writer.write(&chunk).await {
+                        warn!(key = %key, error = %e, "Failed to write object to cache");
+                        if let Some(writer) = cache_writer.take() {
+                            writer.abort().await;
                         }
                     }
-                    Err(Error::Validation(msg)) if msg.contains("does not exist") => {
-                        debug!("No data for {}", fetch_date.format("%Y-%m-%d %H:00"));
-                    }
-                    Err(e) => {
-                        debug!("Failed to fetch {}: {}", fetch_date.format("%Y-%m-%d %H:00"), e);
+                }
+
+                if decoding && tx.send(Ok(chunk)).await.is_err() {
+                    decoding = false;
+                }
+                if !decoding && cache_writer.is_none() {
+                    break;
+                }
+            }
+
+            if let Some(writer) = cache_writer {
+                if let Err(e) = writer.finish().await {
+                    warn!(key = %key, error = %e, "Failed to cache object");
+                }
+            }
+        });
+
+        Ok(Some(ObjectStream {
+            reader: Box::new(ChannelReader::new(rx)),
+            etag,
+            cached: false,
+        }))
+    }
+
+    async fn list_objects(&self, prefix: &str) -> Result<HashMap<String, u64>> {
+        let objects = match &self.object_cache {
+            Some(cache) if self.offline => cache.load_listing(prefix).await?.ok_or_else(|| Error::Ingest {
+                source_name: "s3".to_string(),
+                details: format!("Offline: not listing '{}', which was never listed online", prefix),
+            })?,
+            _ => {
+                let objects = self.list_bucket(prefix).await?;
+                if let Some(cache) = &self.object_cache {
+                    if let Err(e) = cache.save_listing(prefix, &objects).await {
+                        warn!(prefix = %prefix, error = %e, "Failed to cache listing");
                     }
                 }
+                objects
             }
+        };
+
+        let mut listed_etags = self.listed_etags.lock().unwrap();
+        Ok(objects.into_iter()
+            .map(|(key, object)| {
+                if let Some(etag) = object.etag {
+                    listed_etags.insert(key.clone(), etag);
+                }
+                (key, object.size)
+            })
+            .collect())
+    }
+
+    async fn list_dirs(&self, prefix: &str) -> Result<Option<Vec<String>>> {
+        // Offline, the bucket can't be asked which days exist
+        if self.offline {
+            return Ok(None);
         }
+        self.list_bucket_dirs(prefix).await.map(Some)
+    }
+
+    fn listing_cache(&self) -> &ListingCache {
+        &self.listing_cache
+    }
+
+    fn schemas(&self) -> &Arc<SchemaRegistry> {
+        &self.schemas
+    }
 
-        info!("Fetched {} total fills from {} hours in parallel", all_fills.len(), hours_to_fetch);
-        Ok(all_fills)
+    fn parse_pool(&self) -> &ParsePool {
+        &self.parse_pool
     }
 }
 
@@ -399,90 +486,31 @@ impl IngestSource for S3Source {
     async fn fetch_page(
         &self,
         start_from: DateTime<Utc>,
-        cursor: Option<String>,
+        cursor: Option<Cursor>,
     ) -> Result<IngestBatch> {
-        // Parse cursor to determine current position
-        let (current_date, current_hour) = if let Some(cursor) = cursor.clone() {
-            let parts: Vec<&str> = cursor.split('_').collect();
-            if parts.len() == 2 {
-                let date = NaiveDate::parse_from_str(parts[0], "%Y%m%d")
-                    .map_err(|e| Error::Validation(format!("Invalid cursor date: {}", e)))?;
-                let hour = parts[1].parse::<u32>()
-                    .map_err(|e| Error::Validation(format!("Invalid cursor hour: {}", e)))?;
-                (date.and_hms_opt(hour, 0, 0).unwrap().and_utc(), hour)
-            } else {
-                (start_from, start_from.hour())
-            }
-        } else {
-            (start_from, start_from.hour())
-        };
-
-        // Use parallel fetching for better performance - fetch 8 hours at a time
-        const HOURS_PER_BATCH: usize = 8;
-
-        // If we have enough hours ahead, fetch in parallel
-        let hours_until_now = ((Utc::now() - current_date).num_hours() as usize).min(HOURS_PER_BATCH);
-
-        if hours_until_now >= 4 {
-            // Fetch multiple hours in parallel
-            let fills = self.fetch_parallel_batch(current_date, hours_until_now).await?;
-
-            // Calculate cursor for next batch
-            let next_date = current_date + chrono::Duration::hours(hours_until_now as i64);
-            let next_cursor = format!("{}_{}", next_date.format("%Y%m%d"), next_date.hour());
-
-            return Ok(IngestBatch {
-                fills,
-                cursor: Some(next_cursor),
-                has_more: next_date < Utc::now(),
-                bytes_downloaded: None,
-            });
-        }
-
-        // Fall back to single hour fetch if near the end
-        let (data, bytes_downloaded) = match self.fetch_hour_data(current_date, current_hour).await {
-            Ok((data, bytes)) => (data, bytes),
-            Err(Error::Validation(msg)) if msg.contains("does not exist") => {
-                // S3 key doesn't exist - this means we've reached the end of available data
-                debug!("Reached end of available data: {}", msg);
-                return Ok(IngestBatch {
-                    fills: vec![],
-                    cursor: None,
-                    has_more: false,
-                    bytes_downloaded: Some(0),
-                });
-            }
-            Err(e) => return Err(e),
-        };
-
-        let fills = self.parse_fills(&data, current_date)?;
-
-        // Calculate next cursor
-        let mut next_date = current_date;
-        let mut next_hour = current_hour + 1;
-        if next_hour >= 24 {
-            next_hour = 0;
-            next_date = next_date + chrono::Duration::days(1);
-        }
+        hourly::fetch_page(self, start_from, cursor).await
+    }
 
-        let next_cursor = format!("{}_{}", next_date.format("%Y%m%d"), next_hour);
+    async fn stream_page(
+        &self,
+        start_from: DateTime<Utc>,
+        cursor: Option<Cursor>,
+        batch_size: usize,
+        tx: &mpsc::Sender<IngestBatch>,
+    ) -> Result<PageSummary> {
+        hourly::stream_page(self, start_from, cursor, batch_size, tx).await
+    }
 
-        // Check if we have more data (simple heuristic: check if we're not in the future)
-        let has_more = next_date < Utc::now();
+    async fn plan_download(&self, hours: &[DateTime<Utc>]) -> Result<Option<DownloadPlan>> {
+        hourly::plan_download(self, hours).await.map(Some)
+    }
 
-        debug!(
-            fills_count = fills.len(),
-            has_more,
-            next_cursor = %next_cursor,
-            "Fetched S3 data"
-        );
+    fn transfer_billed(&self) -> bool {
+        self.requester_pays
+    }
 
-        Ok(IngestBatch {
-            fills,
-            cursor: Some(next_cursor),
-            has_more,
-            bytes_downloaded: Some(bytes_downloaded),
-        })
+    fn hour_cursor(&self, hour: DateTime<Utc>) -> Cursor {
+        hourly::hour_cursor(self, hour)
     }
 
     fn source_id(&self) -> &str {
@@ -490,12 +518,17 @@ impl IngestSource for S3Source {
     }
 
     async fn health_check(&self) -> Result<()> {
+        if self.offline {
+            debug!("Offline, skipping S3 health check");
+            return Ok(());
+        }
+
         // Try to list objects to verify access
         let result = self.client
             .list_objects_v2()
             .bucket(&self.bucket)
             .max_keys(1)
-            .request_payer(aws_sdk_s3::types::RequestPayer::Requester)
+            .set_request_payer(self.request_payer())
             .send()
             .await;
 
@@ -0,0 +1,898 @@
+use crate::model::{Block, DupSeqs, Fill, Liquidation, TradeSide};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use indexer_core::config::{IngestSourceConfig, SchemaEpochConfig};
+use indexer_core::{Error, Result};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Parses the lines of one hour-file format into fills. Adding support for a new
+/// upstream format means implementing this and registering it in `parser_by_name`.
+pub trait FillParser: Send + Sync {
+    /// Format name used in config and logs
+    fn name(&self) -> &'static str;
+
+    /// Whether a decoded line has the shape of this format
+    fn sniff(&self, record: &serde_json::Value) -> bool;
+
+    /// Parse one non-empty line. The outer error means the line could not be decoded
+    /// at all; inner errors reject individual fills while keeping the rest of the line.
+    fn parse_line(&self, line: &str) -> Result<Vec<Result<Fill>>>;
+
+    /// Parse one non-empty line along with its block header, for formats with one
+    /// line per block
+    fn parse_block_line(&self, line: &str) -> Result<(Vec<Result<Fill>>, Option<Block>)> {
+        self.parse_line(line).map(|fills| (fills, None))
+    }
+
+    /// Block number of a line, read without parsing its fills. `None` for formats
+    /// without one line per block.
+    fn block_number(&self, _line: &str) -> Option<i64> {
+        None
+    }
+}
+
+/// Every format name accepted by `parser_by_name`
+const KNOWN_FORMATS: [&str; 3] = ["node_fills_by_block", "node_fills", "node_trades"];
+
+/// How close to an epoch boundary an hour must be for the neighbouring epoch's
+/// prefix to be tried when the expected key is missing
+const CUTOVER_PROBE_WINDOW_HOURS: i64 = 48;
+
+/// Parser for a format name used in `ingest.source.schema_epochs`
+pub fn parser_by_name(name: &str) -> Option<Arc<dyn FillParser>> {
+    match name {
+        "node_fills_by_block" => Some(Arc::new(NodeFillsByBlockParser)),
+        "node_fills" => Some(Arc::new(NodeFillsParser)),
+        "node_trades" => Some(Arc::new(NodeTradesParser)),
+        _ => None,
+    }
+}
+
+/// A date range during which hour files are published under `prefix` in one format
+#[derive(Clone)]
+pub struct SchemaEpoch {
+    /// Key prefix inside the bucket, e.g. `node_fills_by_block/hourly`
+    pub prefix: String,
+    /// First hour covered, unbounded if `None`
+    pub start: Option<DateTime<Utc>>,
+    /// First hour no longer covered, unbounded if `None`
+    pub end: Option<DateTime<Utc>>,
+    pub parser: Arc<dyn FillParser>,
+}
+
+impl SchemaEpoch {
+    fn contains(&self, date: DateTime<Utc>) -> bool {
+        self.start.map_or(true, |start| date >= start) && self.end.map_or(true, |end| date < end)
+    }
+}
+
+impl std::fmt::Debug for SchemaEpoch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SchemaEpoch")
+            .field("prefix", &self.prefix)
+            .field("start", &self.start)
+            .field("end", &self.end)
+            .field("parser", &self.parser.name())
+            .finish()
+    }
+}
+
+/// Ordered, non-overlapping list of schema epochs used to pick the key prefix and
+/// parser for any hour
+#[derive(Debug, Clone)]
+pub struct SchemaRegistry {
+    epochs: Vec<SchemaEpoch>,
+    max_rejects_per_hour: usize,
+}
+
+/// Matches the default of `ingest.source.max_rejects_per_hour`
+const DEFAULT_MAX_REJECTS_PER_HOUR: usize = 100;
+
+/// A line of an hour file that was not fully ingested
+#[derive(Debug, Clone)]
+pub struct LineReject {
+    /// 1-based line number within the decompressed file
+    pub line_number: usize,
+    pub raw_line: String,
+    pub reason: String,
+}
+
+impl SchemaRegistry {
+    pub fn new(mut epochs: Vec<SchemaEpoch>) -> Result<Self> {
+        if epochs.is_empty() {
+            return Err(Error::Config("at least one schema epoch is required".to_string()));
+        }
+
+        epochs.sort_by_key(|epoch| epoch.start);
+
+        for epoch in &epochs {
+            if let (Some(start), Some(end)) = (epoch.start, epoch.end) {
+                if start >= end {
+                    return Err(Error::Config(format!(
+                        "schema epoch '{}' starts at {} but ends at {}",
+                        epoch.prefix, start, end
+                    )));
+                }
+            }
+        }
+
+        for pair in epochs.windows(2) {
+            let overlaps = match (pair[0].end, pair[1].start) {
+                (Some(end), Some(start)) => end > start,
+                _ => true,
+            };
+            if overlaps {
+                return Err(Error::Config(format!(
+                    "schema epochs '{}' and '{}' overlap",
+                    pair[0].prefix, pair[1].prefix
+                )));
+            }
+        }
+
+        Ok(Self {
+            epochs,
+            max_rejects_per_hour: DEFAULT_MAX_REJECTS_PER_HOUR,
+        })
+    }
+
+    /// Build the registry from config, using the built-in epochs if none are configured
+    pub fn from_config(config: &IngestSourceConfig) -> Result<Self> {
+        let registry = if config.schema_epochs.is_empty() {
+            Self::default()
+        } else {
+            Self::new(Self::epochs_from_config(&config.schema_epochs)?)?
+        };
+
+        Ok(Self {
+            max_rejects_per_hour: config.max_rejects_per_hour,
+            ..registry
+        })
+    }
+
+    fn epochs_from_config(configs: &[SchemaEpochConfig]) -> Result<Vec<SchemaEpoch>> {
+        configs
+            .iter()
+            .map(|config| {
+                let parser = parser_by_name(&config.format).ok_or_else(|| {
+                    Error::Config(format!("unknown schema format '{}'", config.format))
+                })?;
+                Ok(SchemaEpoch {
+                    prefix: config.prefix.trim_end_matches('/').to_string(),
+                    start: config.start,
+                    end: config.end,
+                    parser,
+                })
+            })
+            .collect()
+    }
+
+    /// The epoch covering `date`
+    pub fn epoch_for(&self, date: DateTime<Utc>) -> Result<&SchemaEpoch> {
+        self.epochs
+            .iter()
+            .find(|epoch| epoch.contains(date))
+            .ok_or_else(|| Error::Validation(format!("No schema epoch covers {}", date)))
+    }
+
+    /// Key prefixes of all epochs, oldest first
+    pub fn prefixes(&self) -> impl Iterator<Item = &str> {
+        self.epochs.iter().map(|epoch| epoch.prefix.as_str())
+    }
+
+    /// Object key of the hour file for the given date and hour
+    pub fn hour_key(&self, date: DateTime<Utc>, hour: u32) -> Result<String> {
+        Ok(format!(
+            "{}/{}/{}.lz4",
+            self.epoch_for(date)?.prefix,
+            date.format("%Y%m%d"),
+            hour
+        ))
+    }
+
+    /// Keys to try for an hour: the expected key first, then the prefixes of
+    /// neighbouring epochs if the hour is close to a cutover
+    pub fn candidate_keys(&self, date: DateTime<Utc>, hour: u32) -> Result<Vec<String>> {
+        let expected = self.epoch_for(date)?;
+        let window = chrono::Duration::hours(CUTOVER_PROBE_WINDOW_HOURS);
+
+        let mut keys = vec![self.hour_key(date, hour)?];
+        for epoch in &self.epochs {
+            let near = epoch.start.map_or(true, |start| date >= start - window)
+                && epoch.end.map_or(true, |end| date < end + window);
+            if near && epoch.prefix != expected.prefix {
+                let key = format!("{}/{}/{}.lz4", epoch.prefix, date.format("%Y%m%d"), hour);
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+
+        Ok(keys)
+    }
+
+    /// Detect the format of an hour file from its first non-empty line
+    pub fn detect_format(&self, data: &[u8]) -> Option<Arc<dyn FillParser>> {
+        let first_line = data
+            .split(|b| *b == b'\n')
+            .find(|line| !line.iter().all(u8::is_ascii_whitespace))?;
+        sniff_format(first_line)
+    }
+
+    /// Start parsing the hour file for `date`. Lines arrive in blocks, so the file
+    /// never has to be held in memory.
+    pub fn hour_parser(&self, date: DateTime<Utc>) -> Result<HourParser> {
+        Ok(HourParser {
+            date,
+            expected: Arc::clone(&self.epoch_for(date)?.parser),
+            parser: None,
+            detected: None,
+            max_rejects: self.max_rejects_per_hour,
+            dup_seqs: DupSeqs::default(),
+            lines: 0,
+            skipped: 0,
+            blocks: BlockRange::default(),
+            rejected: 0,
+        })
+    }
+}
+
+/// Parser whose `sniff` accepts the given line
+fn sniff_format(line: &[u8]) -> Option<Arc<dyn FillParser>> {
+    let record: serde_json::Value = serde_json::from_slice(line).ok()?;
+
+    KNOWN_FORMATS
+        .iter()
+        .filter_map(|name| parser_by_name(name))
+        .find(|parser| parser.sniff(&record))
+}
+
+/// A run of whole lines from an hour file, parsed as one job on the parse pool
+#[derive(Debug)]
+pub struct LineBlock {
+    /// 1-based line number of the first line in `data`
+    pub first_line: usize,
+    /// Raw decompressed lines, each ending in `\n` except possibly the file's last
+    pub data: Vec<u8>,
+}
+
+impl LineBlock {
+    /// Lines without their `\n` or `\r\n`, numbered within the file
+    fn lines(&self) -> impl Iterator<Item = (usize, &[u8])> {
+        let data = self.data.strip_suffix(b"\n").unwrap_or(&self.data);
+        data.split(|b| *b == b'\n')
+            .enumerate()
+            .map(|(i, line)| (self.first_line + i, line.strip_suffix(b"\r").unwrap_or(line)))
+    }
+}
+
+/// Block numbers seen in the lines of a file with one line per block
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BlockRange {
+    pub first: Option<i64>,
+    pub last: Option<i64>,
+    /// Lines with a block number
+    pub count: usize,
+}
+
+impl BlockRange {
+    fn add(&mut self, block: i64) {
+        self.first = Some(self.first.map_or(block, |first| first.min(block)));
+        self.last = Some(self.last.map_or(block, |last| last.max(block)));
+        self.count += 1;
+    }
+
+    fn merge(&mut self, other: &BlockRange) {
+        self.first = self.first.into_iter().chain(other.first).min();
+        self.last = self.last.into_iter().chain(other.last).max();
+        self.count += other.count;
+    }
+}
+
+/// Fills and rejects parsed from one `LineBlock`, before `dup_seq` numbering
+#[derive(Debug, Default)]
+pub struct ParsedBlock {
+    pub fills: Vec<Fill>,
+    pub rejects: Vec<LineReject>,
+    /// Non-empty lines in the block
+    pub lines: usize,
+    /// Lines skipped because their block was already loaded
+    pub skipped: usize,
+    pub blocks: BlockRange,
+    /// Headers of the lines parsed, for formats with one line per block
+    pub headers: Vec<Block>,
+}
+
+/// Parse every line of a block. Blocks of one hour can be parsed in parallel;
+/// `HourParser::accept` then takes the results in file order. Lines of blocks at
+/// or below `skip_through` were loaded before a restart and are only counted.
+pub fn parse_block(parser: &dyn FillParser, block: &LineBlock, skip_through: Option<i64>) -> ParsedBlock {
+    let mut parsed = ParsedBlock::default();
+    let mut skip_through = skip_through;
+
+    for (line_number, line) in block.lines() {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        parsed.lines += 1;
+
+        let reject = |reason: String| LineReject {
+            line_number,
+            raw_line: String::from_utf8_lossy(line).into_owned(),
+            reason,
+        };
+
+        // Each line is a JSON object
+        let line = match std::str::from_utf8(line) {
+            Ok(line) => line,
+            Err(e) => {
+                parsed.rejects.push(reject(format!("Invalid UTF-8 data: {}", e)));
+                continue;
+            }
+        };
+
+        // Blocks are in ascending order, so skipping ends at the first newer block.
+        // Lines without a block number are parsed, so malformed ones are rejected again.
+        if let Some(skip) = skip_through {
+            match parser.block_number(line) {
+                Some(block) if block <= skip => {
+                    parsed.skipped += 1;
+                    parsed.blocks.add(block);
+                    continue;
+                }
+                Some(_) => skip_through = None,
+                None => {}
+            }
+        }
+
+        match parser.parse_block_line(line) {
+            Ok((results, header)) => {
+                for result in results {
+                    match result {
+                        Ok(fill) => parsed.fills.push(fill),
+                        Err(e) => parsed.rejects.push(reject(e.to_string())),
+                    }
+                }
+                // Blocks without fills still count towards the range
+                if let Some(header) = header {
+                    parsed.blocks.add(header.block_number);
+                    parsed.headers.push(header);
+                }
+            }
+            Err(e) => parsed.rejects.push(reject(e.to_string())),
+        }
+    }
+
+    parsed
+}
+
+/// Tracks the parse of one hour file across its blocks. The format is sniffed
+/// from the first non-empty line and falls back to the parser of the epoch
+/// covering the hour. Bad lines and fills become rejects; the hour only fails
+/// once there are more than `max_rejects_per_hour` of them.
+pub struct HourParser {
+    date: DateTime<Utc>,
+    expected: Arc<dyn FillParser>,
+    /// Chosen on the first non-empty line
+    parser: Option<Arc<dyn FillParser>>,
+    detected: Option<&'static str>,
+    max_rejects: usize,
+    dup_seqs: DupSeqs,
+    lines: usize,
+    skipped: usize,
+    blocks: BlockRange,
+    rejected: usize,
+}
+
+impl HourParser {
+    /// Parser for the blocks of this file, sniffed from the first non-empty line
+    /// of `block` unless it was already chosen
+    pub fn parser_for(&mut self, block: &LineBlock) -> Arc<dyn FillParser> {
+        if let Some(parser) = &self.parser {
+            return Arc::clone(parser);
+        }
+
+        match block.lines().find(|(_, line)| !line.iter().all(u8::is_ascii_whitespace)) {
+            Some((_, first_line)) => self.choose_parser(first_line),
+            None => Arc::clone(&self.expected),
+        }
+    }
+
+    /// Take the next parsed block in file order: number its tid-less fills and
+    /// count its rejects. Fails once the hour has too many rejects.
+    pub fn accept(&mut self, block: &mut ParsedBlock) -> Result<()> {
+        self.dup_seqs.assign(&mut block.fills);
+        self.lines += block.lines;
+        self.skipped += block.skipped;
+        self.blocks.merge(&block.blocks);
+
+        let rejected_before = self.rejected;
+        self.rejected += block.rejects.len();
+        if self.rejected > self.max_rejects {
+            let latest = &block.rejects[self.max_rejects.saturating_sub(rejected_before)];
+            return Err(Error::Validation(format!(
+                "more than {} rejected lines in hour {} (latest: line {}: {})",
+                self.max_rejects,
+                self.date.format("%Y-%m-%d %H:00"),
+                latest.line_number,
+                latest.reason
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Format of the epoch covering the hour
+    pub fn expected_format(&self) -> &'static str {
+        self.expected.name()
+    }
+
+    /// Format sniffed from the first line, `None` if there was none or it was unrecognized
+    pub fn detected_format(&self) -> Option<&'static str> {
+        self.detected
+    }
+
+    /// Non-empty lines accepted so far
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+
+    /// Lines skipped so far because their block was loaded before a restart
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Block numbers seen so far, including skipped lines
+    pub fn blocks(&self) -> BlockRange {
+        self.blocks
+    }
+
+    fn choose_parser(&mut self, first_line: &[u8]) -> Arc<dyn FillParser> {
+        let detected = sniff_format(first_line);
+        self.detected = detected.as_ref().map(|parser| parser.name());
+
+        let parser = match detected {
+            Some(detected) if detected.name() != self.expected.name() => {
+                warn!(
+                    hour = %self.date.format("%Y-%m-%d %H:00"),
+                    expected = self.expected.name(),
+                    detected = detected.name(),
+                    "Hour file format differs from schema epoch"
+                );
+                detected
+            }
+            Some(detected) => detected,
+            None => Arc::clone(&self.expected),
+        };
+
+        self.parser = Some(Arc::clone(&parser));
+        parser
+    }
+}
+
+impl Default for SchemaRegistry {
+    /// Formats published in `hl-mainnet-node-data` so far. `node_trades` starts on
+    /// 2025-03-22 but is left open-ended so earlier hours resolve to a key that
+    /// simply doesn't exist.
+    fn default() -> Self {
+        let v2_start = Utc.with_ymd_and_hms(2025, 5, 25, 0, 0, 0).unwrap();
+        let v3_start = Utc.with_ymd_and_hms(2025, 7, 27, 0, 0, 0).unwrap();
+
+        Self {
+            epochs: vec![
+                SchemaEpoch {
+                    prefix: "node_trades/hourly".to_string(),
+                    start: None,
+                    end: Some(v2_start),
+                    parser: Arc::new(NodeTradesParser),
+                },
+                SchemaEpoch {
+                    prefix: "node_fills/hourly".to_string(),
+                    start: Some(v2_start),
+                    end: Some(v3_start),
+                    parser: Arc::new(NodeFillsParser),
+                },
+                SchemaEpoch {
+                    prefix: "node_fills_by_block/hourly".to_string(),
+                    start: Some(v3_start),
+                    end: None,
+                    parser: Arc::new(NodeFillsByBlockParser),
+                },
+            ],
+            max_rejects_per_hour: DEFAULT_MAX_REJECTS_PER_HOUR,
+        }
+    }
+}
+
+/// Schema v3: one line per block with `[user, fill]` events
+pub struct NodeFillsByBlockParser;
+
+impl FillParser for NodeFillsByBlockParser {
+    fn name(&self) -> &'static str {
+        "node_fills_by_block"
+    }
+
+    fn sniff(&self, record: &serde_json::Value) -> bool {
+        record.get("events").is_some() && record.get("block_number").is_some()
+    }
+
+    fn parse_line(&self, line: &str) -> Result<Vec<Result<Fill>>> {
+        self.parse_block_line(line).map(|(fills, _)| fills)
+    }
+
+    fn parse_block_line(&self, line: &str) -> Result<(Vec<Result<Fill>>, Option<Block>)> {
+        let block_data: FillByBlock = serde_json::from_str(line)
+            .map_err(|e| Error::Validation(format!("Failed to parse FillByBlock: {}", e)))?;
+
+        let block = Block {
+            block_number: block_data.block_number,
+            block_time: block_data.block_time.as_deref().and_then(parse_node_time),
+            local_time: block_data.local_time.as_deref().and_then(parse_node_time),
+            fill_count: block_data.events.len(),
+            hour: None,
+            key: None,
+        };
+
+        let fills = block_data
+            .events
+            .into_iter()
+            .map(|event| parse_fill_data(event.0, event.1, Some(block_data.block_number)))
+            .collect();
+
+        Ok((fills, Some(block)))
+    }
+
+    fn block_number(&self, line: &str) -> Option<i64> {
+        // Skips over the events without allocating them
+        #[derive(Deserialize)]
+        struct BlockHeader {
+            block_number: i64,
+        }
+
+        serde_json::from_str::<BlockHeader>(line).ok().map(|header| header.block_number)
+    }
+}
+
+/// Schema v2: one fill per line with the user inlined
+pub struct NodeFillsParser;
+
+impl FillParser for NodeFillsParser {
+    fn name(&self) -> &'static str {
+        "node_fills"
+    }
+
+    fn sniff(&self, record: &serde_json::Value) -> bool {
+        record.get("user").is_some() && record.get("px").is_some()
+    }
+
+    fn parse_line(&self, line: &str) -> Result<Vec<Result<Fill>>> {
+        let node_fill: NodeFill = serde_json::from_str(line)
+            .map_err(|e| Error::Validation(format!("Failed to parse NodeFill: {}", e)))?;
+
+        Ok(vec![parse_node_fill(node_fill)])
+    }
+}
+
+/// Schema v1: one trade per line with both sides in `side_info`
+pub struct NodeTradesParser;
+
+impl FillParser for NodeTradesParser {
+    fn name(&self) -> &'static str {
+        "node_trades"
+    }
+
+    fn sniff(&self, record: &serde_json::Value) -> bool {
+        record.get("side_info").is_some()
+    }
+
+    fn parse_line(&self, line: &str) -> Result<Vec<Result<Fill>>> {
+        let node_trade: NodeTrade = serde_json::from_str(line)
+            .map_err(|e| Error::Validation(format!("Failed to parse NodeTrade: {}", e)))?;
+
+        Ok(node_trade
+            .side_info
+            .iter()
+            .map(|side_info| parse_trade_fill(&node_trade, side_info.clone()))
+            .collect())
+    }
+}
+
+// Schema v3: node_fills_by_block (July 27, 2025 onwards)
+#[derive(Debug, Clone, Deserialize)]
+struct FillByBlock {
+    events: Vec<FillEvent>,
+    block_number: i64,
+    #[serde(default)]
+    block_time: Option<String>,
+    #[serde(default)]
+    local_time: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct FillEvent(String, FillData); // (user_address, fill_data)
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FillData {
+    px: String,
+    sz: String,
+    coin: String,
+    side: String,
+    time: i64,
+    fee: Option<String>,
+    closed_pnl: Option<String>,
+    #[serde(default)]
+    hash: Option<String>,
+    #[serde(default)]
+    oid: Option<i64>,
+    #[serde(default)]
+    tid: Option<i64>,
+    #[serde(default)]
+    crossed: Option<bool>,
+    #[serde(default)]
+    dir: Option<String>,
+    #[serde(default)]
+    start_position: Option<String>,
+    #[serde(default)]
+    fee_token: Option<String>,
+    #[serde(default)]
+    liquidation: Option<LiquidationData>,
+    #[serde(default)]
+    twap_id: Option<i64>,
+    #[serde(default)]
+    builder_fee: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LiquidationData {
+    #[serde(default)]
+    liquidated_user: Option<String>,
+    #[serde(default)]
+    mark_px: Option<String>,
+    #[serde(default)]
+    method: Option<String>,
+}
+
+// Schema v2: node_fills (May 25, 2025 to July 26, 2025)
+#[derive(Debug, Clone, Deserialize)]
+struct NodeFill {
+    user: String,
+    #[serde(flatten)]
+    fill: FillData,
+}
+
+// Schema v1: node_trades (March 22, 2025 to May 24, 2025)
+#[derive(Debug, Clone, Deserialize)]
+struct NodeTrade {
+    px: String,
+    sz: String,
+    coin: String,
+    time: i64,
+    #[serde(default)]
+    side: Option<String>,
+    #[serde(default)]
+    hash: Option<String>,
+    #[serde(default)]
+    tid: Option<i64>,
+    side_info: Vec<SideInfo>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SideInfo {
+    user: String,
+    side: String,
+    fee: Option<String>,
+    #[serde(default)]
+    start_pos: Option<String>,
+    #[serde(default)]
+    oid: Option<i64>,
+    #[serde(default)]
+    twap_id: Option<i64>,
+}
+
+fn parse_side(side: &str) -> Result<TradeSide> {
+    match side.to_uppercase().as_str() {
+        "BUY" | "B" => Ok(TradeSide::Buy),
+        "SELL" | "S" | "A" => Ok(TradeSide::Sell),  // A = Ask = Sell
+        _ => Err(Error::Validation(format!("Invalid side: {}", side))),
+    }
+}
+
+/// Addresses are stored lowercase whatever case they were published in, so the
+/// same fill gets the same key from every source and a user's stats don't split
+fn normalize_address(address: &str) -> String {
+    address.to_lowercase()
+}
+
+/// Parse an upstream decimal string exactly, accepting scientific notation as a fallback
+pub(crate) fn parse_decimal(field: &str, value: &str) -> Result<Decimal> {
+    Decimal::from_str(value)
+        .or_else(|_| Decimal::from_scientific(value))
+        .map_err(|_| Error::Validation(format!("Invalid {}: {}", field, value)))
+}
+
+/// Parse an optional decimal; a present but malformed value rejects the fill
+fn parse_optional_decimal(field: &str, value: Option<&str>) -> Result<Option<Decimal>> {
+    value.map(|v| parse_decimal(field, v)).transpose()
+}
+
+/// Node timestamps such as `2025-08-02T00:00:01.123456789`, in UTC without an offset.
+/// Unreadable ones are dropped rather than rejecting the block's fills.
+pub(crate) fn parse_node_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f").map(|time| time.and_utc()))
+        .ok()
+}
+
+fn parse_fill_data(
+    user_address: String,
+    fill: FillData,
+    block_number: Option<i64>,
+) -> Result<Fill> {
+    let side = parse_side(&fill.side)?;
+
+    let price = parse_decimal("price", &fill.px)?;
+    let size = parse_decimal("size", &fill.sz)?;
+
+    let fee = parse_optional_decimal("fee", fill.fee.as_deref())?;
+    let closed_pnl = parse_optional_decimal("closed_pnl", fill.closed_pnl.as_deref())?;
+    let start_position = parse_optional_decimal("start_position", fill.start_position.as_deref())?;
+    let builder_fee = parse_optional_decimal("builder_fee", fill.builder_fee.as_deref())?;
+
+    let timestamp = DateTime::<Utc>::from_timestamp_millis(fill.time)
+        .ok_or_else(|| Error::Validation(format!("Invalid timestamp: {}", fill.time)))?;
+
+    let liquidation = match fill.liquidation {
+        Some(l) => Some(Liquidation {
+            liquidated_user: l.liquidated_user.as_deref().map(normalize_address),
+            mark_price: parse_optional_decimal("mark_px", l.mark_px.as_deref())?,
+            method: l.method,
+        }),
+        None => None,
+    };
+
+    Ok(Fill {
+        user_address: normalize_address(&user_address),
+        coin: fill.coin,
+        side,
+        price,
+        size,
+        fee,
+        closed_pnl,
+        timestamp,
+        block_number,
+        source_id: None,
+        tid: fill.tid,
+        oid: fill.oid,
+        hash: fill.hash,
+        crossed: fill.crossed,
+        dir: fill.dir,
+        start_position,
+        fee_token: fill.fee_token,
+        liquidation,
+        twap_id: fill.twap_id,
+        builder_fee,
+        dup_seq: 0,
+    })
+}
+
+fn parse_node_fill(node_fill: NodeFill) -> Result<Fill> {
+    parse_fill_data(node_fill.user, node_fill.fill, None)
+}
+
+fn parse_trade_fill(
+    trade: &NodeTrade,
+    side_info: SideInfo,
+) -> Result<Fill> {
+    let side = parse_side(&side_info.side)?;
+
+    let price = parse_decimal("price", &trade.px)?;
+    let size = parse_decimal("size", &trade.sz)?;
+
+    let fee = parse_optional_decimal("fee", side_info.fee.as_deref())?;
+    let start_position = parse_optional_decimal("start_position", side_info.start_pos.as_deref())?;
+
+    let timestamp = DateTime::<Utc>::from_timestamp_millis(trade.time)
+        .ok_or_else(|| Error::Validation(format!("Invalid timestamp: {}", trade.time)))?;
+
+    // The trade-level side is the aggressor, so the matching side_info entry crossed the book
+    let crossed = match &trade.side {
+        Some(trade_side) => Some(parse_side(trade_side)? == side),
+        None => None,
+    };
+
+    Ok(Fill {
+        user_address: normalize_address(&side_info.user),
+        coin: trade.coin.clone(),
+        side,
+        price,
+        size,
+        fee,
+        closed_pnl: None,
+        timestamp,
+        block_number: None,
+        source_id: None,
+        tid: trade.tid,
+        oid: side_info.oid,
+        hash: trade.hash.clone(),
+        crossed,
+        dir: None,
+        start_position,
+        fee_token: None,
+        liquidation: None,
+        twap_id: side_info.twap_id,
+        builder_fee: None,
+        dup_seq: 0,
+    })
+}
+
+/// A fill of the info API's `userFills*` responses, which carries the fields of the
+/// node fill files for the user it was requested for
+pub(crate) fn parse_api_fill(user_address: &str, fill: serde_json::Value) -> Result<Fill> {
+    let fill: FillData = serde_json::from_value(fill)
+        .map_err(|e| Error::Validation(format!("Failed to parse API fill: {}", e)))?;
+
+    parse_fill_data(user_address.to_string(), fill, None)
+}
+
+/// Trade of the info API's `recentTrades` response and of the WebSocket `trades`
+/// channel, with the buyer and seller in `users`
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct ApiTrade {
+    pub(crate) coin: String,
+    /// Side of the aggressor
+    side: String,
+    px: String,
+    sz: String,
+    pub(crate) time: i64,
+    #[serde(default)]
+    hash: Option<String>,
+    #[serde(default)]
+    pub(crate) tid: Option<i64>,
+    users: Vec<String>,
+}
+
+/// Both sides of an API trade as fills. Fees, PnL and order ids are not published
+/// with trades, so they stay empty until the hour file replaces these fills.
+pub(crate) fn parse_api_trade(trade: &ApiTrade) -> Result<Vec<Fill>> {
+    let [buyer, seller] = trade.users.as_slice() else {
+        return Err(Error::Validation(format!("Trade with {} users", trade.users.len())));
+    };
+
+    let aggressor = parse_side(&trade.side)?;
+    let price = parse_decimal("price", &trade.px)?;
+    let size = parse_decimal("size", &trade.sz)?;
+    let timestamp = DateTime::<Utc>::from_timestamp_millis(trade.time)
+        .ok_or_else(|| Error::Validation(format!("Invalid timestamp: {}", trade.time)))?;
+
+    Ok([(buyer, TradeSide::Buy), (seller, TradeSide::Sell)]
+        .into_iter()
+        .map(|(user, side)| Fill {
+            user_address: normalize_address(user),
+            coin: trade.coin.clone(),
+            crossed: Some(side == aggressor),
+            side,
+            price,
+            size,
+            fee: None,
+            closed_pnl: None,
+            timestamp,
+            block_number: None,
+            source_id: None,
+            tid: trade.tid,
+            oid: None,
+            hash: trade.hash.clone(),
+            dir: None,
+            start_position: None,
+            fee_token: None,
+            liquidation: None,
+            twap_id: None,
+            builder_fee: None,
+            dup_seq: 0,
+        })
+        .collect())
+}
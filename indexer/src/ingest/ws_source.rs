@@ -0,0 +1,563 @@
+use super::cursor::{Cursor, SourceKind};
+use super::schema::{parse_api_trade, ApiTrade};
+use super::IngestSource;
+use crate::market::MarketRegistry;
+use crate::model::{Fill, IngestBatch};
+use async_trait::async_trait;
+use backoff::backoff::Backoff;
+use chrono::{DateTime, Utc};
+use futures::{SinkExt, StreamExt};
+use indexer_core::backoff::create_backoff;
+use indexer_core::config::LiveConfig;
+use indexer_core::{Error, Result};
+use metrics::counter;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tracing::{debug, error, info, instrument, warn};
+
+/// `source_id` of fills built from streamed trades
+pub const WS_TRADES_SOURCE: &str = "api:trades";
+
+/// Hyperliquid closes connections that send nothing for a minute
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Trade messages buffered between the connection and the pipeline. Messages that
+/// arrive while it is full are dropped, so a pipeline busy loading hour files never
+/// stalls the connection long enough for pings to stop.
+const MESSAGE_BUFFER: usize = 1024;
+
+/// First wait before reconnecting; doubles up to a minute while connects keep failing
+const RECONNECT_BASE_DELAY_MS: u64 = 1000;
+
+/// Streams the WebSocket `trades` channel of every coin. One connection task lives
+/// across pages: it reconnects with backoff, resubscribes, skips trades replayed on
+/// resubscribe by trade id and reports trades that may have been missed, checking
+/// every message. Each page is the fills streamed during `ws_flush_ms`. Like the
+/// info API source, its fills are provisional until the hour file is loaded.
+pub struct WebSocketSource {
+    url: String,
+    markets: Arc<MarketRegistry>,
+    coins: Vec<String>,
+    flush_interval: Duration,
+    /// Trades from the connection task, started by the first page
+    trades: Mutex<Option<mpsc::Receiver<Vec<Fill>>>>,
+    /// Possible gaps the connection task found
+    gaps: Arc<AtomicU64>,
+}
+
+/// Trade ids remembered per coin. Replays hold only the latest trades, so older ids
+/// are never needed to recognise a replayed trade.
+const SEEN_TIDS: usize = 4096;
+
+/// Trades of one coin seen so far. Trade ids are hashes of a trade's two orders, not
+/// a sequence, so they tell a replayed trade from a new one but can't show that one
+/// is missing. Gaps are told from messages instead: a replay after subscribing that
+/// shares no trade with those seen before, or a message dropped before the pipeline.
+#[derive(Debug, Default)]
+struct CoinTrades {
+    tids: HashSet<i64>,
+    /// `tids` in the order seen, to forget the oldest
+    order: VecDeque<i64>,
+    /// Newest trade time seen; trades without an id are told apart by time
+    newest: i64,
+    /// Whether the next message is the replay sent on subscribing
+    replay_due: bool,
+}
+
+impl CoinTrades {
+    fn seen(&self, trade: &ApiTrade) -> bool {
+        match trade.tid {
+            Some(tid) => self.tids.contains(&tid),
+            None => trade.time <= self.newest,
+        }
+    }
+
+    /// Trades of one message not seen before, and whether trades may be missing
+    /// before them: the message is a replay that shares no trade with those seen
+    fn unseen<'a>(&mut self, trades: &'a [ApiTrade]) -> (Vec<&'a ApiTrade>, bool) {
+        let new: Vec<&ApiTrade> = trades.iter().filter(|trade| !self.seen(trade)).collect();
+        let replay = std::mem::take(&mut self.replay_due);
+        let gap = replay && self.newest > 0 && !new.is_empty() && new.len() == trades.len();
+        (new, gap)
+    }
+
+    fn mark_seen(&mut self, trades: &[&ApiTrade]) {
+        for trade in trades {
+            self.newest = self.newest.max(trade.time);
+            let Some(tid) = trade.tid else {
+                continue;
+            };
+            if self.tids.insert(tid) {
+                self.order.push_back(tid);
+            }
+            if self.order.len() > SEEN_TIDS {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.tids.remove(&oldest);
+                }
+            }
+        }
+    }
+}
+
+impl WebSocketSource {
+    /// Source streaming from `config.ws_url`, taking coins from `markets` unless `config.coins` lists them
+    pub fn new(config: &LiveConfig, markets: Arc<MarketRegistry>) -> Self {
+        Self {
+            url: config.ws_url.clone(),
+            markets,
+            coins: config.coins.clone(),
+            flush_interval: Duration::from_millis(config.ws_flush_ms),
+            trades: Mutex::new(None),
+            gaps: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Start the connection task and return the receiving end of its trades
+    async fn start_stream(&self) -> Result<mpsc::Receiver<Vec<Fill>>> {
+        let coins = if self.coins.is_empty() {
+            self.markets.perp_coins().await
+        } else {
+            self.coins.clone()
+        };
+        if coins.is_empty() {
+            return Err(Error::Config("No coins to subscribe to; set ingest.live.coins".to_string()));
+        }
+
+        let (sender, receiver) = mpsc::channel(MESSAGE_BUFFER);
+        tokio::spawn(stream_trades(self.url.clone(), coins, sender, Arc::clone(&self.gaps)));
+        Ok(receiver)
+    }
+}
+
+#[async_trait]
+impl IngestSource for WebSocketSource {
+    /// Collect the fills streamed during one flush interval. Pages always report more
+    /// data, so the pipeline asks for the next one right away.
+    #[instrument(skip(self))]
+    async fn fetch_page(
+        &self,
+        start_from: DateTime<Utc>,
+        _cursor: Option<Cursor>,
+    ) -> Result<IngestBatch> {
+        let mut trades = self.trades.lock().await;
+        let receiver = match trades.as_mut() {
+            Some(receiver) => receiver,
+            None => trades.insert(self.start_stream().await?),
+        };
+
+        let deadline = Instant::now() + self.flush_interval;
+        let mut fills = Vec::new();
+        let mut stopped = false;
+        loop {
+            tokio::select! {
+                received = receiver.recv() => match received {
+                    Some(batch) => fills.extend(batch),
+                    None => {
+                        stopped = true;
+                        break;
+                    }
+                },
+                _ = tokio::time::sleep_until(deadline) => break,
+            }
+        }
+
+        if stopped && fills.is_empty() {
+            *trades = None;
+            return Err(Error::Ingest {
+                source_name: self.source_id().to_string(),
+                details: "WebSocket stream stopped".to_string(),
+            });
+        }
+
+        let last_timestamp = fills.iter().map(|fill| fill.timestamp).max();
+        debug!(fills = fills.len(), gaps = self.gaps.load(Ordering::Relaxed), "Collected streamed trades");
+
+        Ok(IngestBatch {
+            fills,
+            cursor: Some(self.hour_cursor(last_timestamp.unwrap_or(start_from))),
+            has_more: true,
+            bytes_downloaded: None,
+            hours: Vec::new(),
+            rejects: Vec::new(),
+            blocks: Vec::new(),
+        })
+    }
+
+    fn hour_cursor(&self, hour: DateTime<Utc>) -> Cursor {
+        Cursor::hourly(SourceKind::WebSocket, None, hour)
+    }
+
+    fn serves_history(&self) -> bool {
+        false
+    }
+
+    fn source_id(&self) -> &str {
+        "websocket"
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        let (mut socket, _) = tokio_tungstenite::connect_async(self.url.as_str())
+            .await
+            .map_err(|e| ws_error(&self.url, e))?;
+        let _ = socket.close(None).await;
+        Ok(())
+    }
+}
+
+/// Keep a subscription to the trades of `coins` alive until `sender` is dropped,
+/// reconnecting with backoff whenever the connection fails. Gives up if the endpoint
+/// refuses connections, so the next page fails instead of waiting forever.
+async fn stream_trades(url: String, coins: Vec<String>, sender: mpsc::Sender<Vec<Fill>>, gaps: Arc<AtomicU64>) {
+    let mut backoff = create_backoff(0, RECONNECT_BASE_DELAY_MS);
+    backoff.max_elapsed_time = None;
+    let mut streams: HashMap<String, CoinTrades> = HashMap::new();
+
+    loop {
+        let error = match tokio_tungstenite::connect_async(url.as_str()).await {
+            Ok((socket, _)) => match stream_connection(socket, &url, &coins, &sender, &mut streams, &gaps, &mut backoff).await {
+                Ok(()) => {
+                    debug!("Pipeline stopped reading trades, closing WebSocket");
+                    return;
+                }
+                Err(e) => e,
+            },
+            Err(e) if refused(&e) => {
+                error!(url = %url, error = %e, "WebSocket endpoint refused the connection, stopping the stream");
+                return;
+            }
+            Err(e) => ws_error(&url, e),
+        };
+
+        let delay = backoff.next_backoff().unwrap_or(backoff.max_interval);
+        warn!(error = %error, retry_after_ms = delay.as_millis(), "WebSocket disconnected, reconnecting");
+        counter!("indexer_ws_reconnects").increment(1);
+
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = sender.closed() => return,
+        }
+    }
+}
+
+/// Subscribe to every coin and forward trades until the connection fails (an error)
+/// or the pipeline stops reading (`Ok`)
+async fn stream_connection(
+    mut socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    url: &str,
+    coins: &[String],
+    sender: &mpsc::Sender<Vec<Fill>>,
+    streams: &mut HashMap<String, CoinTrades>,
+    gaps: &AtomicU64,
+    backoff: &mut impl Backoff,
+) -> Result<()> {
+    for coin in coins {
+        let subscribe = json!({ "method": "subscribe", "subscription": { "type": "trades", "coin": coin } });
+        socket.send(Message::Text(subscribe.to_string())).await.map_err(|e| ws_error(url, e))?;
+    }
+    info!(url = %url, coins = coins.len(), "📡 Subscribed to WebSocket trades");
+
+    // The first message of each coin after subscribing replays its latest trades
+    for coin in coins {
+        streams.entry(coin.clone()).or_default().replay_due = true;
+    }
+    let mut ping = tokio::time::interval_at(Instant::now() + PING_INTERVAL, PING_INTERVAL);
+    let mut dropping = false;
+
+    loop {
+        tokio::select! {
+            _ = ping.tick() => {
+                let ping = json!({ "method": "ping" });
+                socket.send(Message::Text(ping.to_string())).await.map_err(|e| ws_error(url, e))?;
+            }
+            message = socket.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => {
+                        // Only a connection that delivers messages ends the backoff
+                        backoff.reset();
+                        text
+                    }
+                    Some(Ok(Message::Close(frame))) => {
+                        return Err(ws_closed(url, &format!("closed by server: {:?}", frame)));
+                    }
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => return Err(ws_error(url, e)),
+                    None => return Err(ws_closed(url, "stream ended")),
+                };
+
+                let Some((coin, trades)) = parse_trades(&text) else {
+                    continue;
+                };
+                let stream = streams.entry(coin.clone()).or_default();
+                let (new, gap) = stream.unseen(&trades);
+
+                // The hour file fills in whatever the replay doesn't reach back to
+                if gap {
+                    let first = new.iter().map(|trade| trade.time).min().unwrap_or(stream.newest);
+                    warn!(
+                        coin = %coin,
+                        last_seen = ?DateTime::<Utc>::from_timestamp_millis(stream.newest),
+                        next = ?DateTime::<Utc>::from_timestamp_millis(first),
+                        "Possible gap in streamed trades"
+                    );
+                    record_gap(gaps);
+                }
+                if new.is_empty() {
+                    continue;
+                }
+
+                match sender.try_send(trade_fills(&coin, &new)) {
+                    Ok(()) => {
+                        stream.mark_seen(&new);
+                        dropping = false;
+                    }
+                    // Dropped trades stay unseen, so a replay after a reconnect can still deliver them
+                    Err(TrySendError::Full(fills)) => {
+                        if !dropping {
+                            warn!("Pipeline is not reading streamed trades, dropping them until it does");
+                            dropping = true;
+                        }
+                        counter!("indexer_ws_dropped_fills").increment(fills.len() as u64);
+                        record_gap(gaps);
+                    }
+                    Err(TrySendError::Closed(_)) => return Ok(()),
+                }
+            }
+            _ = sender.closed() => return Ok(()),
+        }
+    }
+}
+
+/// Coin and trades of a `trades` message. Other channels, such as
+/// `subscriptionResponse` and `pong`, give none.
+fn parse_trades(text: &str) -> Option<(String, Vec<ApiTrade>)> {
+    let message: Value = match serde_json::from_str(text) {
+        Ok(message) => message,
+        Err(e) => {
+            warn!(error = %e, "Skipping unparseable WebSocket message");
+            return None;
+        }
+    };
+    if message["channel"] != "trades" {
+        return None;
+    }
+
+    let trades: Vec<ApiTrade> = match serde_json::from_value(message["data"].clone()) {
+        Ok(trades) => trades,
+        Err(e) => {
+            warn!(error = %e, "Skipping unparseable trades message");
+            counter!("indexer_ws_rejected_trades").increment(1);
+            return None;
+        }
+    };
+    let coin = trades.first()?.coin.clone();
+
+    Some((coin, trades))
+}
+
+/// Both sides of each of `trades` as fills
+fn trade_fills(coin: &str, trades: &[&ApiTrade]) -> Vec<Fill> {
+    let mut fills = Vec::with_capacity(trades.len() * 2);
+    for trade in trades {
+        match parse_api_trade(trade) {
+            Ok(trade_fills) => fills.extend(trade_fills.into_iter().map(|fill| Fill {
+                source_id: Some(WS_TRADES_SOURCE.to_string()),
+                ..fill
+            })),
+            Err(e) => {
+                warn!(coin = %coin, error = %e, "Skipping unparseable streamed trade");
+                counter!("indexer_ws_rejected_trades").increment(1);
+            }
+        }
+    }
+
+    fills
+}
+
+fn record_gap(gaps: &AtomicU64) {
+    gaps.fetch_add(1, Ordering::Relaxed);
+    counter!("indexer_ws_trade_gaps").increment(1);
+}
+
+/// Whether reconnecting can't fix a failed connect: a malformed URL, or a handshake
+/// the server rejects as a client error other than rate limiting
+fn refused(error: &WsError) -> bool {
+    match error {
+        WsError::Url(_) => true,
+        WsError::Http(response) => {
+            response.status().is_client_error() && response.status() != StatusCode::TOO_MANY_REQUESTS
+        }
+        _ => false,
+    }
+}
+
+fn ws_error(url: &str, error: impl std::fmt::Display) -> Error {
+    ws_closed(url, &error.to_string())
+}
+
+fn ws_closed(url: &str, details: &str) -> Error {
+    Error::Ingest {
+        source_name: "websocket".to_string(),
+        details: format!("{}: {}", url, details),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const BUYER: &str = "0x1111111111111111111111111111111111111111";
+    const SELLER: &str = "0x2222222222222222222222222222222222222222";
+
+    /// WebSocket stub on a local port. Each connection acknowledges `coins`
+    /// subscriptions, sends its messages and closes, except the last one, which stays
+    /// open. Returns its URL and the subscriptions it received.
+    async fn stub_server(coins: usize, connections: Vec<Vec<Value>>) -> (String, Arc<std::sync::Mutex<Vec<Value>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let subscriptions = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let received = Arc::clone(&subscriptions);
+        tokio::spawn(async move {
+            let last = connections.len() - 1;
+            for (index, messages) in connections.into_iter().enumerate() {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+
+                for _ in 0..coins {
+                    let Some(Ok(Message::Text(text))) = socket.next().await else {
+                        return;
+                    };
+                    let request: Value = serde_json::from_str(&text).unwrap();
+                    let response = json!({ "channel": "subscriptionResponse", "data": request });
+                    socket.send(Message::Text(response.to_string())).await.unwrap();
+                    received.lock().unwrap().push(request);
+                }
+                for message in messages {
+                    socket.send(Message::Text(message.to_string())).await.unwrap();
+                }
+
+                if index < last {
+                    let _ = socket.close(None).await;
+                } else {
+                    while let Some(Ok(_)) = socket.next().await {}
+                }
+            }
+        });
+
+        (url, subscriptions)
+    }
+
+    fn source(url: String, coins: &[&str]) -> WebSocketSource {
+        let config = LiveConfig {
+            ws_url: url,
+            ws_flush_ms: 100,
+            coins: coins.iter().map(|coin| coin.to_string()).collect(),
+            ..indexer_core::Config::default().ingest.live
+        };
+        WebSocketSource::new(&config, Arc::new(MarketRegistry::empty()))
+    }
+
+    /// A `trades` message with one BTC trade per tid, a second apart
+    fn trades(tids: &[i64]) -> Value {
+        let data: Vec<Value> = tids
+            .iter()
+            .map(|tid| {
+                json!({
+                    "coin": "BTC", "side": "B", "px": "100000", "sz": "0.1", "time": 1_754_000_000_000 + tid * 1000,
+                    "hash": "0xabc", "tid": tid, "users": [BUYER, SELLER],
+                })
+            })
+            .collect();
+        json!({ "channel": "trades", "data": data })
+    }
+
+    /// Collect pages until `fills` fills arrived, then one more page to catch any extra
+    async fn collect(source: &WebSocketSource, fills: usize) -> Vec<Fill> {
+        let start = DateTime::from_timestamp_millis(1_754_000_000_000).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut collected = Vec::new();
+        while collected.len() < fills && Instant::now() < deadline {
+            collected.extend(source.fetch_page(start, None).await.unwrap().fills);
+        }
+        collected.extend(source.fetch_page(start, None).await.unwrap().fills);
+        collected
+    }
+
+    fn tids(fills: &[Fill]) -> Vec<i64> {
+        let mut tids: Vec<i64> = fills.iter().filter_map(|fill| fill.tid).collect();
+        tids.sort();
+        tids
+    }
+
+    #[tokio::test]
+    async fn subscribes_to_every_coin_and_streams_trades() {
+        let (url, subscriptions) = stub_server(2, vec![vec![trades(&[1])]]).await;
+        let source = source(url, &["BTC", "ETH"]);
+
+        let fills = collect(&source, 2).await;
+
+        assert_eq!(
+            *subscriptions.lock().unwrap(),
+            vec![
+                json!({ "method": "subscribe", "subscription": { "type": "trades", "coin": "BTC" } }),
+                json!({ "method": "subscribe", "subscription": { "type": "trades", "coin": "ETH" } }),
+            ]
+        );
+        assert_eq!(tids(&fills), vec![1, 1]);
+        assert!(fills.iter().all(|fill| fill.source_id.as_deref() == Some(WS_TRADES_SOURCE)));
+        assert_eq!(source.gaps.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn skips_trades_replayed_after_reconnecting() {
+        let (url, _) = stub_server(1, vec![vec![trades(&[1, 2])], vec![trades(&[1, 2, 3])]]).await;
+        let source = source(url, &["BTC"]);
+
+        let fills = collect(&source, 6).await;
+
+        assert_eq!(tids(&fills), vec![1, 1, 2, 2, 3, 3]);
+        assert_eq!(source.gaps.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn reports_gap_when_replay_shares_no_trade() {
+        let (url, _) = stub_server(1, vec![vec![trades(&[1, 2])], vec![trades(&[5, 6])]]).await;
+        let source = source(url, &["BTC"]);
+
+        let fills = collect(&source, 8).await;
+
+        assert_eq!(tids(&fills), vec![1, 1, 2, 2, 5, 5, 6, 6]);
+        assert_eq!(source.gaps.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn fails_page_once_stream_stops() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            // Refuse the handshake
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 4096];
+            let _ = stream.read(&mut request).await;
+            stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n").await.unwrap();
+        });
+        let source = source(url, &["BTC"]);
+
+        let page = source.fetch_page(DateTime::UNIX_EPOCH, None).await;
+
+        assert!(matches!(page, Err(Error::Ingest { details, .. }) if details == "WebSocket stream stopped"));
+        assert!(source.trades.lock().await.is_none());
+    }
+}
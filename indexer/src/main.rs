@@ -8,6 +8,7 @@ mod store;
 use clap::{Parser, Subcommand};
 use indexer_core::{telemetry, Config};
 use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
 use std::process;
 use tracing::{error, info};
 
@@ -15,6 +16,10 @@ use tracing::{error, info};
 #[clap(name = "indexer")]
 #[clap(about = "Hyperliquid historical data indexer", version)]
 struct Cli {
+    /// Serve S3 objects only from the object cache and fail instead of downloading
+    #[clap(long, global = true)]
+    offline: bool,
+
     #[clap(subcommand)]
     command: Commands,
 }
@@ -33,6 +38,10 @@ enum Commands {
         /// Override end timestamp (RFC3339 format)
         #[clap(long, env = "BACKFILL_END")]
         end: Option<chrono::DateTime<chrono::Utc>>,
+
+        /// List the objects the backfill would download and estimate their cost, without fetching
+        #[clap(long)]
+        plan: bool,
     },
 
     /// Run continuous ingestion
@@ -49,6 +58,92 @@ enum Commands {
         #[clap(long, env = "BACKFILL_TO")]
         backfill_to: Option<chrono::DateTime<chrono::Utc>>,
     },
+
+    /// Download raw hour files from S3 into a local mirror directory
+    Mirror {
+        /// First hour to download (RFC3339 format)
+        #[clap(long)]
+        start: chrono::DateTime<chrono::Utc>,
+
+        /// Stop before this timestamp (RFC3339 format, defaults to NOW)
+        #[clap(long)]
+        end: Option<chrono::DateTime<chrono::Utc>>,
+
+        /// Mirror directory (defaults to ingest.source.local_dir)
+        #[clap(long)]
+        dir: Option<String>,
+    },
+
+    /// Load L2 book snapshots from the hyperliquid-archive bucket into l2_book_levels
+    L2Book {
+        /// First hour to load (RFC3339 format)
+        #[clap(long)]
+        start: chrono::DateTime<chrono::Utc>,
+
+        /// Stop before this timestamp (RFC3339 format, defaults to NOW)
+        #[clap(long)]
+        end: Option<chrono::DateTime<chrono::Utc>>,
+
+        /// Comma-separated coins to load (defaults to ingest.archive.l2_book_coins, or every coin)
+        #[clap(long, value_delimiter = ',')]
+        coins: Vec<String>,
+
+        /// Read from a local mirror of the archive bucket instead of S3
+        #[clap(long)]
+        dir: Option<String>,
+    },
+
+    /// Load daily asset contexts from the hyperliquid-archive bucket into asset_ctxs
+    AssetCtxs {
+        /// First day to load (RFC3339 format)
+        #[clap(long)]
+        start: chrono::DateTime<chrono::Utc>,
+
+        /// Stop before this timestamp (RFC3339 format, defaults to NOW)
+        #[clap(long)]
+        end: Option<chrono::DateTime<chrono::Utc>>,
+
+        /// Read from a local mirror of the archive bucket instead of S3
+        #[clap(long)]
+        dir: Option<String>,
+    },
+
+    /// Load explorer blocks from the node data bucket into l1_blocks and l1_transactions
+    ExplorerBlocks {
+        /// First block height to load (defaults to the first block in the bucket)
+        #[clap(long)]
+        start_height: Option<i64>,
+
+        /// Stop before this block height (defaults to the last block in the bucket)
+        #[clap(long)]
+        end_height: Option<i64>,
+
+        /// Read from a local mirror of the node data bucket instead of S3
+        #[clap(long)]
+        dir: Option<String>,
+    },
+
+    /// Load order, cancel and other L1 actions from the node data bucket's replica_cmds into order_actions
+    ReplicaCmds {
+        /// Load files dated from the day of this timestamp (RFC3339 format)
+        #[clap(long)]
+        start: chrono::DateTime<chrono::Utc>,
+
+        /// Stop before this timestamp (RFC3339 format, defaults to NOW)
+        #[clap(long)]
+        end: Option<chrono::DateTime<chrono::Utc>>,
+
+        /// Read from a local mirror of the node data bucket instead of S3
+        #[clap(long)]
+        dir: Option<String>,
+    },
+
+    /// Parse quarantined lines and fills in fill_rejects again and ingest the ones that now succeed
+    ReprocessRejects {
+        /// Maximum number of rejects to examine
+        #[clap(long)]
+        limit: Option<usize>,
+    },
 }
 
 #[tokio::main]
@@ -68,28 +163,19 @@ async fn run() -> anyhow::Result<()> {
     telemetry::init(&config.telemetry)?;
 
     let cli = Cli::parse();
-
-    // Create database connection pool
-    let pool = PgPoolOptions::new()
-        .max_connections(config.database.max_connections)
-        .min_connections(config.database.min_connections)
-        .acquire_timeout(std::time::Duration::from_secs(
-            config.database.connect_timeout_secs,
-        ))
-        .idle_timeout(std::time::Duration::from_secs(
-            config.database.idle_timeout_secs,
-        ))
-        .connect(&config.database.url)
-        .await?;
+    if cli.offline {
+        config.ingest.source.offline = true;
+    }
 
     match cli.command {
         Commands::Migrate => {
+            let pool = connect(&config).await?;
             info!("Running database migrations");
             sqlx::migrate!("../migrations").run(&pool).await?;
             info!("Migrations completed successfully");
         }
 
-        Commands::Backfill { start, end } => {
+        Commands::Backfill { start, end, plan } => {
             // Override config with CLI args
             if let Some(start) = start {
                 config.ingest.start_from = Some(start);
@@ -101,11 +187,18 @@ async fn run() -> anyhow::Result<()> {
                 "Starting backfill"
             );
 
+            let pool = connect(&config).await?;
             let app = app::App::new(config, pool).await?;
-            app.run_backfill(end).await?;
+            if plan {
+                app.plan_backfill(end).await?;
+            } else {
+                app.run_backfill(end).await?;
+            }
         }
 
         Commands::Run { start, backfill_from, backfill_to } => {
+            let pool = connect(&config).await?;
+
             // Override config with CLI args
             if let Some(start) = start {
                 config.ingest.start_from = Some(start);
@@ -142,8 +235,89 @@ async fn run() -> anyhow::Result<()> {
             let app = app::App::new(config, pool).await?;
             app.run_continuous().await?;
         }
+
+        Commands::Mirror { start, end, dir } => {
+            let dir = dir
+                .or_else(|| config.ingest.source.local_dir.clone())
+                .ok_or_else(|| anyhow::anyhow!("--dir or ingest.source.local_dir is required for mirror"))?;
+            let end = end.unwrap_or_else(chrono::Utc::now);
+
+            let schemas = ingest::schema::SchemaRegistry::from_config(&config.ingest.source)?;
+            let source = ingest::S3Source::new(
+                &config.ingest.source,
+                std::sync::Arc::new(schemas),
+                ingest::parse_pool::ParsePool::new(config.ingest.parse_workers),
+            ).await?;
+
+            let mut mirror = ingest::mirror::Mirror::open(source, dir).await?;
+            let summary = mirror.sync_range(start, end).await?;
+
+            if summary.failed > 0 {
+                anyhow::bail!("{} objects failed to download, re-run mirror to retry", summary.failed);
+            }
+        }
+
+        Commands::L2Book { start, end, coins, dir } => {
+            let end = end.unwrap_or_else(chrono::Utc::now);
+            let pool = connect(&config).await?;
+            let store = std::sync::Arc::new(store::Store::new(pool).await?);
+
+            let loader = ingest::l2_book::L2BookLoader::from_config(&config, store, coins, dir).await?;
+            loader.run(start, end).await?;
+        }
+
+        Commands::AssetCtxs { start, end, dir } => {
+            let end = end.unwrap_or_else(chrono::Utc::now);
+            let pool = connect(&config).await?;
+            let store = std::sync::Arc::new(store::Store::new(pool).await?);
+
+            let loader = ingest::asset_ctxs::AssetCtxLoader::from_config(&config, store, dir).await?;
+            loader.run(start, end).await?;
+        }
+
+        Commands::ExplorerBlocks { start_height, end_height, dir } => {
+            let pool = connect(&config).await?;
+            let store = std::sync::Arc::new(store::Store::new(pool).await?);
+
+            let loader = ingest::explorer_blocks::ExplorerBlockLoader::from_config(&config, store, dir).await?;
+            loader.run(start_height, end_height).await?;
+        }
+
+        Commands::ReplicaCmds { start, end, dir } => {
+            let end = end.unwrap_or_else(chrono::Utc::now);
+            let pool = connect(&config).await?;
+            let store = std::sync::Arc::new(store::Store::new(pool).await?);
+
+            let loader = ingest::replica_cmds::ReplicaCmdsLoader::from_config(&config, store, dir).await?;
+            loader.run(start, end).await?;
+        }
+
+        Commands::ReprocessRejects { limit } => {
+            let pool = connect(&config).await?;
+            let store = store::Store::new(pool).await?;
+            let schemas = ingest::schema::SchemaRegistry::from_config(&config.ingest.source)?;
+
+            ingest::rejects::reprocess_rejects(&store, &schemas, limit).await?;
+        }
     }
 
     telemetry::shutdown();
     Ok(())
+}
+
+async fn connect(config: &Config) -> anyhow::Result<PgPool> {
+    // Create database connection pool
+    let pool = PgPoolOptions::new()
+        .max_connections(config.database.max_connections)
+        .min_connections(config.database.min_connections)
+        .acquire_timeout(std::time::Duration::from_secs(
+            config.database.connect_timeout_secs,
+        ))
+        .idle_timeout(std::time::Duration::from_secs(
+            config.database.idle_timeout_secs,
+        ))
+        .connect(&config.database.url)
+        .await?;
+
+    Ok(pool)
 }
\ No newline at end of file
@@ -14,6 +14,7 @@ pub struct MarketRegistry {
     api_endpoint: String,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct MarketInfo {
     pub id: i32,
@@ -45,12 +46,14 @@ struct HyperliquidMeta {
     universe: Vec<AssetInfo>,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 struct HyperliquidSpotMeta {
     tokens: Vec<SpotToken>,
     universe: Vec<SpotAsset>,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 struct AssetInfo {
     name: String,
@@ -58,6 +61,7 @@ struct AssetInfo {
     sz_decimals: u32,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 struct SpotToken {
     name: String,
@@ -67,6 +71,7 @@ struct SpotToken {
     is_canonical: Option<bool>,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 struct SpotAsset {
     name: String,
@@ -266,6 +271,37 @@ impl MarketRegistry {
         Ok(id)
     }
 
+    /// Registry without markets or a reachable database, for sources given their coins
+    #[cfg(test)]
+    pub(crate) fn empty() -> Self {
+        Self::offline(PgPool::connect_lazy("postgres://localhost/unused").unwrap(), 1)
+    }
+
+    /// Registry over `pool` that never calls the API, so markets are only created in the database
+    #[cfg(test)]
+    pub(crate) fn offline(pool: PgPool, exchange_id: i32) -> Self {
+        Self {
+            pool,
+            exchange_id,
+            markets: Arc::new(RwLock::new(HashMap::new())),
+            api_endpoint: String::new(),
+        }
+    }
+
+    /// Coins of the perp markets known to the registry, e.g. `BTC`, in name order
+    pub async fn perp_coins(&self) -> Vec<String> {
+        let mut coins: Vec<String> = self.markets
+            .read()
+            .await
+            .values()
+            .filter(|info| info.market_type == MarketType::Perp)
+            .map(|info| info.market_id.clone())
+            .collect();
+        coins.sort();
+        coins
+    }
+
+    #[allow(dead_code)]
     pub async fn get_market_info(&self, market_id: &str) -> Option<MarketInfo> {
         self.markets.read().await.get(market_id).cloned()
     }
@@ -1,23 +1,128 @@
+use crate::ingest::cursor::Cursor;
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+/// `source_id` prefix of fills from the live API. They are provisional: loading the
+/// hour file with the same trades replaces them.
+pub const PROVISIONAL_SOURCE_PREFIX: &str = "api:";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Fill {
     pub user_address: String,
     pub coin: String,
     pub side: TradeSide,
-    pub price: f64,
-    pub size: f64,
-    pub fee: Option<f64>,
-    pub closed_pnl: Option<f64>,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub fee: Option<Decimal>,
+    pub closed_pnl: Option<Decimal>,
     pub timestamp: DateTime<Utc>,
     pub block_number: Option<i64>,
     pub source_id: Option<String>,
+    /// Trade id shared by both sides of a match
+    pub tid: Option<i64>,
+    /// Order id of this side
+    pub oid: Option<i64>,
+    /// L1 transaction hash
+    pub hash: Option<String>,
+    /// True if this side took liquidity
+    pub crossed: Option<bool>,
+    /// Direction as reported upstream, e.g. "Open Long", "Close Short", "Buy"
+    pub dir: Option<String>,
+    pub start_position: Option<Decimal>,
+    pub fee_token: Option<String>,
+    pub liquidation: Option<Liquidation>,
+    pub twap_id: Option<i64>,
+    pub builder_fee: Option<Decimal>,
+    /// Occurrence index among fills in the same hour file without a `tid` that share
+    /// user, coin, timestamp, price, size and side. Part of the fallback dedupe key.
+    pub dup_seq: i32,
+}
+
+impl Fill {
+    /// Whether this fill came from the live API and is replaced once its hour file is loaded
+    pub fn is_provisional(&self) -> bool {
+        self.source_id.as_deref().is_some_and(|source| source.starts_with(PROVISIONAL_SOURCE_PREFIX))
+    }
+
+    /// Number identical tid-less fills in file order so the fallback dedupe key keeps
+    /// each of them. Must run over a whole hour file to give stable keys on re-ingest.
+    pub fn assign_dup_seqs(fills: &mut [Fill]) {
+        DupSeqs::default().assign(fills);
+    }
+
+    /// Check every numeric value against the precision of its `fills` column.
+    /// Returns the first column that would overflow, so the fill can be quarantined
+    /// instead of failing the whole COPY.
+    pub fn numeric_overflow(&self) -> Option<String> {
+        let liquidation_mark_price = self.liquidation.as_ref().and_then(|l| l.mark_price);
+        let checks = [
+            ("price", Some(self.price), 20),
+            ("size", Some(self.size), 20),
+            // Generated column volume_usd = price * size
+            ("volume_usd", self.price.checked_mul(self.size).or(Some(Decimal::MAX)), 20),
+            ("fee", self.fee, 20),
+            ("closed_pnl", self.closed_pnl, 20),
+            ("start_position", self.start_position, 30),
+            ("liquidation_mark_price", liquidation_mark_price, 20),
+            ("builder_fee", self.builder_fee, 20),
+        ];
+
+        checks.into_iter().find_map(|(column, value, precision)| {
+            value
+                .filter(|v| !fits_numeric(*v, precision, NUMERIC_SCALE))
+                .map(|v| format!("{} {} overflows NUMERIC({}, {})", column, v, precision, NUMERIC_SCALE))
+        })
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
+type DupSeqKey = (String, String, i64, Decimal, Decimal, bool);
+
+/// Running `dup_seq` numbering for one hour file. Streamed hours are parsed in
+/// chunks, so the counts are kept across chunks until the file ends.
+#[derive(Debug, Default)]
+pub struct DupSeqs {
+    seen: std::collections::HashMap<DupSeqKey, i32>,
+}
+
+impl DupSeqs {
+    /// Number the tid-less fills of the next chunk, continuing from earlier chunks
+    pub fn assign(&mut self, fills: &mut [Fill]) {
+        for fill in fills.iter_mut().filter(|f| f.tid.is_none()) {
+            let key = (
+                fill.user_address.clone(),
+                fill.coin.clone(),
+                fill.timestamp.timestamp_millis(),
+                fill.price,
+                fill.size,
+                fill.side == TradeSide::Buy,
+            );
+            let seq = self.seen.entry(key).or_insert(0);
+            fill.dup_seq = *seq;
+            *seq += 1;
+        }
+    }
+}
+
+/// Scale of every decimal column in `fills`
+const NUMERIC_SCALE: u32 = 10;
+
+/// Whether `value` can be stored in a Postgres `NUMERIC(precision, scale)` column
+fn fits_numeric(value: Decimal, precision: u32, scale: u32) -> bool {
+    let limit = Decimal::from_i128_with_scale(10i128.pow(precision - scale), 0);
+    value.round_dp(scale).abs() < limit
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Liquidation {
+    pub liquidated_user: Option<String>,
+    pub mark_price: Option<Decimal>,
+    pub method: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "text")]
 #[sqlx(rename_all = "UPPERCASE")]
 pub enum TradeSide {
@@ -34,6 +139,120 @@ impl std::fmt::Display for TradeSide {
     }
 }
 
+/// Side of an order book level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BookSide {
+    Bid,
+    Ask,
+}
+
+impl std::fmt::Display for BookSide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BookSide::Bid => write!(f, "BID"),
+            BookSide::Ask => write!(f, "ASK"),
+        }
+    }
+}
+
+/// One price level of an L2 book snapshot, kept in `l2_book_levels`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookLevel {
+    pub coin: String,
+    /// Exchange time of the snapshot
+    pub time: DateTime<Utc>,
+    pub side: BookSide,
+    /// Position from the top of the book, 0 for the best price
+    pub level: i16,
+    pub price: Decimal,
+    pub size: Decimal,
+    /// Orders resting at this price
+    pub order_count: i32,
+}
+
+/// Context of one asset at one time from the archive's daily `asset_ctxs` files,
+/// kept in `asset_ctxs`. Values missing from a file are `None`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetCtx {
+    pub coin: String,
+    pub time: DateTime<Utc>,
+    /// Hourly funding rate
+    pub funding: Option<Decimal>,
+    pub open_interest: Option<Decimal>,
+    pub prev_day_px: Option<Decimal>,
+    /// Notional volume over the previous 24 hours
+    pub day_ntl_vlm: Option<Decimal>,
+    pub premium: Option<Decimal>,
+    pub oracle_px: Option<Decimal>,
+    pub mark_px: Option<Decimal>,
+    pub mid_px: Option<Decimal>,
+    pub impact_bid_px: Option<Decimal>,
+    pub impact_ask_px: Option<Decimal>,
+}
+
+/// Header of one explorer block from the node data bucket, kept in `l1_blocks`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct L1Block {
+    pub height: i64,
+    pub block_time: Option<DateTime<Utc>>,
+    pub hash: Option<String>,
+    /// Validator that proposed the block
+    pub proposer: Option<String>,
+    pub tx_count: i32,
+    /// Key of the file the block was read from
+    pub key: String,
+}
+
+/// One transaction of an explorer block, kept in `l1_transactions`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct L1Transaction {
+    pub block_height: i64,
+    /// Position of the transaction within its block
+    pub tx_index: i32,
+    pub hash: Option<String>,
+    pub time: Option<DateTime<Utc>>,
+    /// `type` of the transaction's action, e.g. `order` or `cancel`
+    pub action_type: Option<String>,
+    /// Address that signed the transaction
+    pub user_address: Option<String>,
+    /// Error the exchange returned, if the transaction failed
+    pub error: Option<String>,
+}
+
+/// One item of an L1 action from `replica_cmds`, kept in `order_actions`: an order of
+/// an `order` action, a cancel of a `cancel` action, or a whole action of any other type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderAction {
+    pub block_number: i64,
+    pub block_time: Option<DateTime<Utc>>,
+    /// Position of the signed action within its block
+    pub tx_index: i32,
+    /// Position of the item within its action
+    pub item_index: i32,
+    /// Address the exchange attributed the action to
+    pub user_address: Option<String>,
+    pub vault_address: Option<String>,
+    /// `type` of the action, e.g. `order`, `cancel`, `updateLeverage`
+    pub action_type: String,
+    /// Asset index the action refers to
+    pub asset: Option<i32>,
+    /// Order id, from the response for new orders and from the action for cancels and modifies
+    pub oid: Option<i64>,
+    pub cloid: Option<String>,
+    pub is_buy: Option<bool>,
+    pub price: Option<Decimal>,
+    pub size: Option<Decimal>,
+    pub reduce_only: Option<bool>,
+    /// Time in force of a limit order, or `Trigger`
+    pub order_type: Option<String>,
+    /// Outcome, e.g. `resting`, `filled`, `success`, `error`
+    pub status: Option<String>,
+    pub error: Option<String>,
+    /// The action as sent, for types without dedicated columns
+    pub details: Option<serde_json::Value>,
+}
+
+#[allow(dead_code)]
 #[derive(Debug, Clone, FromRow)]
 pub struct FillRow {
     pub id: Uuid,
@@ -48,20 +267,132 @@ pub struct FillRow {
     pub block_number: Option<i64>,
     pub source_id: Option<String>,
     pub ingested_at: DateTime<Utc>,
+    pub tid: Option<i64>,
+    pub oid: Option<i64>,
+    pub hash: Option<String>,
+    pub crossed: Option<bool>,
+    pub dir: Option<String>,
+    pub start_position: Option<rust_decimal::Decimal>,
+    pub fee_token: Option<String>,
+    pub liquidated_user: Option<String>,
+    pub liquidation_mark_price: Option<rust_decimal::Decimal>,
+    pub liquidation_method: Option<String>,
+    pub twap_id: Option<i64>,
+    pub builder_fee: Option<rust_decimal::Decimal>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IngestBatch {
     pub fills: Vec<Fill>,
-    pub cursor: Option<String>,
+    pub cursor: Option<Cursor>,
     pub has_more: bool,
     pub bytes_downloaded: Option<u64>,
+    /// One manifest entry per hour file, or chunk of an hour file, in this batch
+    pub hours: Vec<HourReport>,
+    /// Lines and fills from these hours that could not be ingested
+    pub rejects: Vec<FillReject>,
+    /// Block headers from `node_fills_by_block` lines parsed for this batch
+    pub blocks: Vec<Block>,
+}
+
+/// Header of one `node_fills_by_block` line, kept in `blocks`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Block {
+    pub block_number: i64,
+    /// When the block was produced on L1
+    pub block_time: Option<DateTime<Utc>>,
+    /// When the node that wrote the file processed the block
+    pub local_time: Option<DateTime<Utc>>,
+    /// Fill events in the block, including any that were rejected
+    pub fill_count: usize,
+    pub hour: Option<DateTime<Utc>>,
+    /// Key of the hour file the block was read from
+    pub key: Option<String>,
+}
+
+/// Manifest entry for one hour file: where it was found, how it parsed and whether it loaded.
+/// Streamed hours report once per chunk; counts cover that chunk only.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HourReport {
+    pub hour: DateTime<Utc>,
+    pub key: String,
+    /// Index of the chunk within the hour file, starting at 0
+    pub chunk: usize,
+    /// Set on the chunk that finishes the file
+    pub last_chunk: bool,
+    /// No file was published for this hour although later hours exist
+    pub missing_upstream: bool,
+    pub expected_format: String,
+    pub detected_format: Option<String>,
+    /// ETag of the object, if the store reports one
+    pub etag: Option<String>,
+    pub compressed_bytes: u64,
+    pub lines: usize,
+    /// Lowest and highest block number seen in the file so far, for formats with blocks
+    pub first_block: Option<i64>,
+    pub last_block: Option<i64>,
+    /// Lines with a block number in this chunk
+    pub block_count: usize,
+    pub fills: usize,
+    /// Lines rejected while parsing
+    pub rejected: usize,
+    /// Why the hour could not be loaded; `None` for a successful load
+    pub error: Option<String>,
+}
+
+impl HourReport {
+    /// Manifest status once this report is written: `complete`, `partial`, `failed`
+    /// or `missing_upstream`
+    pub fn status(&self) -> &'static str {
+        match (&self.error, self.last_chunk) {
+            (Some(_), _) => "failed",
+            (None, _) if self.missing_upstream => "missing_upstream",
+            (None, true) => "complete",
+            (None, false) => "partial",
+        }
+    }
 }
 
+/// Where a streamed page ended, after its batches were sent to the pipeline
+#[derive(Debug, Clone, Default)]
+pub struct PageSummary {
+    pub cursor: Option<Cursor>,
+    pub has_more: bool,
+    pub fills: usize,
+    /// Compressed bytes downloaded for the page
+    pub bytes: u64,
+    pub last_timestamp: Option<DateTime<Utc>>,
+}
+
+/// A line or fill that could not be ingested, kept in `fill_rejects` so it can be
+/// re-processed once the parser or schema is fixed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FillReject {
+    pub hour: Option<DateTime<Utc>>,
+    pub key: Option<String>,
+    pub line_number: Option<i32>,
+    pub raw_line: Option<String>,
+    pub reason: String,
+    /// Record format the line was parsed as
+    pub format: Option<String>,
+    /// The parsed fill, when it was rejected after parsing
+    pub payload: Option<serde_json::Value>,
+    pub fill_timestamp: Option<DateTime<Utc>>,
+}
+
+/// A row of `fill_rejects` that has not been re-processed yet
 #[derive(Debug, Clone, FromRow)]
+pub struct StoredReject {
+    pub id: i64,
+    pub raw_line: Option<String>,
+    pub schema_format: Option<String>,
+    pub payload: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone)]
 pub struct Checkpoint {
     pub source: String,
-    pub cursor: Option<String>,
+    pub cursor: Option<Cursor>,
     pub last_record_ts: Option<DateTime<Utc>>,
     pub last_block_number: Option<i64>,
     pub records_processed: i64,
@@ -83,6 +414,7 @@ impl Checkpoint {
     }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DailyStats {
     pub date: chrono::NaiveDate,
@@ -98,6 +430,7 @@ pub struct DailyStats {
     pub close_price: Option<f64>,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserStats {
     pub user_address: String,
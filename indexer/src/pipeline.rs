@@ -1,10 +1,13 @@
+use crate::ingest::budget::{format_bytes, DownloadBudget, DownloadPlan};
+use crate::ingest::cursor::Cursor;
 use crate::ingest::IngestSource;
-use crate::model::{Checkpoint, IngestBatch};
+use crate::model::{Checkpoint, IngestBatch, PageSummary};
 use crate::store::Store;
 use chrono::{DateTime, Timelike, Utc};
 use indexer_core::backoff::retry_with_backoff;
 use indexer_core::{Error, Result};
 use metrics::{counter, gauge, histogram};
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
@@ -66,11 +69,7 @@ impl Pipeline {
                     );
 
                     // Reset checkpoint to earliest gap
-                    let gap_cursor = format!(
-                        "{}_{}",
-                        earliest_gap.format("%Y%m%d"),
-                        earliest_gap.hour()
-                    );
+                    let gap_cursor = self.source.hour_cursor(*earliest_gap);
 
                     info!(
                         "📍 Resetting checkpoint from {} to {} (cursor: {})",
@@ -89,32 +88,43 @@ impl Pipeline {
             }
         }
 
-        // Then check if we already have data for this time range
-        let (has_data, existing_count) = self.store.check_time_range_exists(start_from, end_at).await?;
+        // Then check whether the manifest already has every hour of this range
+        let missing_hours = self.store.get_missing_hours(start_from, end_at).await?;
+        let missing_upstream = self.store.get_missing_upstream_hours(start_from, end_at).await?;
 
-        if has_data {
-            // Check if we have complete data or just partial
-            let missing_hours = self.store.get_missing_hours(start_from, end_at).await?;
+        if missing_hours.is_empty() && missing_upstream.is_empty() {
+            info!(
+                start = %start_from,
+                end = %end_at,
+                "⏩ Skipping backfill - every hour in this range is already loaded. Save money! 💰"
+            );
+            return Ok(());
+        }
 
-            if missing_hours.is_empty() {
-                info!(
-                    start = %start_from,
-                    end = %end_at,
-                    existing_count = existing_count,
-                    "⏩ Skipping backfill - complete data already exists for this time range. Save money! 💰"
-                );
-                return Ok(());
-            } else {
-                warn!(
-                    start = %start_from,
-                    end = %end_at,
-                    existing_count = existing_count,
-                    missing_hours = missing_hours.len(),
-                    "⚠️ Found {} hours with missing/incomplete data. Will fetch all data to ensure completeness.",
-                    missing_hours.len()
-                );
-                // Continue with backfill to fill gaps
+        info!(
+            start = %start_from,
+            end = %end_at,
+            missing_hours = missing_hours.len(),
+            missing_upstream = missing_upstream.len(),
+            "Found {} hours not yet loaded and {} hours missing upstream to check again",
+            missing_hours.len(),
+            missing_upstream.len()
+        );
+
+        // Price the download before it starts, so a mistyped range can't run up a bill
+        let budget = DownloadBudget::new(&self.config.ingest, self.source.transfer_billed());
+        let mut to_fetch: Vec<DateTime<Utc>> = missing_hours.iter().chain(&missing_upstream).copied().collect();
+        to_fetch.sort();
+
+        match self.source.plan_download(&to_fetch).await {
+            Ok(Some(plan)) => {
+                self.log_plan(&plan, &budget);
+                budget.check(plan.bytes).inspect_err(|e| {
+                    error!(error = %e, "💸 Refusing to start backfill; raise the budget or narrow the range");
+                })?;
             }
+            Ok(None) => {}
+            Err(e) => warn!(error = %e, "Failed to plan download, relying on the running budget check"),
         }
 
         // Smart restart: Check if we should skip to the first gap or continue from checkpoint
@@ -153,12 +163,7 @@ impl Pipeline {
                     );
 
                     // Create cursor for the gap hour
-                    let gap_cursor = Some(format!(
-                        "{}_{}",
-                        first_gap.format("%Y%m%d"),
-                        first_gap.hour()
-                    ));
-                    (first_gap, gap_cursor)
+                    (first_gap, Some(self.source.hour_cursor(first_gap)))
                 } else if !gaps_after_start.is_empty() {
                     info!(
                         "ℹ️ Found {} hours of gaps before checkpoint, but continuing from checkpoint for efficiency",
@@ -196,7 +201,8 @@ impl Pipeline {
         let (tx, mut rx) = mpsc::channel::<IngestBatch>(self.config.pipeline.channel_buffer_size);
 
         // Spawn fetcher task
-        let fetcher = self.spawn_fetcher(tx, current_start, cursor.clone(), end_at);
+        let fetcher = self.spawn_fetcher(tx, current_start, cursor.clone(), end_at, budget.clone());
+        let previous_download = DownloadBudget::previous(checkpoint.metadata.as_ref());
 
         // Process batches
         let mut total_processed = checkpoint.records_processed;
@@ -222,10 +228,10 @@ impl Pipeline {
 
             // Process batch with retries
             let inserted = retry_with_backoff(
-                || self.store.insert_fills(&batch.fills),
+                || self.store.insert_batch(&batch),
                 self.config.ingest.max_retries,
                 self.config.ingest.retry_base_delay_ms,
-                "insert_fills",
+                "insert_batch",
             )
             .await?;
 
@@ -236,14 +242,41 @@ impl Pipeline {
             let should_update_checkpoint = if let Some(last_fill) = batch.fills.last() {
                 checkpoint.last_record_ts.map_or(true, |ts| last_fill.timestamp >= ts)
             } else {
-                false
+                // A batch without fills, e.g. a page ending in hours missing upstream,
+                // can still move the cursor forward
+                match (&batch.cursor, &checkpoint.cursor) {
+                    (Some(next), Some(current)) => next.hour > current.hour,
+                    (Some(_), None) => checkpoint.last_record_ts.is_none(),
+                    _ => false,
+                }
             };
 
+            // Hours of a page load concurrently, so the cursor's hour can still be loading
+            // after later hours moved the timestamp on. Its block only ever grows.
+            let block_advanced = matches!(
+                (&batch.cursor, &checkpoint.cursor),
+                (Some(next), Some(current)) if next.hour == current.hour && next.block > current.block
+            );
+
             if should_update_checkpoint {
                 if let Some(last_fill) = batch.fills.last() {
                     checkpoint.last_record_ts = Some(last_fill.timestamp);
                     checkpoint.last_block_number = last_fill.block_number;
                 }
+                // Batches of later hours in the page carry its cursor without a block;
+                // keep the block already loaded from the cursor's hour
+                checkpoint.cursor = match (batch.cursor.clone(), &checkpoint.cursor) {
+                    (Some(mut next), Some(current))
+                        if next.block.is_none() && next.hour == current.hour =>
+                    {
+                        next.block = current.block;
+                        Some(next)
+                    }
+                    (next, _) => next,
+                };
+                checkpoint.records_processed = total_processed;
+                any_checkpoint_updates = true;
+            } else if block_advanced {
                 checkpoint.cursor = batch.cursor.clone();
                 checkpoint.records_processed = total_processed;
                 any_checkpoint_updates = true;
@@ -303,8 +336,14 @@ impl Pipeline {
             }
 
             // Save checkpoint periodically (only if we're updating it)
-            if should_update_checkpoint &&
+            if (should_update_checkpoint || block_advanced) &&
                last_checkpoint_save.elapsed() > Duration::from_secs(self.config.pipeline.checkpoint_interval_secs) {
+                checkpoint.metadata = Some(budget.record(
+                    checkpoint.metadata.as_ref(),
+                    &previous_download,
+                    total_bytes_downloaded,
+                    budget.check(total_bytes_downloaded).is_err(),
+                ));
                 self.store.save_checkpoint(&checkpoint).await?;
                 last_checkpoint_save = Instant::now();
 
@@ -320,8 +359,14 @@ impl Pipeline {
             histogram!("indexer_pipeline_batch_size").record(batch_size as f64);
         }
 
-        // Final checkpoint save (only if we made forward progress)
-        if any_checkpoint_updates {
+        // Final checkpoint save (only if we made forward progress or downloaded anything)
+        if any_checkpoint_updates || total_bytes_downloaded > 0 {
+            checkpoint.metadata = Some(budget.record(
+                checkpoint.metadata.as_ref(),
+                &previous_download,
+                total_bytes_downloaded,
+                budget.check(total_bytes_downloaded).is_err(),
+            ));
             self.store.save_checkpoint(&checkpoint).await?;
         }
 
@@ -336,6 +381,7 @@ impl Pipeline {
         };
 
         let total_mb = total_bytes_downloaded as f64 / (1024.0 * 1024.0);
+        let total_cost = budget.cost_usd(total_bytes_downloaded);
         let elapsed_time = pipeline_start_time.elapsed();
         let throughput_mbps = if elapsed_time.as_secs() > 0 {
             (total_mb * 8.0) / elapsed_time.as_secs() as f64
@@ -344,17 +390,76 @@ impl Pipeline {
         };
 
         info!(
-            "✨ Backfill completed! Processed {} records | Downloaded: {:.1} MB | Rate: {:.0} records/sec | Throughput: {:.1} Mbps | Batches: {}",
+            "✨ Backfill completed! Processed {} records | Downloaded: {:.1} MB (~${:.2}) | Rate: {:.0} records/sec | Throughput: {:.1} Mbps | Batches: {}",
             total_processed,
             total_mb,
+            total_cost,
             rate,
             throughput_mbps,
             batches_processed
         );
 
+        let missing_upstream = self.store.get_missing_upstream_hours(start_from, end_at).await?;
+        if let (Some(first), Some(last)) = (missing_upstream.first(), missing_upstream.last()) {
+            warn!(
+                "🕳️ {} hours are missing upstream between {} and {}; backfills that reach them list them again",
+                missing_upstream.len(),
+                first.format("%Y-%m-%d %H:%M"),
+                last.format("%Y-%m-%d %H:%M")
+            );
+        }
+
         Ok(())
     }
 
+    /// Print what a backfill of `[start_from, end_at)` would download and cost,
+    /// without fetching anything
+    #[instrument(skip(self))]
+    pub async fn plan_backfill(&self, end_at: Option<DateTime<Utc>>) -> Result<()> {
+        let start_from = self.config.ingest.start_from
+            .ok_or_else(|| Error::Config("start_from is required for backfill".to_string()))?;
+        let end_at = end_at.unwrap_or_else(Utc::now);
+
+        let mut hours = self.store.get_missing_hours(start_from, end_at).await?;
+        hours.extend(self.store.get_missing_upstream_hours(start_from, end_at).await?);
+        hours.sort();
+
+        let plan = self.source.plan_download(&hours).await?.ok_or_else(|| {
+            Error::Config(format!("source {} cannot list its objects", self.source.source_id()))
+        })?;
+
+        let budget = DownloadBudget::new(&self.config.ingest, self.source.transfer_billed());
+        info!(
+            start = %start_from,
+            end = %end_at,
+            "📋 Backfill plan"
+        );
+        self.log_plan(&plan, &budget);
+
+        match budget.check(plan.bytes) {
+            Ok(()) => info!("✅ Plan fits the download budget"),
+            Err(e) => warn!(error = %e, "💸 Plan exceeds the download budget; backfill would refuse to start"),
+        }
+
+        Ok(())
+    }
+
+    fn log_plan(&self, plan: &DownloadPlan, budget: &DownloadBudget) {
+        info!(
+            hours = plan.hours,
+            objects = plan.objects,
+            bytes = plan.bytes,
+            missing_upstream = plan.missing_upstream,
+            unpublished = plan.unpublished,
+            "💰 Download estimate: {} objects | {} | ~${:.2} transfer | {} hours missing upstream | {} not published yet",
+            plan.objects,
+            format_bytes(plan.bytes),
+            budget.cost_usd(plan.bytes),
+            plan.missing_upstream,
+            plan.unpublished
+        );
+    }
+
     #[instrument(skip(self))]
     pub async fn run_continuous(&self) -> Result<()> {
         info!("Starting continuous ingestion pipeline");
@@ -366,7 +471,7 @@ impl Pipeline {
             .unwrap_or_else(|| Checkpoint::new(self.source.source_id().to_string()));
 
         // Auto-refresh checkpoint for continuous ingestion if gaps detected
-        if let Some(checkpoint_ts) = checkpoint.last_record_ts {
+        if let Some(checkpoint_ts) = checkpoint.last_record_ts.filter(|_| self.source.serves_history()) {
             let now = Utc::now();
             let lookback_start = now - chrono::Duration::days(7); // Check last 7 days for gaps
             let gap_detection_start = checkpoint_ts.max(lookback_start);
@@ -382,11 +487,7 @@ impl Pipeline {
                     );
 
                     // Reset checkpoint to earliest gap
-                    let gap_cursor = format!(
-                        "{}_{}",
-                        earliest_gap.format("%Y%m%d"),
-                        earliest_gap.hour()
-                    );
+                    let gap_cursor = self.source.hour_cursor(*earliest_gap);
 
                     info!(
                         "📍 Resetting checkpoint from {} to {} (cursor: {})",
@@ -411,6 +512,7 @@ impl Pipeline {
 
         let mut current_start = start_from;
         let mut cursor = checkpoint.cursor;
+        let metadata = checkpoint.metadata;
         let mut total_processed = checkpoint.records_processed;
 
         // Create shutdown channel
@@ -437,31 +539,31 @@ impl Pipeline {
 
                 result = self.fetch_and_process_batch(current_start, cursor.clone()) => {
                     match result {
-                        Ok((batch, inserted)) => {
+                        Ok((page, inserted, last_block_number)) => {
                             total_processed += inserted as i64;
 
                             // Update state for next iteration
-                            if let Some(last_fill) = batch.fills.last() {
-                                current_start = last_fill.timestamp;
+                            if let Some(last_timestamp) = page.last_timestamp {
+                                current_start = last_timestamp;
                             }
-                            cursor = batch.cursor;
+                            cursor = page.cursor;
 
                             // Save checkpoint
                             let checkpoint = Checkpoint {
                                 source: self.source.source_id().to_string(),
                                 cursor: cursor.clone(),
                                 last_record_ts: Some(current_start),
-                                last_block_number: batch.fills.last().and_then(|f| f.block_number),
+                                last_block_number,
                                 records_processed: total_processed,
                                 updated_at: Utc::now(),
-                                metadata: None,
+                                metadata: metadata.clone(),
                             };
 
                             self.store.save_checkpoint(&checkpoint).await?;
 
                             // If no more data, wait before polling again
-                            if !batch.has_more {
-                                tokio::time::sleep(Duration::from_secs(60)).await;
+                            if !page.has_more {
+                                tokio::time::sleep(self.source.poll_interval()).await;
                             }
                         }
                         Err(e) if e.is_retryable() => {
@@ -479,52 +581,69 @@ impl Pipeline {
         Ok(())
     }
 
+    /// Stream one page and insert its batches as they arrive. Returns where the page
+    /// ended, the number of fills inserted and the block of the last fill.
     async fn fetch_and_process_batch(
         &self,
         start_from: DateTime<Utc>,
-        cursor: Option<String>,
-    ) -> Result<(IngestBatch, usize)> {
+        cursor: Option<Cursor>,
+    ) -> Result<(PageSummary, usize, Option<i64>)> {
         let start = Instant::now();
+        let (tx, mut rx) = mpsc::channel::<IngestBatch>(self.config.pipeline.channel_buffer_size);
+
+        // Fetch page. Not retried here: batches sent before a failure are already being
+        // inserted, and the source retries its own requests.
+        let fetch = async move {
+            let page = self.source.stream_page(start_from, cursor, self.config.ingest.batch_size, &tx).await;
+
+            histogram!("indexer_fetch_duration_ms").record(start.elapsed().as_millis() as f64);
+            page
+        };
 
-        // Fetch batch
-        let batch = retry_with_backoff(
-            || self.source.fetch_page(start_from, cursor.clone()),
-            self.config.ingest.max_retries,
-            self.config.ingest.retry_base_delay_ms,
-            "fetch_page",
-        )
-        .await?;
+        // Insert fills and record the hours they came from. Owns the receiver, so
+        // the fetch stops as soon as an insert fails.
+        let process = async move {
+            let mut inserted = 0;
+            let mut last_block_number = None;
+
+            while let Some(batch) = rx.recv().await {
+                inserted += self.store.insert_batch(&batch).await?;
+
+                if let Some(last_fill) = batch.fills.last() {
+                    last_block_number = last_fill.block_number;
+                }
+                if let Some(bytes) = batch.bytes_downloaded {
+                    histogram!("indexer_batch_bytes").record(bytes as f64);
+                }
+            }
 
-        let fetch_duration = start.elapsed();
-        histogram!("indexer_fetch_duration_ms").record(fetch_duration.as_millis() as f64);
+            Ok::<_, Error>((inserted, last_block_number))
+        };
 
-        // Insert fills
-        let inserted = self.store.insert_fills(&batch.fills).await?;
+        let (page, processed) = tokio::join!(fetch, process);
+        let (inserted, last_block_number) = processed?;
+        let page = page?;
 
         let total_duration = start.elapsed();
         histogram!("indexer_batch_duration_ms").record(total_duration.as_millis() as f64);
 
-        if let Some(bytes) = batch.bytes_downloaded {
-            histogram!("indexer_batch_bytes").record(bytes as f64);
-        }
-
         debug!(
-            fetched = batch.fills.len(),
+            fetched = page.fills,
             inserted,
-            bytes = ?batch.bytes_downloaded,
             duration_ms = total_duration.as_millis(),
-            "Processed batch"
+            "Processed page"
         );
 
-        Ok((batch, inserted))
+        Ok((page, inserted, last_block_number))
     }
 
     fn spawn_fetcher(
         &self,
         tx: mpsc::Sender<IngestBatch>,
         start_from: DateTime<Utc>,
-        initial_cursor: Option<String>,
+        initial_cursor: Option<Cursor>,
         end_at: DateTime<Utc>,
+        budget: DownloadBudget,
     ) -> JoinHandle<Result<()>> {
         let source = Arc::clone(&self.source);
         let config = self.config.clone();
@@ -537,6 +656,7 @@ impl Pipeline {
             let mut skipped_hours = 0u64;
             let mut last_fetch_log = Instant::now();
             let mut total_fills_fetched = 0u64;
+            let mut bytes_downloaded = 0u64;
 
             info!(
                 "🚀 Starting smart data fetch from {} to {}",
@@ -544,20 +664,26 @@ impl Pipeline {
                 end_at.format("%Y-%m-%d %H:%M:%S")
             );
 
-            // Pre-fetch the list of missing hours for smart skipping
-            let missing_hours = match store.get_missing_hours(start_from, end_at).await {
+            // Pre-fetch the hours the manifest doesn't have yet for smart skipping. Hours
+            // missing upstream are listed again in case they were published late.
+            let to_fetch = async {
+                let mut hours = store.get_missing_hours(start_from, end_at).await?;
+                hours.extend(store.get_missing_upstream_hours(start_from, end_at).await?);
+                Ok::<_, Error>(hours)
+            };
+            let missing_hours: Option<HashSet<DateTime<Utc>>> = match to_fetch.await {
                 Ok(hours) => {
                     if !hours.is_empty() {
                         info!(
-                            "📊 Found {} hours with missing/incomplete data to backfill",
+                            "📊 Found {} hours not yet loaded to backfill",
                             hours.len()
                         );
                     }
-                    hours
+                    Some(hours.into_iter().collect())
                 },
                 Err(e) => {
                     warn!(error = %e, "Failed to get missing hours, falling back to sequential fetch");
-                    Vec::new()
+                    None
                 }
             };
 
@@ -572,41 +698,25 @@ impl Pipeline {
                     break;
                 }
 
-                // Smart skip: Check if this hour already has sufficient data
+                // Smart skip: hours marked complete in the manifest are not fetched again
                 let hour_start = current_start.date_naive().and_hms_opt(current_start.hour(), 0, 0).unwrap().and_utc();
                 let hour_end = hour_start + chrono::Duration::hours(1);
 
-                // If we have a list of missing hours, use it for smart skipping
-                let should_skip = if !missing_hours.is_empty() {
-                    !missing_hours.contains(&hour_start)
-                } else {
-                    // Otherwise, check each hour individually (slower but more accurate)
-                    match store.check_time_range_exists(hour_start, hour_end).await {
-                        Ok((has_data, count)) if has_data && count >= 1000 => {
-                            debug!(
-                                hour = %hour_start.format("%Y-%m-%d %H:%M"),
-                                count = count,
-                                "Hour already has sufficient data"
-                            );
-                            true
-                        },
-                        _ => false
-                    }
-                };
+                let should_skip = missing_hours
+                    .as_ref()
+                    .is_some_and(|missing| !missing.contains(&hour_start));
 
                 if should_skip {
                     skipped_hours += 1;
 
                     // Advance cursor to next hour
                     current_start = hour_end;
-                    let next_date = current_start.date_naive();
-                    let next_hour = current_start.hour();
-                    cursor = Some(format!("{}_{}", next_date.format("%Y%m%d"), next_hour));
+                    cursor = Some(source.hour_cursor(current_start));
 
                     // Log skip progress periodically
                     if skipped_hours % 24 == 0 || last_fetch_log.elapsed() > Duration::from_secs(5) {
                         info!(
-                            "⏩ Skipped {} hours with existing data, now at: {}",
+                            "⏩ Skipped {} hours already loaded, now at: {}",
                             skipped_hours,
                             current_start.format("%Y-%m-%d %H:%M")
                         );
@@ -616,33 +726,39 @@ impl Pipeline {
                     continue;
                 }
 
-                // Fetch batch with retries
-                let batch = match retry_with_backoff(
-                    || source.fetch_page(current_start, cursor.clone()),
-                    config.ingest.max_retries,
-                    config.ingest.retry_base_delay_ms,
-                    "fetch_page",
-                )
-                .await {
-                    Ok(batch) => batch,
+                // Stop once the download budget is spent
+                if let Err(e) = budget.check(bytes_downloaded) {
+                    error!(error = %e, "💸 Stopping backfill, download budget spent");
+                    return Err(e);
+                }
+
+                // Stream the page into the channel. A failed page is not streamed again: its
+                // batches are already queued, and the source retries its own requests.
+                let page = match source.stream_page(current_start, cursor.clone(), config.ingest.batch_size, &tx).await {
+                    Ok(page) => page,
+                    Err(_) if tx.is_closed() => {
+                        warn!("Pipeline channel closed, stopping fetcher");
+                        break;
+                    }
                     Err(e) => {
-                        error!(error = %e, "❌ Failed to fetch page after retries");
+                        error!(error = %e, "❌ Failed to fetch page");
                         return Err(e);
                     }
                 };
 
-                let has_more = batch.has_more;
-                let batch_fill_count = batch.fills.len();
-                cursor = batch.cursor.clone();
+                let has_more = page.has_more;
+                let batch_fill_count = page.fills;
+                bytes_downloaded += page.bytes;
+                cursor = page.cursor.clone();
                 fetched_batches += 1;
                 total_fills_fetched += batch_fill_count as u64;
 
-                // Update current_start for next iteration
-                if let Some(last_fill) = batch.fills.last() {
-                    current_start = last_fill.timestamp;
-                } else {
-                    // If no fills, advance to next hour
-                    current_start = hour_end;
+                // Update current_start for next iteration. Fills near the end of an hour file
+                // can be stamped in the previous hour, so never move back past this one, nor
+                // behind the page's cursor when its last hours were missing upstream.
+                current_start = page.last_timestamp.map_or(hour_end, |ts| ts.max(hour_end));
+                if let Some(cursor) = &cursor {
+                    current_start = current_start.max(cursor.hour);
                 }
 
                 // Log fetch progress periodically
@@ -659,12 +775,6 @@ impl Pipeline {
                     last_fetch_log = Instant::now();
                 }
 
-                // Send batch through channel
-                if tx.send(batch).await.is_err() {
-                    warn!("Pipeline channel closed, stopping fetcher");
-                    break;
-                }
-
                 // If no more data, we're done
                 if !has_more {
                     info!("✅ No more data available");
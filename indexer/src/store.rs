@@ -1,14 +1,104 @@
+use crate::ingest::api_source::USER_FILLS_SOURCE;
 use crate::market::MarketRegistry;
-use crate::model::{Checkpoint, Fill};
+use crate::ingest::cursor::{Cursor, SourceKind};
+use crate::model::{
+    AssetCtx, Block, BookLevel, Checkpoint, Fill, FillReject, HourReport, IngestBatch, L1Block, L1Transaction,
+    OrderAction, StoredReject, TradeSide, PROVISIONAL_SOURCE_PREFIX,
+};
 use chrono::{DateTime, Utc};
 use indexer_core::Result;
-use bigdecimal::BigDecimal;
-use std::str::FromStr;
+use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
 use std::sync::Arc;
-use metrics::counter;
-use sqlx::PgPool;
+use metrics::{counter, histogram};
+use sqlx::{Connection, PgConnection, PgPool};
 use tracing::{debug, info, warn, instrument};
 
+/// Columns written for every fill, in COPY/VALUES order
+const FILL_COLUMNS: &str = "exchange_id, market_id, user_address, side, price, size, fee, closed_pnl, \
+    timestamp, block_number, source_id, tid, oid, hash, crossed, dir, start_position, fee_token, \
+    liquidated_user, liquidation_mark_price, liquidation_method, twap_id, builder_fee";
+const FILL_COLUMN_COUNT: usize = 23;
+
+/// Dedupe key computed from staged columns plus `dup_seq` (see migration 0003)
+const FILL_KEY_EXPR: &str =
+    "fill_dedupe_key(tid, side, user_address, market_id, timestamp, price, size, dup_seq)";
+
+/// Format an optional value for COPY CSV input, using `\N` for NULL
+fn csv_value<T: std::fmt::Display>(value: Option<T>) -> String {
+    value.map_or_else(|| "\\N".to_string(), |v| v.to_string())
+}
+
+/// `source` label of inserted fills. Fills from hour files carry their object key,
+/// so they share one label; live fills keep their `api:` source id.
+fn fill_source_label(source_id: Option<&str>) -> String {
+    match source_id {
+        Some(source) if source.starts_with(PROVISIONAL_SOURCE_PREFIX) => source.to_string(),
+        Some(_) => "hour_file".to_string(),
+        None => "unknown".to_string(),
+    }
+}
+
+/// Dedupe key of a fill, matching `fill_dedupe_key`: the trade id and side when
+/// there is a trade id, otherwise the fill's own values
+#[derive(PartialEq, Eq, Hash)]
+enum FillKey<'a> {
+    Tid(i64, bool),
+    Legacy(&'a str, &'a str, DateTime<Utc>, rust_decimal::Decimal, rust_decimal::Decimal, bool, i32),
+}
+
+impl<'a> FillKey<'a> {
+    fn of(fill: &'a Fill) -> Self {
+        let is_buy = fill.side == TradeSide::Buy;
+        match fill.tid {
+            Some(tid) => FillKey::Tid(tid, is_buy),
+            None => FillKey::Legacy(
+                &fill.user_address,
+                &fill.coin,
+                fill.timestamp,
+                fill.price,
+                fill.size,
+                is_buy,
+                fill.dup_seq,
+            ),
+        }
+    }
+}
+
+/// Keep one fill per dedupe key. `ON CONFLICT` leaves it to Postgres which of two
+/// staged duplicates is stored, so the choice is made here: the user's own fill from
+/// `userFillsByTime`, which carries fee and pnl, otherwise the first one.
+fn dedupe_fills(fills: Cow<'_, [Fill]>) -> Cow<'_, [Fill]> {
+    let mut kept: Vec<usize> = Vec::with_capacity(fills.len());
+    let mut slots: HashMap<FillKey, usize> = HashMap::with_capacity(fills.len());
+    for (index, fill) in fills.iter().enumerate() {
+        match slots.entry(FillKey::of(fill)) {
+            Entry::Occupied(slot) => {
+                let kept_index = &mut kept[*slot.get()];
+                let from_user_fills = |fill: &Fill| fill.source_id.as_deref() == Some(USER_FILLS_SOURCE);
+                if from_user_fills(fill) && !from_user_fills(&fills[*kept_index]) {
+                    *kept_index = index;
+                }
+            }
+            Entry::Vacant(slot) => {
+                slot.insert(kept.len());
+                kept.push(index);
+            }
+        }
+    }
+
+    if kept.len() == fills.len() {
+        return fills;
+    }
+    Cow::Owned(kept.into_iter().map(|index| fills[index].clone()).collect())
+}
+
+/// Format optional free text for COPY CSV input, quoting it so commas and quotes survive
+fn csv_text(value: Option<&str>) -> String {
+    value.map_or_else(|| "\\N".to_string(), |v| format!("\"{}\"", v.replace('"', "\"\"")))
+}
+
 pub struct Store {
     pool: PgPool,
     exchange_id: i32,
@@ -34,51 +124,775 @@ impl Store {
         })
     }
 
+    /// Market registry fills are resolved against
+    pub fn market_registry(&self) -> Arc<MarketRegistry> {
+        Arc::clone(&self.market_registry)
+    }
+
+    /// Insert fills outside of an hour-file load, e.g. re-processed rejects
     #[instrument(skip(self, fills))]
     pub async fn insert_fills(&self, fills: &[Fill]) -> Result<usize> {
-        if fills.is_empty() {
+        self.insert_fills_with_hours(fills, &[], &[], &[]).await
+    }
+
+    /// Insert a batch in one transaction: its fills, its rejects, its block headers and
+    /// one manifest row per hour file. An hour is only marked complete together with its fills.
+    #[instrument(skip(self, batch))]
+    pub async fn insert_batch(&self, batch: &IngestBatch) -> Result<usize> {
+        let start = std::time::Instant::now();
+        let inserted = self
+            .insert_fills_with_hours(&batch.fills, &batch.hours, &batch.rejects, &batch.blocks)
+            .await?;
+
+        histogram!("indexer_stage_duration_ms", "stage" => "insert").record(start.elapsed().as_millis() as f64);
+        counter!("indexer_stage_fills", "stage" => "insert").increment(batch.fills.len() as u64);
+
+        Ok(inserted)
+    }
+
+    async fn insert_fills_with_hours(
+        &self,
+        fills: &[Fill],
+        hours: &[HourReport],
+        rejects: &[FillReject],
+        blocks: &[Block],
+    ) -> Result<usize> {
+        if fills.is_empty() && hours.is_empty() && rejects.is_empty() && blocks.is_empty() {
             return Ok(0);
         }
 
+        // Values that overflow their NUMERIC column would abort the whole COPY, so set them aside
+        let overflowing: Vec<(&Fill, String)> = fills
+            .iter()
+            .filter_map(|fill| fill.numeric_overflow().map(|reason| (fill, reason)))
+            .collect();
+
+        let fills: Cow<[Fill]> = if overflowing.is_empty() {
+            Cow::Borrowed(fills)
+        } else {
+            Cow::Owned(
+                fills
+                    .iter()
+                    .filter(|fill| fill.numeric_overflow().is_none())
+                    .cloned()
+                    .collect(),
+            )
+        };
+
+        let fills = dedupe_fills(fills);
+
+        let mut tx = self.pool.begin().await?;
+
+        self.replace_provisional(&mut tx, &fills).await?;
+        self.replace_legacy(&mut tx, &fills).await?;
+
         // Process in large chunks for better throughput
         const CHUNK_SIZE: usize = 200000; // Increased batch size for faster inserts
-        let mut total_inserted = 0;
+        let mut inserted_by_key: HashMap<Option<String>, usize> = HashMap::new();
 
         for chunk in fills.chunks(CHUNK_SIZE) {
-            let inserted = self.bulk_insert_fills_chunk(chunk).await?;
-            total_inserted += inserted;
+            for (key, count) in self.bulk_insert_fills_chunk(&mut tx, chunk).await? {
+                *inserted_by_key.entry(key).or_default() += count;
+            }
         }
+        let total_inserted: usize = inserted_by_key.values().sum();
+
+        let mut all_rejects = rejects.to_vec();
+        all_rejects.extend(self.quarantine_fills(&overflowing)?);
+        self.record_rejects(&mut tx, &all_rejects).await?;
 
-        counter!("indexer_fills_inserted", "source" => "s3").increment(total_inserted as u64);
+        self.record_hours(&mut tx, hours, &inserted_by_key, &overflowing).await?;
+        self.settle_provisional(&mut tx, hours).await?;
+        self.record_blocks(&mut tx, blocks).await?;
+
+        tx.commit().await?;
+
+        for (source_id, inserted) in &inserted_by_key {
+            counter!("indexer_fills_inserted", "source" => fill_source_label(source_id.as_deref()))
+                .increment(*inserted as u64);
+        }
+        counter!("indexer_fills_quarantined").increment(all_rejects.len() as u64);
 
         debug!(
             total = fills.len(),
             inserted = total_inserted,
             duplicates = fills.len() - total_inserted,
+            quarantined = all_rejects.len(),
+            hours = hours.len(),
             "Inserted fills"
         );
 
         Ok(total_inserted)
     }
 
-    async fn bulk_insert_fills_chunk(&self, fills: &[Fill]) -> Result<usize> {
+    /// Delete the provisional fills that fills of this batch supersede, so the insert
+    /// stores the better copy under the shared `tid:<tid>:<side>` key: fills from hour
+    /// files replace any API fill, and `userFillsByTime` fills replace the fills built
+    /// from trades of the same trade side, which lack fees and order ids.
+    async fn replace_provisional(&self, conn: &mut PgConnection, fills: &[Fill]) -> Result<()> {
+        let mut authoritative = Vec::new();
+        let mut user_fills = Vec::new();
+        for fill in fills {
+            let Some(tid) = fill.tid else {
+                continue;
+            };
+            let key = format!("tid:{}:{}", tid, fill.side);
+            match fill.source_id.as_deref() {
+                Some(USER_FILLS_SOURCE) => user_fills.push(key),
+                _ if fill.is_provisional() => {}
+                _ => authoritative.push(key),
+            }
+        }
+
+        let mut replaced = 0;
+        if !authoritative.is_empty() {
+            replaced += sqlx::query!(
+                r#"
+                DELETE FROM fills
+                WHERE exchange_id = $1 AND fill_key = ANY($2) AND source_id LIKE $3 || '%'
+                "#,
+                self.exchange_id,
+                &authoritative,
+                PROVISIONAL_SOURCE_PREFIX
+            )
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+        }
+        if !user_fills.is_empty() {
+            replaced += sqlx::query!(
+                r#"
+                DELETE FROM fills
+                WHERE exchange_id = $1 AND fill_key = ANY($2) AND source_id LIKE $3 || '%' AND source_id <> $4
+                "#,
+                self.exchange_id,
+                &user_fills,
+                PROVISIONAL_SOURCE_PREFIX,
+                USER_FILLS_SOURCE
+            )
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+        }
+
+        counter!("indexer_provisional_fills_replaced").increment(replaced);
+        Ok(())
+    }
+
+    /// Delete rows stored under a `legacy:` key, from before trade ids were captured,
+    /// that fills of this batch carry a trade id for, so reloading such an hour from
+    /// `node_fills` or `node_fills_by_block` replaces them instead of counting them
+    /// twice. Rows match on user, market, time, price, size and side.
+    async fn replace_legacy(&self, conn: &mut PgConnection, fills: &[Fill]) -> Result<()> {
+        let mut users = Vec::new();
+        let mut markets = Vec::new();
+        let mut timestamps = Vec::new();
+        let mut prices = Vec::new();
+        let mut sizes = Vec::new();
+        let mut sides = Vec::new();
+        for fill in fills.iter().filter(|fill| fill.tid.is_some() && !fill.is_provisional()) {
+            users.push(fill.user_address.clone());
+            markets.push(self.market_registry.get_or_create_market(&fill.coin).await?);
+            timestamps.push(fill.timestamp);
+            prices.push(fill.price);
+            sizes.push(fill.size);
+            sides.push(fill.side.to_string());
+        }
+        if users.is_empty() {
+            return Ok(());
+        }
+
+        let replaced = sqlx::query!(
+            r#"
+            DELETE FROM fills f
+            USING UNNEST($2::text[], $3::int[], $4::timestamptz[], $5::numeric[], $6::numeric[], $7::text[])
+                AS n(user_address, market_id, timestamp, price, size, side)
+            WHERE f.exchange_id = $1
+              AND f.fill_key LIKE 'legacy:%'
+              AND (f.source_id IS NULL OR f.source_id NOT LIKE $8 || '%')
+              AND f.user_address = n.user_address
+              AND f.market_id = n.market_id
+              AND f.timestamp = n.timestamp
+              AND f.price = ROUND(n.price, 10)
+              AND f.size = ROUND(n.size, 10)
+              AND f.side = n.side
+            "#,
+            self.exchange_id,
+            &users,
+            &markets,
+            &timestamps,
+            &prices as &[rust_decimal::Decimal],
+            &sizes as &[rust_decimal::Decimal],
+            &sides,
+            PROVISIONAL_SOURCE_PREFIX
+        )
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+
+        if replaced > 0 {
+            debug!(replaced, "Replaced legacy-keyed fills with trade-id fills");
+        }
+        counter!("indexer_legacy_fills_replaced").increment(replaced);
+        Ok(())
+    }
+
+    /// Delete the provisional fills left in hours whose hour files are loaded. Fills near
+    /// the end of an hour can be published in the next hour's file, so an hour is only
+    /// settled once it and the hour after it are `complete`. Provisional fills of trades
+    /// the hour files don't have are dropped then too.
+    async fn settle_provisional(&self, conn: &mut PgConnection, hours: &[HourReport]) -> Result<()> {
+        let completed: Vec<DateTime<Utc>> = hours
+            .iter()
+            .filter(|hour| hour.status() == "complete")
+            .map(|hour| hour.hour)
+            .collect();
+        if completed.is_empty() {
+            return Ok(());
+        }
+
+        // Completing an hour can settle the hour before it as well as itself
+        let settled = sqlx::query!(
+            r#"
+            DELETE FROM fills f
+            WHERE f.exchange_id = $1
+              AND f.source_id LIKE $2 || '%'
+              AND f.timestamp >= (SELECT MIN(h) FROM UNNEST($3::timestamptz[]) AS h) - INTERVAL '1 hour'
+              AND f.timestamp < (SELECT MAX(h) FROM UNNEST($3::timestamptz[]) AS h) + INTERVAL '1 hour'
+              AND EXISTS (
+                  SELECT 1 FROM ingest_hours ih
+                  WHERE ih.exchange_id = $1 AND ih.status = 'complete'
+                    AND ih.hour = DATE_TRUNC('hour', f.timestamp, 'UTC')
+              )
+              AND EXISTS (
+                  SELECT 1 FROM ingest_hours ih
+                  WHERE ih.exchange_id = $1 AND ih.status = 'complete'
+                    AND ih.hour = DATE_TRUNC('hour', f.timestamp, 'UTC') + INTERVAL '1 hour'
+              )
+            "#,
+            self.exchange_id,
+            PROVISIONAL_SOURCE_PREFIX,
+            &completed
+        )
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+
+        if settled > 0 {
+            debug!(settled, hours = completed.len(), "Dropped provisional fills of loaded hours");
+        }
+        counter!("indexer_provisional_fills_settled").increment(settled);
+        Ok(())
+    }
+
+    /// Turn fills that cannot be written to `fills` into `fill_rejects` rows for later inspection
+    fn quarantine_fills(&self, overflowing: &[(&Fill, String)]) -> Result<Vec<FillReject>> {
+        let mut rejects = Vec::with_capacity(overflowing.len());
+
+        for (fill, reason) in overflowing {
+            warn!(
+                user = %fill.user_address,
+                coin = %fill.coin,
+                timestamp = %fill.timestamp,
+                reason = %reason,
+                "Quarantining fill"
+            );
+
+            rejects.push(FillReject {
+                hour: None,
+                key: fill.source_id.clone(),
+                line_number: None,
+                raw_line: None,
+                reason: reason.clone(),
+                format: None,
+                payload: Some(serde_json::to_value(fill)?),
+                fill_timestamp: Some(fill.timestamp),
+            });
+        }
+
+        Ok(rejects)
+    }
+
+    /// Write rejected lines and fills to the `fill_rejects` dead-letter table
+    async fn record_rejects(&self, conn: &mut PgConnection, rejects: &[FillReject]) -> Result<()> {
+        for reject in rejects {
+            // Lines are keyed by their position in the hour file, fills quarantined after
+            // parsing by their payload, so reloading an hour doesn't record them again
+            let query = if reject.raw_line.is_some() {
+                r#"
+                INSERT INTO fill_rejects (
+                    exchange_id, hour, s3_key, line_number, raw_line, reason,
+                    schema_format, payload, fill_timestamp
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                ON CONFLICT (exchange_id, s3_key, line_number, md5(reason)) WHERE raw_line IS NOT NULL
+                DO NOTHING
+                "#
+            } else {
+                r#"
+                INSERT INTO fill_rejects (
+                    exchange_id, hour, s3_key, line_number, raw_line, reason,
+                    schema_format, payload, fill_timestamp
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                ON CONFLICT (exchange_id, COALESCE(s3_key, ''), md5(payload::text), md5(reason))
+                    WHERE raw_line IS NULL
+                DO NOTHING
+                "#
+            };
+
+            sqlx::query(query)
+            .bind(self.exchange_id)
+            .bind(reject.hour)
+            .bind(reject.key.as_deref())
+            .bind(reject.line_number)
+            .bind(reject.raw_line.as_deref())
+            .bind(&reject.reason)
+            .bind(reject.format.as_deref())
+            .bind(&reject.payload)
+            .bind(reject.fill_timestamp)
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Write block headers to `blocks`. A block read again, e.g. when an hour is
+    /// re-loaded, replaces its row.
+    async fn record_blocks(&self, conn: &mut PgConnection, blocks: &[Block]) -> Result<()> {
+        if blocks.is_empty() {
+            return Ok(());
+        }
+
+        let mut numbers = Vec::with_capacity(blocks.len());
+        let mut block_times = Vec::with_capacity(blocks.len());
+        let mut local_times = Vec::with_capacity(blocks.len());
+        let mut fill_counts = Vec::with_capacity(blocks.len());
+        let mut hours = Vec::with_capacity(blocks.len());
+        let mut keys = Vec::with_capacity(blocks.len());
+        for block in blocks {
+            numbers.push(block.block_number);
+            block_times.push(block.block_time);
+            local_times.push(block.local_time);
+            fill_counts.push(block.fill_count as i32);
+            hours.push(block.hour);
+            keys.push(block.key.clone());
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO blocks (exchange_id, block_number, block_time, local_time, fill_count, hour, s3_key)
+            SELECT $1, block_number, block_time, local_time, fill_count, hour, s3_key
+            FROM UNNEST($2::bigint[], $3::timestamptz[], $4::timestamptz[], $5::int[], $6::timestamptz[], $7::text[])
+                AS b(block_number, block_time, local_time, fill_count, hour, s3_key)
+            ON CONFLICT (exchange_id, block_number) DO UPDATE SET
+                block_time = EXCLUDED.block_time,
+                local_time = EXCLUDED.local_time,
+                fill_count = EXCLUDED.fill_count,
+                hour = EXCLUDED.hour,
+                s3_key = EXCLUDED.s3_key
+            "#,
+            self.exchange_id,
+            &numbers,
+            &block_times as &[Option<DateTime<Utc>>],
+            &local_times as &[Option<DateTime<Utc>>],
+            &fill_counts,
+            &hours as &[Option<DateTime<Utc>>],
+            &keys as &[Option<String>]
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        counter!("indexer_blocks_recorded").increment(blocks.len() as u64);
+        Ok(())
+    }
+
+    /// Insert L2 book levels, skipping levels of snapshots already stored. Returns
+    /// the number of new rows.
+    #[instrument(skip(self, levels))]
+    pub async fn insert_book_levels(&self, levels: &[BookLevel]) -> Result<usize> {
+        if levels.is_empty() {
+            return Ok(0);
+        }
+
+        let mut market_ids: HashMap<&str, i32> = HashMap::new();
+        let mut markets = Vec::with_capacity(levels.len());
+        let mut times = Vec::with_capacity(levels.len());
+        let mut sides = Vec::with_capacity(levels.len());
+        let mut positions = Vec::with_capacity(levels.len());
+        let mut prices = Vec::with_capacity(levels.len());
+        let mut sizes = Vec::with_capacity(levels.len());
+        let mut order_counts = Vec::with_capacity(levels.len());
+        for level in levels {
+            let market_id = match market_ids.get(level.coin.as_str()) {
+                Some(id) => *id,
+                None => {
+                    let id = self.market_registry.get_or_create_market(&level.coin).await?;
+                    market_ids.insert(&level.coin, id);
+                    id
+                }
+            };
+
+            markets.push(market_id);
+            times.push(level.time);
+            sides.push(level.side.to_string());
+            positions.push(level.level);
+            prices.push(level.price);
+            sizes.push(level.size);
+            order_counts.push(level.order_count);
+        }
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO l2_book_levels (exchange_id, market_id, time, side, level, price, size, order_count)
+            SELECT $1, market_id, time, side, level, price, size, order_count
+            FROM UNNEST($2::int[], $3::timestamptz[], $4::text[], $5::smallint[], $6::numeric[], $7::numeric[], $8::int[])
+                AS l(market_id, time, side, level, price, size, order_count)
+            ON CONFLICT (market_id, time, side, level) DO NOTHING
+            "#,
+            self.exchange_id,
+            &markets,
+            &times,
+            &sides,
+            &positions,
+            &prices as &[rust_decimal::Decimal],
+            &sizes as &[rust_decimal::Decimal],
+            &order_counts
+        )
+        .execute(&self.pool)
+        .await?;
+
+        let inserted = result.rows_affected() as usize;
+        counter!("indexer_l2_book_levels_inserted").increment(inserted as u64);
+        Ok(inserted)
+    }
+
+    /// Insert asset contexts, skipping ones already stored for the market and time.
+    /// Returns the number of new rows.
+    #[instrument(skip(self, ctxs))]
+    pub async fn insert_asset_ctxs(&self, ctxs: &[AssetCtx]) -> Result<usize> {
+        if ctxs.is_empty() {
+            return Ok(0);
+        }
+
+        let mut market_ids: HashMap<&str, i32> = HashMap::new();
+        let mut markets = Vec::with_capacity(ctxs.len());
+        let mut times = Vec::with_capacity(ctxs.len());
+        let mut funding = Vec::with_capacity(ctxs.len());
+        let mut open_interest = Vec::with_capacity(ctxs.len());
+        let mut prev_day_px = Vec::with_capacity(ctxs.len());
+        let mut day_ntl_vlm = Vec::with_capacity(ctxs.len());
+        let mut premium = Vec::with_capacity(ctxs.len());
+        let mut oracle_px = Vec::with_capacity(ctxs.len());
+        let mut mark_px = Vec::with_capacity(ctxs.len());
+        let mut mid_px = Vec::with_capacity(ctxs.len());
+        let mut impact_bid_px = Vec::with_capacity(ctxs.len());
+        let mut impact_ask_px = Vec::with_capacity(ctxs.len());
+        for ctx in ctxs {
+            let market_id = match market_ids.get(ctx.coin.as_str()) {
+                Some(id) => *id,
+                None => {
+                    let id = self.market_registry.get_or_create_market(&ctx.coin).await?;
+                    market_ids.insert(&ctx.coin, id);
+                    id
+                }
+            };
+
+            markets.push(market_id);
+            times.push(ctx.time);
+            funding.push(ctx.funding);
+            open_interest.push(ctx.open_interest);
+            prev_day_px.push(ctx.prev_day_px);
+            day_ntl_vlm.push(ctx.day_ntl_vlm);
+            premium.push(ctx.premium);
+            oracle_px.push(ctx.oracle_px);
+            mark_px.push(ctx.mark_px);
+            mid_px.push(ctx.mid_px);
+            impact_bid_px.push(ctx.impact_bid_px);
+            impact_ask_px.push(ctx.impact_ask_px);
+        }
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO asset_ctxs (
+                exchange_id, market_id, time, funding, open_interest, prev_day_px, day_ntl_vlm,
+                premium, oracle_px, mark_px, mid_px, impact_bid_px, impact_ask_px
+            )
+            SELECT $1, market_id, time, funding, open_interest, prev_day_px, day_ntl_vlm,
+                premium, oracle_px, mark_px, mid_px, impact_bid_px, impact_ask_px
+            FROM UNNEST(
+                $2::int[], $3::timestamptz[], $4::numeric[], $5::numeric[], $6::numeric[], $7::numeric[],
+                $8::numeric[], $9::numeric[], $10::numeric[], $11::numeric[], $12::numeric[], $13::numeric[]
+            ) AS a(market_id, time, funding, open_interest, prev_day_px, day_ntl_vlm,
+                premium, oracle_px, mark_px, mid_px, impact_bid_px, impact_ask_px)
+            ON CONFLICT (market_id, time) DO NOTHING
+            "#,
+            self.exchange_id,
+            &markets,
+            &times,
+            &funding as &[Option<rust_decimal::Decimal>],
+            &open_interest as &[Option<rust_decimal::Decimal>],
+            &prev_day_px as &[Option<rust_decimal::Decimal>],
+            &day_ntl_vlm as &[Option<rust_decimal::Decimal>],
+            &premium as &[Option<rust_decimal::Decimal>],
+            &oracle_px as &[Option<rust_decimal::Decimal>],
+            &mark_px as &[Option<rust_decimal::Decimal>],
+            &mid_px as &[Option<rust_decimal::Decimal>],
+            &impact_bid_px as &[Option<rust_decimal::Decimal>],
+            &impact_ask_px as &[Option<rust_decimal::Decimal>]
+        )
+        .execute(&self.pool)
+        .await?;
+
+        let inserted = result.rows_affected() as usize;
+        counter!("indexer_asset_ctxs_inserted").increment(inserted as u64);
+        Ok(inserted)
+    }
+
+    /// Insert explorer blocks and their transactions in one transaction, skipping
+    /// blocks already stored. Returns the number of new blocks and transactions.
+    #[instrument(skip(self, blocks, txs))]
+    pub async fn insert_l1_blocks(&self, blocks: &[L1Block], txs: &[L1Transaction]) -> Result<(usize, usize)> {
+        if blocks.is_empty() {
+            return Ok((0, 0));
+        }
+
+        let mut heights = Vec::with_capacity(blocks.len());
+        let mut block_times = Vec::with_capacity(blocks.len());
+        let mut block_hashes = Vec::with_capacity(blocks.len());
+        let mut proposers = Vec::with_capacity(blocks.len());
+        let mut tx_counts = Vec::with_capacity(blocks.len());
+        let mut keys = Vec::with_capacity(blocks.len());
+        for block in blocks {
+            heights.push(block.height);
+            block_times.push(block.block_time);
+            block_hashes.push(block.hash.clone());
+            proposers.push(block.proposer.clone());
+            tx_counts.push(block.tx_count);
+            keys.push(block.key.clone());
+        }
+
+        let mut tx_heights = Vec::with_capacity(txs.len());
+        let mut tx_indexes = Vec::with_capacity(txs.len());
+        let mut tx_hashes = Vec::with_capacity(txs.len());
+        let mut tx_times = Vec::with_capacity(txs.len());
+        let mut action_types = Vec::with_capacity(txs.len());
+        let mut users = Vec::with_capacity(txs.len());
+        let mut errors = Vec::with_capacity(txs.len());
+        for tx in txs {
+            tx_heights.push(tx.block_height);
+            tx_indexes.push(tx.tx_index);
+            tx_hashes.push(tx.hash.clone());
+            tx_times.push(tx.time);
+            action_types.push(tx.action_type.clone());
+            users.push(tx.user_address.clone());
+            errors.push(tx.error.clone());
+        }
+
+        let mut db_tx = self.pool.begin().await?;
+
+        let blocks_inserted = sqlx::query!(
+            r#"
+            INSERT INTO l1_blocks (exchange_id, height, block_time, hash, proposer, tx_count, s3_key)
+            SELECT $1, height, block_time, hash, proposer, tx_count, s3_key
+            FROM UNNEST($2::bigint[], $3::timestamptz[], $4::text[], $5::text[], $6::int[], $7::text[])
+                AS b(height, block_time, hash, proposer, tx_count, s3_key)
+            ON CONFLICT (exchange_id, height) DO NOTHING
+            "#,
+            self.exchange_id,
+            &heights,
+            &block_times as &[Option<DateTime<Utc>>],
+            &block_hashes as &[Option<String>],
+            &proposers as &[Option<String>],
+            &tx_counts,
+            &keys
+        )
+        .execute(&mut *db_tx)
+        .await?
+        .rows_affected() as usize;
+
+        let txs_inserted = sqlx::query!(
+            r#"
+            INSERT INTO l1_transactions (exchange_id, block_height, tx_index, hash, time, action_type, user_address, error)
+            SELECT $1, block_height, tx_index, hash, time, action_type, user_address, error
+            FROM UNNEST($2::bigint[], $3::int[], $4::text[], $5::timestamptz[], $6::text[], $7::text[], $8::text[])
+                AS t(block_height, tx_index, hash, time, action_type, user_address, error)
+            ON CONFLICT (exchange_id, block_height, tx_index) DO NOTHING
+            "#,
+            self.exchange_id,
+            &tx_heights,
+            &tx_indexes,
+            &tx_hashes as &[Option<String>],
+            &tx_times as &[Option<DateTime<Utc>>],
+            &action_types as &[Option<String>],
+            &users as &[Option<String>],
+            &errors as &[Option<String>]
+        )
+        .execute(&mut *db_tx)
+        .await?
+        .rows_affected() as usize;
+
+        db_tx.commit().await?;
+
+        counter!("indexer_l1_blocks_inserted").increment(blocks_inserted as u64);
+        counter!("indexer_l1_transactions_inserted").increment(txs_inserted as u64);
+        Ok((blocks_inserted, txs_inserted))
+    }
+
+    /// Insert L1 actions, skipping items already stored. Returns the number of new rows.
+    #[instrument(skip(self, actions))]
+    pub async fn insert_order_actions(&self, actions: &[OrderAction]) -> Result<usize> {
+        if actions.is_empty() {
+            return Ok(0);
+        }
+
+        let mut block_numbers = Vec::with_capacity(actions.len());
+        let mut block_times = Vec::with_capacity(actions.len());
+        let mut tx_indexes = Vec::with_capacity(actions.len());
+        let mut item_indexes = Vec::with_capacity(actions.len());
+        let mut users = Vec::with_capacity(actions.len());
+        let mut vaults = Vec::with_capacity(actions.len());
+        let mut action_types = Vec::with_capacity(actions.len());
+        let mut assets = Vec::with_capacity(actions.len());
+        let mut oids = Vec::with_capacity(actions.len());
+        let mut cloids = Vec::with_capacity(actions.len());
+        let mut is_buys = Vec::with_capacity(actions.len());
+        let mut prices = Vec::with_capacity(actions.len());
+        let mut sizes = Vec::with_capacity(actions.len());
+        let mut reduce_onlys = Vec::with_capacity(actions.len());
+        let mut order_types = Vec::with_capacity(actions.len());
+        let mut statuses = Vec::with_capacity(actions.len());
+        let mut errors = Vec::with_capacity(actions.len());
+        let mut details = Vec::with_capacity(actions.len());
+        for action in actions {
+            block_numbers.push(action.block_number);
+            block_times.push(action.block_time);
+            tx_indexes.push(action.tx_index);
+            item_indexes.push(action.item_index);
+            users.push(action.user_address.clone());
+            vaults.push(action.vault_address.clone());
+            action_types.push(action.action_type.clone());
+            assets.push(action.asset);
+            oids.push(action.oid);
+            cloids.push(action.cloid.clone());
+            is_buys.push(action.is_buy);
+            prices.push(action.price);
+            sizes.push(action.size);
+            reduce_onlys.push(action.reduce_only);
+            order_types.push(action.order_type.clone());
+            statuses.push(action.status.clone());
+            errors.push(action.error.clone());
+            details.push(action.details.as_ref().map(|details| details.to_string()));
+        }
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO order_actions (
+                exchange_id, block_number, block_time, tx_index, item_index, user_address, vault_address,
+                action_type, asset, oid, cloid, is_buy, price, size, reduce_only, order_type, status, error, details
+            )
+            SELECT $1, block_number, block_time, tx_index, item_index, user_address, vault_address,
+                action_type, asset, oid, cloid, is_buy, price, size, reduce_only, order_type, status, error,
+                details::jsonb
+            FROM UNNEST(
+                $2::bigint[], $3::timestamptz[], $4::int[], $5::int[], $6::text[], $7::text[], $8::text[],
+                $9::int[], $10::bigint[], $11::text[], $12::bool[], $13::numeric[], $14::numeric[], $15::bool[],
+                $16::text[], $17::text[], $18::text[], $19::text[]
+            ) AS a(block_number, block_time, tx_index, item_index, user_address, vault_address, action_type,
+                asset, oid, cloid, is_buy, price, size, reduce_only, order_type, status, error, details)
+            ON CONFLICT (exchange_id, block_number, tx_index, item_index) DO NOTHING
+            "#,
+            self.exchange_id,
+            &block_numbers,
+            &block_times as &[Option<DateTime<Utc>>],
+            &tx_indexes,
+            &item_indexes,
+            &users as &[Option<String>],
+            &vaults as &[Option<String>],
+            &action_types,
+            &assets as &[Option<i32>],
+            &oids as &[Option<i64>],
+            &cloids as &[Option<String>],
+            &is_buys as &[Option<bool>],
+            &prices as &[Option<rust_decimal::Decimal>],
+            &sizes as &[Option<rust_decimal::Decimal>],
+            &reduce_onlys as &[Option<bool>],
+            &order_types as &[Option<String>],
+            &statuses as &[Option<String>],
+            &errors as &[Option<String>],
+            &details as &[Option<String>]
+        )
+        .execute(&self.pool)
+        .await?;
+
+        let inserted = result.rows_affected() as usize;
+        counter!("indexer_order_actions_inserted").increment(inserted as u64);
+        Ok(inserted)
+    }
+
+    /// Rejects after `after_id` that have not been re-processed successfully yet
+    pub async fn get_unresolved_rejects(&self, after_id: i64, limit: i64) -> Result<Vec<StoredReject>> {
+        let rejects = sqlx::query_as::<_, StoredReject>(
+            r#"
+            SELECT id, raw_line, schema_format, payload
+            FROM fill_rejects
+            WHERE exchange_id = $1 AND resolved_at IS NULL AND id > $2
+            ORDER BY id
+            LIMIT $3
+            "#
+        )
+        .bind(self.exchange_id)
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rejects)
+    }
+
+    /// Mark rejects as re-processed
+    pub async fn resolve_rejects(&self, ids: &[i64]) -> Result<()> {
+        sqlx::query("UPDATE fill_rejects SET resolved_at = NOW() WHERE id = ANY($1)")
+            .bind(ids)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Insert one chunk and return the number of new rows per `source_id`
+    async fn bulk_insert_fills_chunk(
+        &self,
+        conn: &mut PgConnection,
+        fills: &[Fill],
+    ) -> Result<HashMap<Option<String>, usize>> {
         // Use PostgreSQL COPY for maximum performance
-        // First try COPY, fallback to multi-row VALUES if needed
-        match self.bulk_insert_with_copy(fills).await {
-            Ok(count) => Ok(count),
+        // First try COPY, fallback to multi-row VALUES if needed.
+        // Each attempt runs in a savepoint so a failed COPY doesn't abort the batch transaction.
+        let mut savepoint = conn.begin().await?;
+        match self.bulk_insert_with_copy(&mut savepoint, fills).await {
+            Ok(counts) => {
+                savepoint.commit().await?;
+                Ok(counts)
+            }
             Err(e) => {
                 debug!("COPY failed, using multi-row VALUES: {:?}", e);
-                self.bulk_insert_with_values_optimized(fills).await
+                savepoint.rollback().await?;
+
+                let mut savepoint = conn.begin().await?;
+                let counts = self.bulk_insert_with_values_optimized(&mut savepoint, fills).await?;
+                savepoint.commit().await?;
+                Ok(counts)
             }
         }
     }
 
-    async fn bulk_insert_with_copy(&self, fills: &[Fill]) -> Result<usize> {
-        use sqlx::Connection;
-
-        let mut conn = self.pool.acquire().await?;
-        let mut tx = conn.begin().await?;
-
+    async fn bulk_insert_with_copy(
+        &self,
+        conn: &mut PgConnection,
+        fills: &[Fill],
+    ) -> Result<HashMap<Option<String>, usize>> {
         // Use COPY with a temporary table to handle conflicts
         sqlx::query(
             r#"
@@ -93,17 +907,33 @@ impl Store {
                 closed_pnl NUMERIC(20, 10),
                 timestamp TIMESTAMPTZ NOT NULL,
                 block_number BIGINT,
-                source_id VARCHAR(100)
+                source_id VARCHAR(100),
+                tid BIGINT,
+                oid BIGINT,
+                hash VARCHAR(66),
+                crossed BOOLEAN,
+                dir VARCHAR(32),
+                start_position NUMERIC(30, 10),
+                fee_token VARCHAR(20),
+                liquidated_user VARCHAR(66),
+                liquidation_mark_price NUMERIC(20, 10),
+                liquidation_method VARCHAR(20),
+                twap_id BIGINT,
+                builder_fee NUMERIC(20, 10),
+                dup_seq INTEGER NOT NULL
             ) ON COMMIT DROP
             "#
         )
-        .execute(&mut *tx)
+        .execute(&mut *conn)
         .await?;
 
         // Use COPY to bulk insert data
-        let copy_query = r#"COPY temp_fills (exchange_id, market_id, user_address, side, price, size, fee, closed_pnl, timestamp, block_number, source_id) FROM STDIN WITH (FORMAT csv, NULL '\N')"#;
+        let copy_query = format!(
+            r#"COPY temp_fills ({}, dup_seq) FROM STDIN WITH (FORMAT csv, NULL '\N')"#,
+            FILL_COLUMNS
+        );
 
-        let mut copy_in = tx.copy_in_raw(copy_query).await?;
+        let mut copy_in = conn.copy_in_raw(&copy_query).await?;
 
         // Build CSV data
         let mut csv_data = String::new();
@@ -111,22 +941,37 @@ impl Store {
             // Get or create market
             let market_id = self.market_registry.get_or_create_market(&fill.coin).await?;
 
+            let liquidation = fill.liquidation.as_ref();
+
             use std::fmt::Write;
-            write!(
+            writeln!(
                 csv_data,
-                "{},{},{},{},{},{},{},{},{},{},{}\n",
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
                 self.exchange_id,
                 market_id,
                 fill.user_address,
                 fill.side,
                 fill.price,
                 fill.size,
-                fill.fee.map_or("\\N".to_string(), |f| f.to_string()),
-                fill.closed_pnl.map_or("\\N".to_string(), |p| p.to_string()),
+                csv_value(fill.fee),
+                csv_value(fill.closed_pnl),
                 fill.timestamp.to_rfc3339(),
-                fill.block_number.map_or("\\N".to_string(), |b| b.to_string()),
-                fill.source_id.as_deref().unwrap_or("\\N")
-            ).map_err(|e| indexer_core::Error::Io(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
+                csv_value(fill.block_number),
+                csv_text(fill.source_id.as_deref()),
+                csv_value(fill.tid),
+                csv_value(fill.oid),
+                csv_text(fill.hash.as_deref()),
+                csv_value(fill.crossed),
+                csv_text(fill.dir.as_deref()),
+                csv_value(fill.start_position),
+                csv_text(fill.fee_token.as_deref()),
+                csv_text(liquidation.and_then(|l| l.liquidated_user.as_deref())),
+                csv_value(liquidation.and_then(|l| l.mark_price)),
+                csv_text(liquidation.and_then(|l| l.method.as_deref())),
+                csv_value(fill.twap_id),
+                csv_value(fill.builder_fee),
+                fill.dup_seq
+            ).map_err(|e| indexer_core::Error::Io(std::io::Error::other(e)))?;
         }
 
         copy_in.send(csv_data.as_bytes()).await?;
@@ -134,32 +979,44 @@ impl Store {
 
         // Insert from temp table with conflict handling
         // Note: We need to specify columns explicitly since fills has an auto-generated id
-        let result = sqlx::query(
+        let insert_query = format!(
             r#"
-            INSERT INTO fills (exchange_id, market_id, user_address, side, price, size, fee, closed_pnl, timestamp, block_number, source_id)
-            SELECT exchange_id, market_id, user_address, side, price, size, fee, closed_pnl, timestamp, block_number, source_id
-            FROM temp_fills
-            ON CONFLICT (exchange_id, user_address, market_id, timestamp, price, size)
-            DO NOTHING
-            "#
-        )
-        .execute(&mut *tx)
-        .await?;
+            WITH inserted AS (
+                INSERT INTO fills ({columns}, fill_key)
+                SELECT {columns}, {fill_key}
+                FROM temp_fills
+                ON CONFLICT (exchange_id, fill_key)
+                DO NOTHING
+                RETURNING source_id
+            )
+            SELECT source_id, COUNT(*) FROM inserted GROUP BY source_id
+            "#,
+            columns = FILL_COLUMNS,
+            fill_key = FILL_KEY_EXPR
+        );
+        let counts: Vec<(Option<String>, i64)> = sqlx::query_as(&insert_query)
+            .fetch_all(&mut *conn)
+            .await?;
 
-        tx.commit().await?;
+        // The batch transaction may insert several chunks
+        sqlx::query("DROP TABLE temp_fills")
+            .execute(&mut *conn)
+            .await?;
 
-        Ok(result.rows_affected() as usize)
+        Ok(counts.into_iter().map(|(key, count)| (key, count as usize)).collect())
     }
 
-    async fn bulk_insert_with_values_optimized(&self, fills: &[Fill]) -> Result<usize> {
+    async fn bulk_insert_with_values_optimized(
+        &self,
+        conn: &mut PgConnection,
+        fills: &[Fill],
+    ) -> Result<HashMap<Option<String>, usize>> {
         // Optimized multi-row VALUES with safe batch size
-        // PostgreSQL has a limit of 65535 parameters, and we use 11 params per row
-        const BATCH_SIZE: usize = 5000; // Safe batch size: 5000 * 11 = 55,000 params
-        let mut total_inserted = 0;
+        // PostgreSQL has a limit of 65535 parameters, and we use 24 params per row
+        const BATCH_SIZE: usize = 2500; // Safe batch size: 2500 * 24 = 60,000 params
+        let mut inserted_by_key: HashMap<Option<String>, usize> = HashMap::new();
 
         for batch in fills.chunks(BATCH_SIZE) {
-            let mut tx = self.pool.begin().await?;
-
             // Build multi-row insert query
             // Get market IDs for all coins in batch first
             let mut market_ids = Vec::with_capacity(batch.len());
@@ -172,57 +1029,190 @@ impl Store {
             let mut param_index = 1;
 
             for _ in batch {
-                let placeholders: Vec<String> = (0..11)
+                // One extra parameter per row for dup_seq
+                let placeholders: Vec<String> = (0..=FILL_COLUMN_COUNT)
                     .map(|i| format!("${}", param_index + i))
                     .collect();
                 values_strings.push(format!("({})", placeholders.join(", ")));
-                param_index += 11;
+                param_index += FILL_COLUMN_COUNT + 1;
             }
 
             let query_string = format!(
                 r#"
-                INSERT INTO fills (
-                    exchange_id, market_id, user_address, side, price, size,
-                    fee, closed_pnl, timestamp, block_number, source_id
-                ) VALUES {}
-                ON CONFLICT (exchange_id, user_address, market_id, timestamp, price, size)
-                DO NOTHING
+                WITH inserted AS (
+                    INSERT INTO fills ({columns}, fill_key)
+                    SELECT {columns}, {fill_key}
+                    FROM (VALUES {values}) AS v({columns}, dup_seq)
+                    ON CONFLICT (exchange_id, fill_key)
+                    DO NOTHING
+                    RETURNING source_id
+                )
+                SELECT source_id, COUNT(*) FROM inserted GROUP BY source_id
                 "#,
-                values_strings.join(", ")
+                columns = FILL_COLUMNS,
+                fill_key = FILL_KEY_EXPR,
+                values = values_strings.join(", ")
             );
 
-            let mut query = sqlx::query(&query_string);
+            let mut query = sqlx::query_as::<_, (Option<String>, i64)>(&query_string);
 
             // Bind all parameters
             for (fill, market_id) in batch.iter().zip(market_ids.iter()) {
+                let liquidation = fill.liquidation.as_ref();
                 query = query
                     .bind(self.exchange_id)
                     .bind(market_id)
                     .bind(&fill.user_address)
                     .bind(fill.side.to_string())
-                    .bind(BigDecimal::from_str(&fill.price.to_string()).ok())
-                    .bind(BigDecimal::from_str(&fill.size.to_string()).ok())
-                    .bind(fill.fee.and_then(|f| BigDecimal::from_str(&f.to_string()).ok()))
-                    .bind(fill.closed_pnl.and_then(|p| BigDecimal::from_str(&p.to_string()).ok()))
+                    .bind(fill.price)
+                    .bind(fill.size)
+                    .bind(fill.fee)
+                    .bind(fill.closed_pnl)
                     .bind(fill.timestamp)
                     .bind(fill.block_number)
-                    .bind(fill.source_id.as_deref());
+                    .bind(fill.source_id.as_deref())
+                    .bind(fill.tid)
+                    .bind(fill.oid)
+                    .bind(fill.hash.as_deref())
+                    .bind(fill.crossed)
+                    .bind(fill.dir.as_deref())
+                    .bind(fill.start_position)
+                    .bind(fill.fee_token.as_deref())
+                    .bind(liquidation.and_then(|l| l.liquidated_user.as_deref()))
+                    .bind(liquidation.and_then(|l| l.mark_price))
+                    .bind(liquidation.and_then(|l| l.method.as_deref()))
+                    .bind(fill.twap_id)
+                    .bind(fill.builder_fee)
+                    .bind(fill.dup_seq);
             }
 
-            let result = query.execute(&mut *tx).await?;
-            total_inserted += result.rows_affected() as usize;
-
-            tx.commit().await?;
+            for (key, count) in query.fetch_all(&mut *conn).await? {
+                *inserted_by_key.entry(key).or_default() += count as usize;
+            }
         }
 
-        Ok(total_inserted)
+        Ok(inserted_by_key)
     }
 
+    /// Write the manifest row of each hour file, or hour chunk, in the batch. An hour
+    /// becomes `complete` with its last chunk and stays complete when re-fetched or
+    /// when a later attempt fails.
+    async fn record_hours(
+        &self,
+        conn: &mut PgConnection,
+        hours: &[HourReport],
+        inserted_by_key: &HashMap<Option<String>, usize>,
+        overflowing: &[(&Fill, String)],
+    ) -> Result<()> {
+        for hour in hours {
+            let key = Some(hour.key.clone());
+            let inserted = inserted_by_key.get(&key).copied().unwrap_or(0);
+            let quarantined = overflowing
+                .iter()
+                .filter(|(fill, _)| fill.source_id == key)
+                .count();
+            let duplicates = hour.fills.saturating_sub(inserted + quarantined);
+
+            // Counters of a chunk add to the chunks before it; chunk 0 starts a new attempt.
+            // A complete hour keeps the counts of the load that completed it.
+            sqlx::query!(
+                r#"
+                INSERT INTO ingest_hours (
+                    exchange_id, hour, s3_key, etag, compressed_bytes, lines,
+                    expected_format, detected_format, fills_parsed, fills_inserted, duplicates,
+                    fills_rejected, status, attempts, last_error, first_block, last_block,
+                    block_count, updated_at
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13::text,
+                        CASE WHEN $13::text = 'partial' THEN 0 ELSE 1 END, $15, $16, $17, $18, NOW())
+                ON CONFLICT (exchange_id, hour) DO UPDATE SET
+                    s3_key = CASE WHEN ingest_hours.status = 'complete' THEN ingest_hours.s3_key ELSE EXCLUDED.s3_key END,
+                    etag = CASE WHEN ingest_hours.status = 'complete' THEN ingest_hours.etag ELSE EXCLUDED.etag END,
+                    expected_format = EXCLUDED.expected_format,
+                    detected_format = CASE
+                        WHEN ingest_hours.status = 'complete' THEN ingest_hours.detected_format
+                        WHEN $14 = 0 THEN EXCLUDED.detected_format
+                        ELSE COALESCE(EXCLUDED.detected_format, ingest_hours.detected_format)
+                    END,
+                    compressed_bytes = CASE
+                        WHEN ingest_hours.status = 'complete' THEN ingest_hours.compressed_bytes
+                        WHEN $14 = 0 THEN EXCLUDED.compressed_bytes
+                        ELSE COALESCE(ingest_hours.compressed_bytes, 0) + EXCLUDED.compressed_bytes
+                    END,
+                    lines = CASE
+                        WHEN ingest_hours.status = 'complete' THEN ingest_hours.lines
+                        WHEN $14 = 0 THEN EXCLUDED.lines
+                        ELSE COALESCE(ingest_hours.lines, 0) + EXCLUDED.lines
+                    END,
+                    fills_parsed = CASE
+                        WHEN ingest_hours.status = 'complete' THEN ingest_hours.fills_parsed
+                        WHEN $14 = 0 THEN EXCLUDED.fills_parsed
+                        ELSE ingest_hours.fills_parsed + EXCLUDED.fills_parsed
+                    END,
+                    fills_inserted = CASE
+                        WHEN ingest_hours.status = 'complete' THEN ingest_hours.fills_inserted
+                        WHEN $14 = 0 THEN EXCLUDED.fills_inserted
+                        ELSE COALESCE(ingest_hours.fills_inserted, 0) + EXCLUDED.fills_inserted
+                    END,
+                    duplicates = CASE
+                        WHEN ingest_hours.status = 'complete' THEN ingest_hours.duplicates
+                        WHEN $14 = 0 THEN EXCLUDED.duplicates
+                        ELSE COALESCE(ingest_hours.duplicates, 0) + EXCLUDED.duplicates
+                    END,
+                    fills_rejected = CASE
+                        WHEN ingest_hours.status = 'complete' THEN ingest_hours.fills_rejected
+                        WHEN $14 = 0 THEN EXCLUDED.fills_rejected
+                        ELSE COALESCE(ingest_hours.fills_rejected, 0) + EXCLUDED.fills_rejected
+                    END,
+                    first_block = CASE
+                        WHEN ingest_hours.status = 'complete' THEN ingest_hours.first_block
+                        WHEN $14 = 0 THEN EXCLUDED.first_block
+                        ELSE LEAST(ingest_hours.first_block, EXCLUDED.first_block)
+                    END,
+                    last_block = CASE
+                        WHEN ingest_hours.status = 'complete' THEN ingest_hours.last_block
+                        WHEN $14 = 0 THEN EXCLUDED.last_block
+                        ELSE GREATEST(ingest_hours.last_block, EXCLUDED.last_block)
+                    END,
+                    block_count = CASE
+                        WHEN ingest_hours.status = 'complete' THEN ingest_hours.block_count
+                        WHEN $14 = 0 THEN EXCLUDED.block_count
+                        ELSE COALESCE(ingest_hours.block_count, 0) + EXCLUDED.block_count
+                    END,
+                    status = CASE WHEN ingest_hours.status = 'complete' THEN 'complete' ELSE EXCLUDED.status END,
+                    attempts = ingest_hours.attempts + EXCLUDED.attempts,
+                    last_error = CASE WHEN EXCLUDED.status = 'partial' THEN ingest_hours.last_error ELSE EXCLUDED.last_error END,
+                    updated_at = EXCLUDED.updated_at
+                "#,
+                self.exchange_id,
+                hour.hour,
+                hour.key,
+                hour.etag,
+                hour.compressed_bytes as i64,
+                hour.lines as i32,
+                hour.expected_format,
+                hour.detected_format,
+                hour.fills as i32,
+                inserted as i32,
+                duplicates as i32,
+                (hour.rejected + quarantined) as i32,
+                hour.status(),
+                hour.chunk as i32,
+                hour.error,
+                hour.first_block,
+                hour.last_block,
+                hour.block_count as i32
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        Ok(())
+    }
 
     #[instrument(skip(self))]
     pub async fn get_checkpoint(&self, source: &str) -> Result<Option<Checkpoint>> {
-        let checkpoint = sqlx::query_as!(
-            Checkpoint,
+        let row = sqlx::query!(
             r#"
             SELECT source, cursor, last_record_ts, last_block_number,
                    records_processed, updated_at as "updated_at!", metadata
@@ -235,7 +1225,24 @@ impl Store {
         .fetch_optional(&self.pool)
         .await?;
 
-        Ok(checkpoint)
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let cursor = match row.cursor {
+            Some(cursor) => Some(Cursor::parse(&cursor, SourceKind::from_source_id(&row.source)?)?),
+            None => None,
+        };
+
+        Ok(Some(Checkpoint {
+            source: row.source,
+            cursor,
+            last_record_ts: row.last_record_ts,
+            last_block_number: row.last_block_number,
+            records_processed: row.records_processed,
+            updated_at: row.updated_at,
+            metadata: row.metadata,
+        }))
     }
 
     #[instrument(skip(self))]
@@ -256,7 +1263,7 @@ impl Store {
             "#,
             self.exchange_id,
             checkpoint.source,
-            checkpoint.cursor,
+            checkpoint.cursor.as_ref().map(|cursor| cursor.to_string()),
             checkpoint.last_record_ts,
             checkpoint.last_block_number,
             checkpoint.records_processed,
@@ -277,6 +1284,7 @@ impl Store {
         Ok(())
     }
 
+    #[allow(dead_code)]
     #[instrument(skip(self))]
     pub async fn get_latest_fill_timestamp(&self) -> Result<Option<DateTime<Utc>>> {
         let result = sqlx::query!(
@@ -293,37 +1301,9 @@ impl Store {
         Ok(result.max_timestamp)
     }
 
-    #[instrument(skip(self))]
-    pub async fn check_time_range_exists(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<(bool, i64)> {
-        // Check if we have data for this time range and get the count
-        let result = sqlx::query!(
-            r#"
-            SELECT COUNT(*) as "count!"
-            FROM fills
-            WHERE exchange_id = $1 AND timestamp >= $2 AND timestamp < $3
-            "#,
-            self.exchange_id,
-            start,
-            end
-        )
-        .fetch_one(&self.pool)
-        .await?;
-
-        let has_data = result.count > 0;
-        debug!(
-            start = %start,
-            end = %end,
-            count = result.count,
-            has_data = has_data,
-            "Checked time range for existing data"
-        );
-
-        Ok((has_data, result.count))
-    }
-
+    /// Hours in `[start, end)` not loaded yet: neither `complete` nor `missing_upstream` in the manifest
     #[instrument(skip(self))]
     pub async fn get_missing_hours(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<DateTime<Utc>>> {
-        // Get list of hours that have no data or very little data
         let result = sqlx::query!(
             r#"
             WITH hour_series AS (
@@ -332,20 +1312,14 @@ impl Store {
                     DATE_TRUNC('hour', $2::timestamptz) - INTERVAL '1 hour',
                     INTERVAL '1 hour'
                 ) AS hour
-            ),
-            hourly_counts AS (
-                SELECT
-                    DATE_TRUNC('hour', timestamp) AS hour,
-                    COUNT(*) AS count
-                FROM fills
-                WHERE exchange_id = $3 AND timestamp >= $1 AND timestamp < $2
-                GROUP BY DATE_TRUNC('hour', timestamp)
             )
             SELECT
                 hs.hour AS "hour!"
             FROM hour_series hs
-            LEFT JOIN hourly_counts hc ON hs.hour = hc.hour
-            WHERE hc.count IS NULL OR hc.count < 1000  -- Hours with less than 1000 records are considered incomplete
+            LEFT JOIN ingest_hours ih
+                ON ih.exchange_id = $3 AND ih.hour = hs.hour
+                AND ih.status IN ('complete', 'missing_upstream')
+            WHERE ih.hour IS NULL
             ORDER BY hs.hour
             "#,
             start,
@@ -362,7 +1336,7 @@ impl Store {
 
         if !missing_hours.is_empty() {
             info!(
-                "Found {} hours with missing or incomplete data between {} and {}",
+                "Found {} hours not yet loaded between {} and {}",
                 missing_hours.len(),
                 start.format("%Y-%m-%d %H:%M"),
                 end.format("%Y-%m-%d %H:%M")
@@ -372,6 +1346,30 @@ impl Store {
         Ok(missing_hours)
     }
 
+    /// Hours in `[start, end)` that the bucket had no file for while later hours existed
+    #[instrument(skip(self))]
+    pub async fn get_missing_upstream_hours(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<DateTime<Utc>>> {
+        let hours = sqlx::query_scalar!(
+            r#"
+            SELECT hour AS "hour!"
+            FROM ingest_hours
+            WHERE exchange_id = $1
+                AND status = 'missing_upstream'
+                AND hour >= DATE_TRUNC('hour', $2::timestamptz)
+                AND hour < DATE_TRUNC('hour', $3::timestamptz)
+            ORDER BY hour
+            "#,
+            self.exchange_id,
+            start,
+            end
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(hours)
+    }
+
+    #[allow(dead_code)]
     #[instrument(skip(self))]
     pub async fn update_daily_stats(&self, date: chrono::NaiveDate) -> Result<()> {
         sqlx::query!(
@@ -426,6 +1424,7 @@ impl Store {
         Ok(())
     }
 
+    #[allow(dead_code)]
     #[instrument(skip(self))]
     pub async fn refresh_hourly_stats_view(&self) -> Result<()> {
         // Track refresh time for performance monitoring
@@ -501,4 +1500,107 @@ impl Store {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingest::api_source::RECENT_TRADES_SOURCE;
+    use rust_decimal::Decimal;
+
+    async fn test_store(pool: PgPool) -> Store {
+        let exchange_id: i32 = sqlx::query_scalar("SELECT id FROM exchanges WHERE code = 'HL'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        Store {
+            market_registry: Arc::new(MarketRegistry::offline(pool.clone(), exchange_id)),
+            pool,
+            exchange_id,
+        }
+    }
+
+    fn fill(tid: Option<i64>, source_id: &str) -> Fill {
+        Fill {
+            user_address: "0x1111111111111111111111111111111111111111".to_string(),
+            coin: "BTC".to_string(),
+            side: TradeSide::Buy,
+            price: Decimal::new(1000005, 1),
+            size: Decimal::new(25, 2),
+            fee: None,
+            closed_pnl: None,
+            timestamp: DateTime::from_timestamp_millis(1_700_000_000_123).unwrap(),
+            block_number: None,
+            source_id: Some(source_id.to_string()),
+            tid,
+            oid: None,
+            hash: None,
+            crossed: Some(true),
+            dir: None,
+            start_position: None,
+            fee_token: None,
+            liquidation: None,
+            twap_id: None,
+            builder_fee: None,
+            dup_seq: 0,
+        }
+    }
+
+    async fn fill_keys(store: &Store) -> Vec<String> {
+        sqlx::query_scalar("SELECT fill_key FROM fills ORDER BY fill_key")
+            .fetch_all(&store.pool)
+            .await
+            .unwrap()
+    }
+
+    /// A row loaded before trade ids were captured, then its hour reloaded with them
+    #[sqlx::test(migrations = "../migrations")]
+    async fn reload_with_trade_ids_replaces_legacy_row(pool: PgPool) {
+        let store = test_store(pool).await;
+        store.insert_fills(&[fill(None, "node_trades/hourly/20231114/22")]).await.unwrap();
+        assert!(fill_keys(&store).await[0].starts_with("legacy:"));
+
+        let reload = [fill(Some(7), "node_fills/hourly/20231114/22.lz4")];
+        assert_eq!(store.insert_fills(&reload).await.unwrap(), 1);
+        assert_eq!(store.insert_fills(&reload).await.unwrap(), 0);
+
+        assert_eq!(fill_keys(&store).await, vec!["tid:7:BUY".to_string()]);
+    }
+
+    /// The copy built from a public trade arrives first in the batch, the user's own fill second
+    #[sqlx::test(migrations = "../migrations")]
+    async fn keeps_user_fill_over_trade_copy(pool: PgPool) {
+        let store = test_store(pool).await;
+        let copy = fill(Some(7), RECENT_TRADES_SOURCE);
+        let mut user_fill = fill(Some(7), USER_FILLS_SOURCE);
+        user_fill.fee = Some(Decimal::new(5, 1));
+
+        assert_eq!(store.insert_fills(&[copy, user_fill]).await.unwrap(), 1);
+
+        let (source_id, fee): (String, Option<Decimal>) = sqlx::query_as("SELECT source_id, fee FROM fills")
+            .fetch_one(&store.pool)
+            .await
+            .unwrap();
+        assert_eq!(source_id, USER_FILLS_SOURCE);
+        assert_eq!(fee, Some(Decimal::new(5, 1)));
+    }
+
+    /// A fill quarantined for overflowing its column, then its hour reloaded
+    #[sqlx::test(migrations = "../migrations")]
+    async fn reload_does_not_repeat_quarantined_fill(pool: PgPool) {
+        let store = test_store(pool).await;
+        let mut overflowing = fill(Some(7), "node_fills/hourly/20231114/22.lz4");
+        overflowing.price = Decimal::from(10_000_000_000_i64);
+
+        store.insert_fills(&[overflowing.clone()]).await.unwrap();
+        store.insert_fills(&[overflowing]).await.unwrap();
+
+        let rejects: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM fill_rejects")
+            .fetch_one(&store.pool)
+            .await
+            .unwrap();
+        assert_eq!(rejects, 1);
+        assert!(fill_keys(&store).await.is_empty());
+    }
+}
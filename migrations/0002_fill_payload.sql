@@ -0,0 +1,29 @@
+-- Capture the full upstream fill payload
+-- Trade/order identity, maker/taker flag, direction, position, fee token, liquidation, TWAP and builder fee
+
+ALTER TABLE fills
+    ADD COLUMN tid BIGINT,
+    ADD COLUMN oid BIGINT,
+    ADD COLUMN hash VARCHAR(66),
+    ADD COLUMN crossed BOOLEAN,
+    ADD COLUMN dir VARCHAR(32),
+    ADD COLUMN start_position NUMERIC(30, 10),
+    ADD COLUMN fee_token VARCHAR(20),
+    ADD COLUMN liquidated_user VARCHAR(66),
+    ADD COLUMN liquidation_mark_price NUMERIC(20, 10),
+    ADD COLUMN liquidation_method VARCHAR(20),
+    ADD COLUMN twap_id BIGINT,
+    ADD COLUMN builder_fee NUMERIC(20, 10);
+
+CREATE INDEX idx_fills_tid ON fills(tid) WHERE tid IS NOT NULL;
+CREATE INDEX idx_fills_oid ON fills(oid) WHERE oid IS NOT NULL;
+CREATE INDEX idx_fills_hash ON fills(hash) WHERE hash IS NOT NULL;
+CREATE INDEX idx_fills_liquidations ON fills(liquidated_user, timestamp DESC) WHERE liquidated_user IS NOT NULL;
+
+COMMENT ON COLUMN fills.tid IS 'Upstream trade id, shared by the buy and sell side of a match';
+COMMENT ON COLUMN fills.oid IS 'Upstream order id of this side';
+COMMENT ON COLUMN fills.hash IS 'L1 transaction hash';
+COMMENT ON COLUMN fills.crossed IS 'True if this side was the taker';
+COMMENT ON COLUMN fills.dir IS 'Direction as reported upstream (Open Long, Close Short, Buy, Sell, ...)';
+COMMENT ON COLUMN fills.start_position IS 'Position size before this fill';
+COMMENT ON COLUMN fills.liquidated_user IS 'Liquidated account when the fill was part of a liquidation';
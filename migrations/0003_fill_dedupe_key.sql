@@ -0,0 +1,55 @@
+-- Deduplicate fills by upstream trade identity
+--
+-- The old unique_fill constraint on (exchange_id, user_address, market_id, timestamp, price, size)
+-- dropped legitimate fills, e.g. one order sweeping several resting orders at the same price.
+-- Fills are now keyed by fill_key:
+--
+--   tid:<tid>:<side>         when the source provides a trade id (node_fills, node_fills_by_block).
+--                            Both sides of a match share the tid, so the side keeps them apart.
+--   legacy:md5(<tuple>)      fallback for node_trades and older rows without a tid. The tuple is
+--                            (user, market, timestamp ms, price, size, side, seq) where seq is the
+--                            occurrence index of identical tuples within one hour file, so
+--                            repeated same-price fills are kept. The tx hash is deliberately not
+--                            part of the key: one transaction can produce many fills, and rows
+--                            loaded before hashes were captured must keep matching on re-ingest.
+--
+-- Existing rows are backfilled with seq = 0, which is what re-ingesting them produces because
+-- the old constraint allowed at most one row per tuple. Rows loaded before tids were captured
+-- keep a legacy key; re-ingesting those hours from node_fills/node_fills_by_block will insert
+-- tid-keyed copies, so delete such ranges before re-loading them.
+
+CREATE OR REPLACE FUNCTION fill_dedupe_key(
+    p_tid BIGINT,
+    p_side VARCHAR,
+    p_user_address VARCHAR,
+    p_market_id INTEGER,
+    p_timestamp TIMESTAMPTZ,
+    p_price NUMERIC,
+    p_size NUMERIC,
+    p_seq INTEGER
+) RETURNS TEXT AS $$
+    SELECT CASE
+        WHEN p_tid IS NOT NULL THEN 'tid:' || p_tid || ':' || p_side
+        ELSE 'legacy:' || md5(concat_ws('|',
+            p_user_address,
+            p_market_id,
+            floor(extract(epoch FROM p_timestamp) * 1000)::BIGINT,
+            round(p_price, 10),
+            round(p_size, 10),
+            p_side,
+            p_seq
+        ))
+    END
+$$ LANGUAGE sql IMMUTABLE;
+
+ALTER TABLE fills ADD COLUMN fill_key TEXT;
+
+UPDATE fills
+SET fill_key = fill_dedupe_key(tid, side, user_address, market_id, timestamp, price, size, 0);
+
+ALTER TABLE fills ALTER COLUMN fill_key SET NOT NULL;
+
+ALTER TABLE fills DROP CONSTRAINT unique_fill;
+ALTER TABLE fills ADD CONSTRAINT unique_fill_key UNIQUE (exchange_id, fill_key);
+
+COMMENT ON COLUMN fills.fill_key IS 'Dedupe key: tid:<tid>:<side> when a trade id exists, legacy:md5(...) otherwise (see fill_dedupe_key)';
@@ -0,0 +1,16 @@
+-- Quarantine for fills that cannot be stored in the fills table
+-- e.g. a price, size or generated volume_usd that overflows NUMERIC(20, 10).
+-- The parsed fill is kept as JSON so it can be re-inserted once the schema is widened.
+
+CREATE TABLE fill_rejects (
+    id BIGSERIAL PRIMARY KEY,
+    exchange_id INTEGER NOT NULL REFERENCES exchanges(id),
+    reason TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    fill_timestamp TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_fill_rejects_created ON fill_rejects(created_at DESC);
+
+COMMENT ON TABLE fill_rejects IS 'Fills rejected at insert time, with the reason and the parsed fill as JSON';
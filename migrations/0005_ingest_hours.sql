@@ -0,0 +1,21 @@
+-- Per-hour ingestion audit
+-- Records which key each hour file was loaded from and which record format was detected,
+-- so hours around schema cutovers can be checked.
+
+CREATE TABLE ingest_hours (
+    exchange_id INTEGER NOT NULL REFERENCES exchanges(id),
+    hour TIMESTAMPTZ NOT NULL,
+    s3_key TEXT NOT NULL,
+    expected_format VARCHAR(50) NOT NULL,
+    detected_format VARCHAR(50),
+    fills_parsed INTEGER NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (exchange_id, hour)
+);
+
+CREATE INDEX idx_ingest_hours_format_mismatch ON ingest_hours(hour)
+    WHERE detected_format IS DISTINCT FROM expected_format;
+
+COMMENT ON TABLE ingest_hours IS 'One row per ingested hour file: source key and detected record format';
+COMMENT ON COLUMN ingest_hours.detected_format IS 'Format sniffed from the first line; NULL if the file was empty or unrecognized';
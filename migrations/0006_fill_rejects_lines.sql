@@ -0,0 +1,26 @@
+-- Dead-letter unparseable lines alongside rejected fills
+-- A bad line no longer fails its hour: it is stored here with its position in the hour file
+-- and can be re-processed with `indexer reprocess-rejects` once the parser is fixed.
+
+ALTER TABLE fill_rejects
+    ALTER COLUMN payload DROP NOT NULL,
+    ADD COLUMN hour TIMESTAMPTZ,
+    ADD COLUMN s3_key TEXT,
+    ADD COLUMN line_number INTEGER,
+    ADD COLUMN raw_line TEXT,
+    ADD COLUMN schema_format VARCHAR(50),
+    ADD COLUMN resolved_at TIMESTAMPTZ;
+
+ALTER TABLE fill_rejects
+    ADD CONSTRAINT fill_rejects_has_content CHECK (raw_line IS NOT NULL OR payload IS NOT NULL);
+
+-- Re-loading an hour must not record its bad lines twice
+CREATE UNIQUE INDEX idx_fill_rejects_line ON fill_rejects(exchange_id, s3_key, line_number, md5(reason))
+    WHERE raw_line IS NOT NULL;
+
+CREATE INDEX idx_fill_rejects_unresolved ON fill_rejects(id) WHERE resolved_at IS NULL;
+CREATE INDEX idx_fill_rejects_hour ON fill_rejects(hour) WHERE hour IS NOT NULL;
+
+COMMENT ON COLUMN fill_rejects.raw_line IS 'Original line from the hour file; NULL for fills rejected after parsing';
+COMMENT ON COLUMN fill_rejects.schema_format IS 'Record format the line was parsed as';
+COMMENT ON COLUMN fill_rejects.resolved_at IS 'Set once the reject was re-processed and ingested';
@@ -0,0 +1,50 @@
+-- Per-hour ingestion manifest
+-- Extends ingest_hours so it records how every hour file was loaded. Gap detection and
+-- backfill skipping use the `complete` rows instead of counting fills per hour.
+
+ALTER TABLE ingest_hours
+    ADD COLUMN etag TEXT,
+    ADD COLUMN compressed_bytes BIGINT,
+    ADD COLUMN lines INTEGER,
+    ADD COLUMN fills_inserted INTEGER,
+    ADD COLUMN duplicates INTEGER,
+    ADD COLUMN fills_rejected INTEGER,
+    ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'complete',
+    ADD COLUMN attempts INTEGER NOT NULL DEFAULT 1,
+    ADD COLUMN last_error TEXT,
+    ADD CONSTRAINT ingest_hours_status_check CHECK (status IN ('complete', 'failed'));
+
+-- Hours loaded before the manifest existed: keep the old heuristic once, so they are
+-- not downloaded again. Keys follow the default schema epochs.
+INSERT INTO ingest_hours (exchange_id, hour, s3_key, expected_format, fills_parsed, fills_inserted, status, attempts)
+SELECT
+    f.exchange_id,
+    DATE_TRUNC('hour', f.timestamp) AS hour,
+    CASE
+        WHEN DATE_TRUNC('hour', f.timestamp) >= '2025-07-27T00:00:00Z' THEN 'node_fills_by_block/hourly/'
+        WHEN DATE_TRUNC('hour', f.timestamp) >= '2025-05-25T00:00:00Z' THEN 'node_fills/hourly/'
+        ELSE 'node_trades/hourly/'
+    END
+        || TO_CHAR(DATE_TRUNC('hour', f.timestamp) AT TIME ZONE 'UTC', 'YYYYMMDD') || '/'
+        || EXTRACT(HOUR FROM DATE_TRUNC('hour', f.timestamp) AT TIME ZONE 'UTC')::INTEGER || '.lz4',
+    CASE
+        WHEN DATE_TRUNC('hour', f.timestamp) >= '2025-07-27T00:00:00Z' THEN 'node_fills_by_block'
+        WHEN DATE_TRUNC('hour', f.timestamp) >= '2025-05-25T00:00:00Z' THEN 'node_fills'
+        ELSE 'node_trades'
+    END,
+    COUNT(*),
+    COUNT(*),
+    'complete',
+    0
+FROM fills f
+GROUP BY f.exchange_id, DATE_TRUNC('hour', f.timestamp)
+HAVING COUNT(*) >= 1000
+ON CONFLICT (exchange_id, hour) DO NOTHING;
+
+CREATE INDEX idx_ingest_hours_failed ON ingest_hours(hour)
+    WHERE status = 'failed';
+
+COMMENT ON TABLE ingest_hours IS 'Manifest of hour files: source key, ETag, load counts and status';
+COMMENT ON COLUMN ingest_hours.status IS 'complete once the hour''s fills are committed; failed if the last attempt errored and no earlier attempt succeeded';
+COMMENT ON COLUMN ingest_hours.duplicates IS 'Parsed fills that were already stored';
+COMMENT ON COLUMN ingest_hours.attempts IS 'Load attempts; 0 for hours seeded from existing fills';
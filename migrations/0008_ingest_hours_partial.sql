@@ -0,0 +1,8 @@
+-- Streamed hour files are loaded in several transactions, one per chunk of fills.
+-- Until the last chunk commits, the hour is `partial` and still counts as a gap.
+
+ALTER TABLE ingest_hours DROP CONSTRAINT ingest_hours_status_check;
+ALTER TABLE ingest_hours
+    ADD CONSTRAINT ingest_hours_status_check CHECK (status IN ('complete', 'partial', 'failed'));
+
+COMMENT ON COLUMN ingest_hours.status IS 'complete once the last chunk of the hour''s fills is committed; partial while earlier chunks are; failed if the last attempt errored and no earlier attempt succeeded';
@@ -0,0 +1,12 @@
+-- Hours are planned from a listing of the bucket. An hour without a file while later
+-- hours exist is `missing_upstream`, which is reported apart from hours not fetched yet.
+
+ALTER TABLE ingest_hours DROP CONSTRAINT ingest_hours_status_check;
+ALTER TABLE ingest_hours
+    ADD CONSTRAINT ingest_hours_status_check
+    CHECK (status IN ('complete', 'partial', 'failed', 'missing_upstream'));
+
+CREATE INDEX idx_ingest_hours_missing_upstream ON ingest_hours(hour)
+    WHERE status = 'missing_upstream';
+
+COMMENT ON COLUMN ingest_hours.status IS 'complete once the last chunk of the hour''s fills is committed; partial while earlier chunks are; failed if the last attempt errored and no earlier attempt succeeded; missing_upstream if the bucket had no file for the hour but had later ones';
@@ -0,0 +1,18 @@
+-- Checkpoint cursors used to be plain `YYYYMMDD_H` strings. They are now versioned JSON,
+-- e.g. {"v":1,"kind":"s3","format":"node_fills_by_block","hour":"2025-08-02T06:00:00Z"},
+-- which non-hourly sources can extend with a byte offset or block number. Rewrite the
+-- old cursors of the hourly sources; the indexer still reads any it finds.
+
+UPDATE ingest_checkpoints
+SET cursor = json_build_object(
+        'v', 1,
+        'kind', source,
+        'hour', to_char(
+            to_date(split_part(cursor, '_', 1), 'YYYYMMDD') + split_part(cursor, '_', 2)::int * INTERVAL '1 hour',
+            'YYYY-MM-DD"T"HH24:MI:SS"Z"'
+        )
+    )::text
+WHERE source IN ('s3', 'local')
+  AND cursor ~ '^[0-9]{8}_[0-9]{1,2}$';
+
+COMMENT ON COLUMN ingest_checkpoints.cursor IS 'Versioned JSON cursor: v, kind, format, hour and optionally offset or block to resume from';
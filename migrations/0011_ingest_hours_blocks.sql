@@ -0,0 +1,14 @@
+-- node_fills_by_block files carry one block per line. Each hour records the block
+-- range it covered so gaps inside an hour, or between hours, can be found with SQL.
+
+ALTER TABLE ingest_hours
+    ADD COLUMN first_block BIGINT,
+    ADD COLUMN last_block BIGINT,
+    ADD COLUMN block_count INTEGER;
+
+CREATE INDEX idx_ingest_hours_block_gaps ON ingest_hours(hour)
+    WHERE last_block - first_block + 1 <> block_count;
+
+COMMENT ON COLUMN ingest_hours.first_block IS 'Lowest block number in the hour file; NULL for formats without block numbers';
+COMMENT ON COLUMN ingest_hours.last_block IS 'Highest block number in the hour file; NULL for formats without block numbers';
+COMMENT ON COLUMN ingest_hours.block_count IS 'Lines of the hour file with a block number; less than last_block - first_block + 1 when blocks are missing';
@@ -0,0 +1,25 @@
+-- node_fills_by_block lines carry the block's L1 time and the time the node processed
+-- it. One row per block keeps them, so fills can be mapped to blocks and node
+-- propagation latency measured.
+
+CREATE TABLE blocks (
+    exchange_id INTEGER NOT NULL REFERENCES exchanges(id),
+    block_number BIGINT NOT NULL,
+    block_time TIMESTAMPTZ,
+    local_time TIMESTAMPTZ,
+    fill_count INTEGER NOT NULL DEFAULT 0,
+    hour TIMESTAMPTZ NOT NULL,
+    s3_key TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (exchange_id, block_number)
+);
+
+CREATE INDEX idx_blocks_block_time ON blocks(block_time);
+CREATE INDEX idx_blocks_hour ON blocks(exchange_id, hour);
+
+COMMENT ON TABLE blocks IS 'Header of every block in the node_fills_by_block hour files';
+COMMENT ON COLUMN blocks.block_time IS 'When the block was produced on L1; NULL if the file had no readable time';
+COMMENT ON COLUMN blocks.local_time IS 'When the node that wrote the file processed the block; local_time - block_time is its propagation latency';
+COMMENT ON COLUMN blocks.fill_count IS 'Fill events in the block, including rejected ones';
+COMMENT ON COLUMN blocks.s3_key IS 'Key of the hour file the block was read from';
@@ -0,0 +1,23 @@
+-- L2 book snapshots from the hyperliquid-archive bucket
+-- (market_data/<date>/<hour>/l2Book/<coin>.lz4), one row per price level so book
+-- depth can be queried next to fills.
+
+CREATE TABLE l2_book_levels (
+    exchange_id INTEGER NOT NULL REFERENCES exchanges(id),
+    market_id INTEGER NOT NULL REFERENCES markets(id),
+    time TIMESTAMPTZ NOT NULL,
+    side VARCHAR(3) NOT NULL CHECK (side IN ('BID', 'ASK')),
+    level SMALLINT NOT NULL,
+    price NUMERIC(20, 10) NOT NULL,
+    size NUMERIC(20, 10) NOT NULL,
+    order_count INTEGER NOT NULL,
+
+    PRIMARY KEY (market_id, time, side, level)
+);
+
+CREATE INDEX idx_l2_book_levels_time ON l2_book_levels(time);
+
+COMMENT ON TABLE l2_book_levels IS 'Price levels of L2 book snapshots from the hyperliquid-archive bucket';
+COMMENT ON COLUMN l2_book_levels.time IS 'Exchange time of the snapshot';
+COMMENT ON COLUMN l2_book_levels.level IS 'Position from the top of the book on its side, 0 for the best price';
+COMMENT ON COLUMN l2_book_levels.order_count IS 'Orders resting at this price';
@@ -0,0 +1,65 @@
+-- Asset contexts from the hyperliquid-archive bucket (asset_ctxs/<date>.csv.lz4):
+-- funding, open interest and oracle/mark prices per market over time, rolled up
+-- per hour so they can be read next to hourly_market_stats.
+
+CREATE TABLE asset_ctxs (
+    exchange_id INTEGER NOT NULL REFERENCES exchanges(id),
+    market_id INTEGER NOT NULL REFERENCES markets(id),
+    time TIMESTAMPTZ NOT NULL,
+    funding NUMERIC(20, 12),
+    open_interest NUMERIC(30, 10),
+    prev_day_px NUMERIC(20, 10),
+    day_ntl_vlm NUMERIC(30, 10),
+    premium NUMERIC(20, 12),
+    oracle_px NUMERIC(20, 10),
+    mark_px NUMERIC(20, 10),
+    mid_px NUMERIC(20, 10),
+    impact_bid_px NUMERIC(20, 10),
+    impact_ask_px NUMERIC(20, 10),
+
+    PRIMARY KEY (market_id, time)
+);
+
+CREATE INDEX idx_asset_ctxs_time ON asset_ctxs(time);
+
+-- Hourly roll-up, keyed like hourly_market_stats
+CREATE VIEW hourly_asset_ctxs AS
+SELECT
+    date_trunc('hour', a.time) AS hour,
+    a.exchange_id,
+    a.market_id,
+    AVG(a.funding) AS avg_funding,
+    AVG(a.premium) AS avg_premium,
+    (array_agg(a.open_interest ORDER BY a.time DESC))[1] AS open_interest,
+    (array_agg(a.oracle_px ORDER BY a.time DESC))[1] AS oracle_px,
+    (array_agg(a.mark_px ORDER BY a.time DESC))[1] AS mark_px,
+    (array_agg(a.day_ntl_vlm ORDER BY a.time DESC))[1] AS day_ntl_vlm,
+    COUNT(*) AS samples
+FROM asset_ctxs a
+GROUP BY date_trunc('hour', a.time), a.exchange_id, a.market_id;
+
+-- Fill statistics with the market's context for the same hour
+CREATE VIEW hourly_market_stats_with_ctx AS
+SELECT
+    s.*,
+    c.avg_funding,
+    c.avg_premium,
+    c.open_interest,
+    c.oracle_px,
+    c.mark_px,
+    c.day_ntl_vlm
+FROM hourly_market_stats s
+LEFT JOIN hourly_asset_ctxs c
+    ON c.hour = s.hour AND c.exchange_id = s.exchange_id AND c.market_id = s.market_id;
+
+GRANT SELECT ON hourly_asset_ctxs TO PUBLIC;
+GRANT SELECT ON hourly_market_stats_with_ctx TO PUBLIC;
+
+COMMENT ON TABLE asset_ctxs IS 'Asset contexts (funding, open interest, prices) from the hyperliquid-archive bucket';
+COMMENT ON COLUMN asset_ctxs.funding IS 'Hourly funding rate';
+COMMENT ON COLUMN asset_ctxs.open_interest IS 'Open interest in contracts';
+COMMENT ON COLUMN asset_ctxs.day_ntl_vlm IS 'Notional volume over the previous 24 hours';
+COMMENT ON COLUMN asset_ctxs.impact_bid_px IS 'Price to sell the impact notional into the book';
+COMMENT ON COLUMN asset_ctxs.impact_ask_px IS 'Price to buy the impact notional from the book';
+COMMENT ON VIEW hourly_asset_ctxs IS 'Asset contexts per market and hour: average funding and premium, last open interest and prices';
+COMMENT ON VIEW hourly_market_stats_with_ctx IS 'hourly_market_stats joined with hourly_asset_ctxs';
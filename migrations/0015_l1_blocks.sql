@@ -0,0 +1,45 @@
+-- Explorer blocks from the node data bucket (explorer_blocks/...): one row per L1
+-- block and one per transaction in it. Fills are joined to the transaction that
+-- produced them on block_number = block_height and hash.
+
+CREATE TABLE l1_blocks (
+    exchange_id INTEGER NOT NULL REFERENCES exchanges(id),
+    height BIGINT NOT NULL,
+    block_time TIMESTAMPTZ,
+    hash VARCHAR(66),
+    proposer VARCHAR(66),
+    tx_count INTEGER NOT NULL DEFAULT 0,
+    s3_key TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (exchange_id, height)
+);
+
+CREATE INDEX idx_l1_blocks_block_time ON l1_blocks(block_time);
+
+CREATE TABLE l1_transactions (
+    exchange_id INTEGER NOT NULL REFERENCES exchanges(id),
+    block_height BIGINT NOT NULL,
+    tx_index INTEGER NOT NULL,
+    hash VARCHAR(66),
+    time TIMESTAMPTZ,
+    action_type TEXT,
+    user_address VARCHAR(66),
+    error TEXT,
+
+    PRIMARY KEY (exchange_id, block_height, tx_index)
+);
+
+CREATE INDEX idx_l1_transactions_hash ON l1_transactions(hash) WHERE hash IS NOT NULL;
+CREATE INDEX idx_l1_transactions_user ON l1_transactions(user_address, block_height DESC) WHERE user_address IS NOT NULL;
+CREATE INDEX idx_l1_transactions_action_type ON l1_transactions(action_type, block_height DESC);
+
+COMMENT ON TABLE l1_blocks IS 'Headers of the explorer blocks in the node data bucket';
+COMMENT ON COLUMN l1_blocks.proposer IS 'Validator that proposed the block';
+COMMENT ON COLUMN l1_blocks.tx_count IS 'Transactions in the block, including ones that failed to parse';
+COMMENT ON COLUMN l1_blocks.s3_key IS 'Key of the file the block was read from';
+COMMENT ON TABLE l1_transactions IS 'Transactions of the explorer blocks; join fills on block_number = block_height and hash';
+COMMENT ON COLUMN l1_transactions.tx_index IS 'Position of the transaction within its block';
+COMMENT ON COLUMN l1_transactions.action_type IS 'type of the transaction action, e.g. order or cancel';
+COMMENT ON COLUMN l1_transactions.user_address IS 'Address that signed the transaction';
+COMMENT ON COLUMN l1_transactions.error IS 'Error returned by the exchange if the transaction failed';
@@ -0,0 +1,43 @@
+-- L1 actions from the replica_cmds files of the node data bucket: every order, cancel
+-- and modify item, plus TWAP, leverage, margin and transfer actions, with the outcome
+-- the exchange returned. Fills only show executed trades; these rows show what was
+-- placed, cancelled and for how long it rested.
+
+CREATE TABLE order_actions (
+    exchange_id INTEGER NOT NULL REFERENCES exchanges(id),
+    block_number BIGINT NOT NULL,
+    block_time TIMESTAMPTZ,
+    tx_index INTEGER NOT NULL,
+    item_index INTEGER NOT NULL,
+    user_address VARCHAR(66),
+    vault_address VARCHAR(66),
+    action_type TEXT NOT NULL,
+    asset INTEGER,
+    oid BIGINT,
+    cloid VARCHAR(66),
+    is_buy BOOLEAN,
+    price NUMERIC(20, 10),
+    size NUMERIC(20, 10),
+    reduce_only BOOLEAN,
+    order_type TEXT,
+    status TEXT,
+    error TEXT,
+    details JSONB,
+
+    PRIMARY KEY (exchange_id, block_number, tx_index, item_index)
+);
+
+CREATE INDEX idx_order_actions_user ON order_actions(user_address, block_time DESC) WHERE user_address IS NOT NULL;
+CREATE INDEX idx_order_actions_oid ON order_actions(oid) WHERE oid IS NOT NULL;
+CREATE INDEX idx_order_actions_type ON order_actions(action_type, block_number);
+CREATE INDEX idx_order_actions_block_time ON order_actions(block_time);
+
+COMMENT ON TABLE order_actions IS 'Order, cancel, modify and other L1 actions from the replica_cmds files';
+COMMENT ON COLUMN order_actions.tx_index IS 'Position of the signed action within its block';
+COMMENT ON COLUMN order_actions.item_index IS 'Position of the item within its action, e.g. the order within a batch';
+COMMENT ON COLUMN order_actions.user_address IS 'Address the exchange attributed the action to';
+COMMENT ON COLUMN order_actions.asset IS 'Asset index of the action: the perp universe index, or 10000 + the spot pair index';
+COMMENT ON COLUMN order_actions.oid IS 'Order id, from the response for new orders and from the action for cancels and modifies; TWAP id for TWAP actions';
+COMMENT ON COLUMN order_actions.order_type IS 'Time in force of a limit order (Gtc, Ioc, Alo), or Trigger';
+COMMENT ON COLUMN order_actions.status IS 'Outcome returned by the exchange, e.g. resting, filled, success, error';
+COMMENT ON COLUMN order_actions.details IS 'The action as sent, for action types without dedicated columns';
@@ -0,0 +1,18 @@
+-- Deduplicate fills rejected after parsing
+-- Quarantined fills, e.g. a numeric overflow, have no raw line, so idx_fill_rejects_line doesn't
+-- cover them and every reload of their hour recorded them again. Key them by hour file, parsed
+-- fill and reason instead, keeping the oldest of any copies already recorded.
+
+DELETE FROM fill_rejects a
+USING fill_rejects b
+WHERE a.raw_line IS NULL
+  AND b.raw_line IS NULL
+  AND a.exchange_id = b.exchange_id
+  AND COALESCE(a.s3_key, '') = COALESCE(b.s3_key, '')
+  AND md5(a.payload::text) = md5(b.payload::text)
+  AND md5(a.reason) = md5(b.reason)
+  AND a.id > b.id;
+
+CREATE UNIQUE INDEX idx_fill_rejects_payload
+    ON fill_rejects(exchange_id, COALESCE(s3_key, ''), md5(payload::text), md5(reason))
+    WHERE raw_line IS NULL;